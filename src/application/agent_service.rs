@@ -72,13 +72,16 @@ impl AgentService {
             ai_guard.generate_embedding(&req.message).await?
         };
 
-        let context_docs = self.kg_repo.find_hybrid_context(embedding, 3).await?;
+        let context_docs = self.kg_repo.find_hybrid_context(embedding, 3, &req.filter).await?;
         let mut context_str = String::new();
         if !context_docs.is_empty() {
             info!("✅ [RAG] {} fragmentos encontrados.", context_docs.len());
             context_str.push_str("\n\n### CONTEXTO (BASE DE DATOS):\n");
-            for (_i, doc) in context_docs.iter().enumerate() {
-                context_str.push_str(&format!("- \"{}\"\n", doc.content.trim()));
+            for doc in context_docs.iter() {
+                match doc.citation() {
+                    Some(citation) => context_str.push_str(&format!("- \"{}\" ({})\n", doc.content.trim(), citation)),
+                    None => context_str.push_str(&format!("- \"{}\"\n", doc.content.trim())),
+                }
             }
             context_str.push_str("---\n");
         } else {
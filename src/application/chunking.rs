@@ -0,0 +1,574 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+use serde::Deserialize;
+use tiktoken_rs::CoreBPE;
+use crate::domain::{models::ChunkingConfig, errors::AppError};
+
+/// Fragmento producido por una estrategia. `start..end` es el rango de bytes del texto
+/// original que cubre (el texto puede diferir: separadores normalizados, ruta de encabezados...).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextChunk {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Estrategia de troceado de texto previa a embeddings y extracción
+pub trait ChunkingStrategy: Send + Sync {
+    fn split(&self, text: &str) -> Vec<TextChunk>;
+}
+
+/// Construye la estrategia concreta a partir de su configuración
+pub fn strategy_for(config: &ChunkingConfig) -> Box<dyn ChunkingStrategy> {
+    match *config {
+        ChunkingConfig::FixedWindow { size, overlap } => Box::new(FixedWindowChunker::new(size, overlap)),
+        ChunkingConfig::Sentence { max_chars } => Box::new(SentenceChunker { max_chars: max_chars.max(1) }),
+        ChunkingConfig::Paragraph { max_chars } => Box::new(ParagraphChunker { max_chars: max_chars.max(1) }),
+        ChunkingConfig::Markdown { max_chars } => Box::new(MarkdownChunker { max_chars: max_chars.max(1) }),
+        ChunkingConfig::Tokens { max_tokens, overlap_tokens } => match tokenizer() {
+            Some(bpe) => Box::new(TokenChunker::new(bpe, max_tokens, overlap_tokens)),
+            // Sin tokenizador: aproximación de ~4 caracteres por token
+            None => Box::new(FixedWindowChunker::new(max_tokens * 4, overlap_tokens * 4)),
+        },
+    }
+}
+
+fn tokenizer() -> Option<&'static CoreBPE> {
+    static CL100K: OnceLock<Option<CoreBPE>> = OnceLock::new();
+    CL100K.get_or_init(|| tiktoken_rs::cl100k_base().ok()).as_ref()
+}
+
+// =========================================================
+// POLÍTICA POR FORMATO (config/chunking.yaml)
+// =========================================================
+
+/// Estrategia por defecto, overrides por extensión de archivo y la de las transcripciones
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ChunkingPolicy {
+    #[serde(default)]
+    pub default: ChunkingConfig,
+    #[serde(default)]
+    pub formats: HashMap<String, ChunkingConfig>,
+    /// Audio y vídeo transcritos, sea cual sea su extensión
+    #[serde(default = "default_transcript_chunking")]
+    pub transcript: ChunkingConfig,
+}
+
+fn default_transcript_chunking() -> ChunkingConfig {
+    ChunkingConfig::Paragraph { max_chars: 1500 }
+}
+
+/// Estrategias que nunca parten un párrafo que cabe entero (un segmento de transcripción)
+fn keeps_paragraphs(config: &ChunkingConfig) -> bool {
+    matches!(config, ChunkingConfig::Paragraph { .. } | ChunkingConfig::Markdown { .. })
+}
+
+impl ChunkingPolicy {
+    pub fn from_yaml_file(path: &str) -> Result<Self, AppError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|_| AppError::ConfigError(format!("Chunking policy not found: {}", path)))?;
+        serde_yaml::from_str(&content)
+            .map_err(|e| AppError::ParseError(format!("YAML Error in {}: {}", path, e)))
+    }
+
+    pub fn for_filename(&self, filename: &str) -> ChunkingConfig {
+        Path::new(filename)
+            .extension()
+            .and_then(|s| s.to_str())
+            .and_then(|ext| self.formats.get(&ext.to_lowercase()))
+            .cloned()
+            .unwrap_or_else(|| self.default.clone())
+    }
+
+    /// Estrategia para una transcripción: cada segmento es un párrafo, así que solo se admiten
+    /// estrategias por párrafos (la pedida si lo es, si no la configurada para transcripciones)
+    pub fn for_transcript(&self, requested: Option<&ChunkingConfig>) -> ChunkingConfig {
+        requested.filter(|config| keeps_paragraphs(config))
+            .or(Some(&self.transcript).filter(|config| keeps_paragraphs(config)))
+            .cloned()
+            .unwrap_or_else(default_transcript_chunking)
+    }
+}
+
+// =========================================================
+// IMPLEMENTACIONES
+// =========================================================
+
+/// Ventana deslizante de caracteres que evita cortar palabras
+pub struct FixedWindowChunker {
+    size: usize,
+    overlap: usize,
+}
+
+impl FixedWindowChunker {
+    pub fn new(size: usize, overlap: usize) -> Self {
+        let size = size.max(1);
+        Self { size, overlap: overlap.min(size - 1) }
+    }
+}
+
+impl ChunkingStrategy for FixedWindowChunker {
+    fn split(&self, text: &str) -> Vec<TextChunk> {
+        let mut chunks = Vec::new();
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let byte_at = |i: usize| chars.get(i).map_or(text.len(), |(b, _)| *b);
+        let mut start = 0;
+
+        while start < chars.len() {
+            let end = std::cmp::min(start + self.size, chars.len());
+
+            // Ajuste para no cortar palabras (buscar espacio hacia atrás)
+            let mut actual_end = end;
+            if actual_end < chars.len() {
+                while actual_end > start && !chars[actual_end].1.is_whitespace() {
+                    actual_end -= 1;
+                }
+            }
+            if actual_end == start { actual_end = end; } // Fallback si la palabra es gigante
+
+            let (byte_start, byte_end) = (byte_at(start), byte_at(actual_end));
+            let chunk_str = &text[byte_start..byte_end];
+            if !chunk_str.trim().is_empty() {
+                chunks.push(TextChunk { text: chunk_str.to_string(), start: byte_start, end: byte_end });
+            }
+
+            // El último trozo ya llega al final: el solapamiento solo generaría un duplicado
+            if actual_end == chars.len() { break; }
+
+            // Avanzar restando el overlap para mantener contexto
+            start += std::cmp::max(1, (actual_end - start).saturating_sub(self.overlap));
+        }
+        chunks
+    }
+}
+
+/// Agrupa frases completas
+pub struct SentenceChunker {
+    max_chars: usize,
+}
+
+impl ChunkingStrategy for SentenceChunker {
+    fn split(&self, text: &str) -> Vec<TextChunk> {
+        let fallback = FixedWindowChunker::new(self.max_chars, 0);
+        pack_units(text, split_sentences(text), self.max_chars, " ", &|unit| fallback.split(unit))
+    }
+}
+
+/// Agrupa párrafos; un párrafo demasiado largo se trocea por líneas (filas de tablas) y luego por frases
+pub struct ParagraphChunker {
+    max_chars: usize,
+}
+
+impl ChunkingStrategy for ParagraphChunker {
+    fn split(&self, text: &str) -> Vec<TextChunk> {
+        let sentences = SentenceChunker { max_chars: self.max_chars };
+        let by_lines = |paragraph: &str| {
+            pack_units(paragraph, split_lines(paragraph), self.max_chars, "\n", &|line| sentences.split(line))
+        };
+        pack_units(text, split_paragraphs(text), self.max_chars, "\n\n", &by_lines)
+    }
+}
+
+/// Una sección por encabezado Markdown. Cada chunk lleva delante la ruta de
+/// encabezados que lo contiene, para no perder el contexto al trocear secciones largas.
+pub struct MarkdownChunker {
+    max_chars: usize,
+}
+
+impl ChunkingStrategy for MarkdownChunker {
+    fn split(&self, text: &str) -> Vec<TextChunk> {
+        let mut chunks = Vec::new();
+        for (breadcrumb, body) in split_markdown_sections(text) {
+            let budget = self.max_chars.saturating_sub(breadcrumb.chars().count() + 1).max(self.max_chars / 4).max(1);
+            for part in (ParagraphChunker { max_chars: budget }).split(&text[body.start..body.end]) {
+                let part = shift(part, body.start);
+                if breadcrumb.is_empty() {
+                    chunks.push(part);
+                } else {
+                    chunks.push(TextChunk { text: format!("{}\n{}", breadcrumb, part.text), ..part });
+                }
+            }
+        }
+        chunks
+    }
+}
+
+/// Ventana de tokens con solapamiento, sin cortar palabras
+pub struct TokenChunker {
+    bpe: &'static CoreBPE,
+    max_tokens: usize,
+    overlap_tokens: usize,
+}
+
+impl TokenChunker {
+    pub fn new(bpe: &'static CoreBPE, max_tokens: usize, overlap_tokens: usize) -> Self {
+        let max_tokens = max_tokens.max(1);
+        Self { bpe, max_tokens, overlap_tokens: overlap_tokens.min(max_tokens - 1) }
+    }
+}
+
+impl ChunkingStrategy for TokenChunker {
+    fn split(&self, text: &str) -> Vec<TextChunk> {
+        let mut chunks = Vec::new();
+        // (inicio, fin, tokens) de cada palabra en la ventana actual
+        let mut window: Vec<(usize, usize, usize)> = Vec::new();
+        let mut window_tokens = 0;
+        let mut offset = 0;
+
+        let flush = |window: &[(usize, usize, usize)], chunks: &mut Vec<TextChunk>| {
+            if let (Some(first), Some(last)) = (window.first(), window.last()) {
+                chunks.extend(trimmed_unit(text, first.0, last.1));
+            }
+        };
+
+        for word in text.split_inclusive(char::is_whitespace) {
+            let (start, end) = (offset, offset + word.len());
+            offset = end;
+            let tokens = self.bpe.encode_ordinary(word).len();
+
+            if !window.is_empty() && window_tokens + tokens > self.max_tokens {
+                flush(&window, &mut chunks);
+
+                // Conservar la cola como solapamiento, dejando sitio para la palabra actual
+                let mut kept = 0;
+                let mut keep_from = window.len();
+                while keep_from > 0 {
+                    let candidate = window[keep_from - 1].2;
+                    if kept + candidate > self.overlap_tokens || kept + candidate + tokens > self.max_tokens { break; }
+                    kept += candidate;
+                    keep_from -= 1;
+                }
+                window.drain(..keep_from);
+                window_tokens = kept;
+            }
+
+            window.push((start, end, tokens));
+            window_tokens += tokens;
+        }
+        flush(&window, &mut chunks);
+        chunks
+    }
+}
+
+// =========================================================
+// UTILIDADES
+// =========================================================
+
+/// Desplaza un chunk calculado sobre un fragmento para que sus offsets apunten al texto completo
+fn shift(chunk: TextChunk, base: usize) -> TextChunk {
+    TextChunk { start: chunk.start + base, end: chunk.end + base, ..chunk }
+}
+
+/// Unidad recortada de espacios en `text[start..end]`, o `None` si queda vacía
+fn trimmed_unit(text: &str, start: usize, end: usize) -> Option<TextChunk> {
+    let slice = &text[start..end];
+    let trimmed = slice.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let unit_start = start + (slice.len() - slice.trim_start().len());
+    Some(TextChunk { text: trimmed.to_string(), start: unit_start, end: unit_start + trimmed.len() })
+}
+
+/// Agrupa unidades consecutivas hasta `max_chars`; las que no caben solas se delegan a `oversized`
+fn pack_units(
+    text: &str,
+    units: Vec<TextChunk>,
+    max_chars: usize,
+    joiner: &str,
+    oversized: &dyn Fn(&str) -> Vec<TextChunk>,
+) -> Vec<TextChunk> {
+    let mut chunks = Vec::new();
+    let mut current: Option<TextChunk> = None;
+    let mut current_len = 0;
+    let joiner_len = joiner.chars().count();
+
+    for unit in units {
+        let unit_len = unit.text.chars().count();
+        if unit_len > max_chars {
+            chunks.extend(current.take());
+            current_len = 0;
+            chunks.extend(oversized(&text[unit.start..unit.end]).into_iter().map(|c| shift(c, unit.start)));
+            continue;
+        }
+        match current.as_mut() {
+            Some(chunk) if current_len + joiner_len + unit_len <= max_chars => {
+                chunk.text.push_str(joiner);
+                chunk.text.push_str(&unit.text);
+                chunk.end = unit.end;
+                current_len += joiner_len + unit_len;
+            },
+            _ => {
+                chunks.extend(current.take());
+                current_len = unit_len;
+                current = Some(unit);
+            },
+        }
+    }
+    chunks.extend(current);
+    chunks
+}
+
+fn split_sentences(text: &str) -> Vec<TextChunk> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|(_, n)| *n);
+        let end_of_sentence = matches!(c, '.' | '!' | '?' | '…') && next.is_none_or(char::is_whitespace);
+        let blank_line = c == '\n' && next == Some('\n');
+        if end_of_sentence || blank_line {
+            let end = i + c.len_utf8();
+            sentences.extend(trimmed_unit(text, start, end));
+            start = end;
+        }
+    }
+    sentences.extend(trimmed_unit(text, start, text.len()));
+    sentences
+}
+
+fn split_lines(text: &str) -> Vec<TextChunk> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        lines.extend(trimmed_unit(text, offset, offset + line.len()));
+        offset += line.len();
+    }
+    lines
+}
+
+fn split_paragraphs(text: &str) -> Vec<TextChunk> {
+    let mut paragraphs = Vec::new();
+    let mut paragraph_start: Option<usize> = None;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            if let Some(start) = paragraph_start.take() {
+                paragraphs.extend(trimmed_unit(text, start, offset));
+            }
+        } else if paragraph_start.is_none() {
+            paragraph_start = Some(offset);
+        }
+        offset += line.len();
+    }
+    if let Some(start) = paragraph_start {
+        paragraphs.extend(trimmed_unit(text, start, text.len()));
+    }
+    paragraphs
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    let rest = &trimmed[level..];
+    ((1..=6).contains(&level) && rest.starts_with(' ')).then_some(level)
+}
+
+/// Devuelve (ruta de encabezados, rango del cuerpo) por sección, ignorando `#` dentro de bloques de código
+fn split_markdown_sections(text: &str) -> Vec<(String, TextChunk)> {
+    let mut sections = Vec::new();
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    let mut body_start = 0;
+    let mut offset = 0;
+    let mut in_code = false;
+
+    let breadcrumb = |stack: &[(usize, String)]| stack.iter().map(|(_, h)| h.as_str()).collect::<Vec<_>>().join("\n");
+
+    for line in text.split_inclusive('\n') {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        if let Some(level) = heading_level(line).filter(|_| !in_code) {
+            if let Some(body) = trimmed_unit(text, body_start, offset) {
+                sections.push((breadcrumb(&stack), body));
+            }
+            while stack.last().is_some_and(|(l, _)| *l >= level) {
+                stack.pop();
+            }
+            stack.push((level, line.trim().to_string()));
+            body_start = offset + line.len();
+        }
+        offset += line.len();
+    }
+    if let Some(body) = trimmed_unit(text, body_start, text.len()) {
+        sections.push((breadcrumb(&stack), body));
+    }
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(chunks: &[TextChunk]) -> Vec<&str> {
+        chunks.iter().map(|c| c.text.as_str()).collect()
+    }
+
+    /// Los offsets caen en fronteras de carácter y cubren las mismas palabras que el chunk
+    fn assert_offsets(text: &str, chunks: &[TextChunk]) {
+        for chunk in chunks {
+            let covered = &text[chunk.start..chunk.end];
+            assert_eq!(
+                covered.split_whitespace().collect::<Vec<_>>(),
+                chunk.text.split_whitespace().collect::<Vec<_>>(),
+                "{:?}", chunk
+            );
+        }
+    }
+
+    #[test]
+    fn every_strategy_returns_nothing_for_empty_input() {
+        let configs = [
+            ChunkingConfig::FixedWindow { size: 10, overlap: 2 },
+            ChunkingConfig::Sentence { max_chars: 10 },
+            ChunkingConfig::Paragraph { max_chars: 10 },
+            ChunkingConfig::Markdown { max_chars: 10 },
+            ChunkingConfig::Tokens { max_tokens: 10, overlap_tokens: 2 },
+        ];
+        for config in &configs {
+            let strategy = strategy_for(config);
+            assert!(strategy.split("").is_empty(), "{:?}", config);
+            assert!(strategy.split(" \n\n \t").is_empty(), "{:?}", config);
+        }
+    }
+
+    #[test]
+    fn fixed_window_keeps_words_overlaps_and_counts_characters() {
+        // 6 caracteres y 8 bytes por palabra
+        let text = "ñandú ".repeat(20);
+        let chunks = FixedWindowChunker::new(20, 6).split(&text);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert_eq!(chunk.text, &text[chunk.start..chunk.end]);
+            assert!(chunk.text.chars().count() <= 20);
+            assert!(chunk.text.split_whitespace().all(|word| word == "ñandú"), "{:?}", chunk);
+        }
+        for pair in chunks.windows(2) {
+            assert!(pair[1].start < pair[0].end, "sin solapamiento: {:?}", pair);
+            assert!(pair[1].start > pair[0].start);
+        }
+        assert_eq!(chunks[0].start, 0);
+        assert_eq!(chunks.last().unwrap().end, text.len());
+
+        // Una palabra más larga que la ventana se corta en lugar de quedarse atascada
+        let chunks = FixedWindowChunker::new(4, 0).split("electroencefalograma");
+        assert_eq!(texts(&chunks), vec!["elec", "troe", "ncef", "alog", "rama"]);
+    }
+
+    #[test]
+    fn sentence_chunker_packs_whole_sentences() {
+        let text = "Hola mundo. ¿Qué tal? Bien… Adiós.";
+        let chunks = SentenceChunker { max_chars: 25 }.split(text);
+        assert_eq!(texts(&chunks), vec!["Hola mundo. ¿Qué tal?", "Bien… Adiós."]);
+        for chunk in &chunks {
+            assert_eq!(chunk.text, &text[chunk.start..chunk.end]);
+        }
+
+        // Los decimales y las abreviaturas sin espacio detrás no cortan la frase
+        let chunks = SentenceChunker { max_chars: 100 }.split("Dosis de 2.5 mg. Revisión.");
+        assert_eq!(texts(&chunks), vec!["Dosis de 2.5 mg. Revisión."]);
+
+        // Una frase que no cabe pasa a ventana fija, con offsets sobre el texto completo
+        let text = "Corta. Una frase bastante más larga que el máximo permitido.";
+        let chunks = SentenceChunker { max_chars: 20 }.split(text);
+        assert_eq!(chunks[0].text, "Corta.");
+        assert!(chunks[1..].iter().all(|c| c.text.chars().count() <= 20));
+        assert_offsets(text, &chunks);
+        assert_eq!(chunks.last().unwrap().end, text.len());
+    }
+
+    #[test]
+    fn paragraph_chunker_splits_on_blank_lines_then_rows() {
+        let text = "Uno.\n\nDos dos.\n\n\nTres.";
+        let chunks = ParagraphChunker { max_chars: 20 }.split(text);
+        assert_eq!(texts(&chunks), vec!["Uno.\n\nDos dos.", "Tres."]);
+        assert_eq!(&text[chunks[1].start..chunks[1].end], "Tres.");
+        assert_offsets(text, &chunks);
+
+        // Una tabla que no cabe se trocea por filas completas
+        let table = "a | b\nc | d\ne | f";
+        let chunks = ParagraphChunker { max_chars: 11 }.split(table);
+        assert_eq!(texts(&chunks), vec!["a | b\nc | d", "e | f"]);
+        assert_eq!((chunks[1].start, chunks[1].end), (12, 17));
+    }
+
+    #[test]
+    fn markdown_chunker_prefixes_the_heading_path() {
+        let text = "Preámbulo.\n# Título\nIntro.\n## Sección\nTexto de la sección.\n```\n# no es título\n```\n# Otro\nFin.";
+        let chunks = MarkdownChunker { max_chars: 200 }.split(text);
+        assert_eq!(texts(&chunks), vec![
+            "Preámbulo.",
+            "# Título\nIntro.",
+            "# Título\n## Sección\nTexto de la sección.\n```\n# no es título\n```",
+            "# Otro\nFin.",
+        ]);
+        // Los offsets apuntan al cuerpo, sin la ruta de encabezados
+        assert_eq!(&text[chunks[1].start..chunks[1].end], "Intro.");
+        assert_eq!(&text[chunks[3].start..chunks[3].end], "Fin.");
+
+        let sections = split_markdown_sections(text);
+        assert_eq!(sections.len(), 4);
+        assert_eq!(sections[2].0, "# Título\n## Sección");
+    }
+
+    #[test]
+    fn token_chunker_respects_the_budget_and_overlaps() {
+        let bpe = tokenizer().expect("cl100k_base");
+        let text = "canción de cuna para dormir ".repeat(10);
+        let chunks = TokenChunker::new(bpe, 12, 4).split(&text);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(bpe.encode_ordinary(&chunk.text).len() <= 12, "{:?}", chunk);
+            assert_eq!(chunk.text, &text[chunk.start..chunk.end]);
+        }
+        for pair in chunks.windows(2) {
+            assert!(pair[1].start < pair[0].end, "sin solapamiento: {:?}", pair);
+        }
+        assert_eq!(chunks[0].start, 0);
+        assert_eq!(chunks.last().unwrap().end, text.trim_end().len());
+    }
+
+    #[test]
+    fn pack_units_joins_until_full_and_shifts_oversized_units() {
+        let text = "aa bb cccccccc dd";
+        let units = vec![
+            TextChunk { text: "aa".into(), start: 0, end: 2 },
+            TextChunk { text: "bb".into(), start: 3, end: 5 },
+            TextChunk { text: "cccccccc".into(), start: 6, end: 14 },
+            TextChunk { text: "dd".into(), start: 15, end: 17 },
+        ];
+        let halves = |unit: &str| vec![
+            TextChunk { text: unit[..4].to_string(), start: 0, end: 4 },
+            TextChunk { text: unit[4..].to_string(), start: 4, end: unit.len() },
+        ];
+        let chunks = pack_units(text, units, 5, " ", &halves);
+        assert_eq!(texts(&chunks), vec!["aa bb", "cccc", "cccc", "dd"]);
+        let offsets: Vec<(usize, usize)> = chunks.iter().map(|c| (c.start, c.end)).collect();
+        assert_eq!(offsets, vec![(0, 5), (6, 10), (10, 14), (15, 17)]);
+    }
+
+    #[test]
+    fn policy_picks_the_strategy_by_extension() {
+        let policy: ChunkingPolicy = serde_yaml::from_str(
+            "default:\n  strategy: fixed_window\n  size: 100\n  overlap: 10\nformats:\n  md:\n    strategy: markdown\n    max_chars: 50\n",
+        ).unwrap();
+        assert_eq!(policy.for_filename("notas.md"), ChunkingConfig::Markdown { max_chars: 50 });
+        assert_eq!(policy.for_filename("NOTAS.MD"), ChunkingConfig::Markdown { max_chars: 50 });
+        assert_eq!(policy.for_filename("informe.pdf"), ChunkingConfig::FixedWindow { size: 100, overlap: 10 });
+        assert_eq!(policy.for_filename("sin_extension"), ChunkingConfig::FixedWindow { size: 100, overlap: 10 });
+
+        // Las transcripciones siempre van por párrafos (segmentos), aunque se pida otra cosa
+        assert_eq!(policy.for_transcript(None), ChunkingConfig::Paragraph { max_chars: 1500 });
+        assert_eq!(policy.for_transcript(Some(&ChunkingConfig::FixedWindow { size: 10, overlap: 0 })), ChunkingConfig::Paragraph { max_chars: 1500 });
+        assert_eq!(policy.for_transcript(Some(&ChunkingConfig::Paragraph { max_chars: 800 })), ChunkingConfig::Paragraph { max_chars: 800 });
+
+        // La política del repositorio se carga sin errores
+        let shipped = ChunkingPolicy::from_yaml_file("config/chunking.yaml").unwrap();
+        assert_eq!(shipped.default, ChunkingConfig::default());
+        assert!(keeps_paragraphs(&shipped.transcript));
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::warn;
+use crate::domain::{
+    ports::{KGRepository, AIService},
+    models::{EntityAlias, GraphEntity, GraphRelation, KnowledgeExtraction, entity_key},
+    errors::AppError
+};
+
+/// Umbrales de la resolución de entidades contra las ya existentes en el grafo
+#[derive(Debug, Clone)]
+pub struct ResolutionConfig {
+    /// Similitud coseno mínima entre embeddings de nombres de la misma categoría
+    pub similarity_threshold: f32,
+    /// Similitud mínima (Levenshtein normalizado sobre la clave) para aceptar un candidato cercano
+    pub fuzzy_threshold: f64,
+    /// Candidatos del índice vectorial que se comparan por cada entidad nueva
+    pub candidates: usize,
+    /// Categorías que solo se identifican por nombre exacto o alias: dos pacientes con nombres
+    /// casi iguales son personas distintas
+    pub exact_categories: Vec<String>,
+}
+
+impl Default for ResolutionConfig {
+    fn default() -> Self {
+        Self { similarity_threshold: 0.92, fuzzy_threshold: 0.9, candidates: 5, exact_categories: vec!["Person".to_string()] }
+    }
+}
+
+impl ResolutionConfig {
+    /// `ENTITY_SIMILARITY_THRESHOLD` y `ENTITY_FUZZY_THRESHOLD`, entre 0 y 1, y
+    /// `ENTITY_EXACT_CATEGORIES` separadas por comas (vacío = ninguna)
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let read = |key: &str| std::env::var(key).ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| (0.0..=1.0).contains(v));
+        Self {
+            similarity_threshold: read("ENTITY_SIMILARITY_THRESHOLD").map(|v| v as f32).unwrap_or(defaults.similarity_threshold),
+            fuzzy_threshold: read("ENTITY_FUZZY_THRESHOLD").unwrap_or(defaults.fuzzy_threshold),
+            candidates: defaults.candidates,
+            exact_categories: std::env::var("ENTITY_EXACT_CATEGORIES").ok()
+                .map(|v| v.split(',').map(str::trim).filter(|c| !c.is_empty()).map(str::to_string).collect())
+                .unwrap_or(defaults.exact_categories),
+        }
+    }
+
+    fn is_exact(&self, category: &str) -> bool {
+        self.exact_categories.iter().any(|c| c.eq_ignore_ascii_case(category))
+    }
+}
+
+/// Extracción con los nombres ya canónicos y lo que hay que registrar junto a ella
+pub struct Resolution {
+    pub extraction: KnowledgeExtraction,
+    /// Nombres identificados con una entidad existente (se enlazan con `ALIAS_OF`)
+    pub aliases: Vec<EntityAlias>,
+    /// Embeddings de los nombres de las entidades nuevas, para resolver las siguientes
+    pub embeddings: Vec<(String, Vec<f32>)>,
+    /// Parecidos que no se fusionan solos (categorías exactas o datos estructurados): quedan
+    /// como entidades nuevas y se avisan para fusionarlas a mano si procede
+    pub suggestions: Vec<EntityAlias>,
+}
+
+/// Identifica las entidades extraídas con las del grafo antes del MERGE:
+/// 1. misma clave (mayúsculas, tildes y signos aparte) o alias registrado;
+/// 2. nombre de la misma categoría con embedding muy similar o escritura casi idéntica,
+///    salvo en las categorías exactas y en los datos estructurados, donde solo se sugiere.
+pub struct EntityResolver {
+    repo: Arc<dyn KGRepository>,
+    ai: Arc<RwLock<dyn AIService>>,
+    config: ResolutionConfig,
+}
+
+impl EntityResolver {
+    pub fn new(repo: Arc<dyn KGRepository>, ai: Arc<RwLock<dyn AIService>>, config: ResolutionConfig) -> Self {
+        Self { repo, ai, config }
+    }
+
+    /// `allow_similar = false` desactiva el paso 2 para todas las categorías
+    pub async fn resolve(&self, extraction: KnowledgeExtraction, allow_similar: bool) -> Result<Resolution, AppError> {
+        // Nombre tal como lo escribió el LLM -> nombre canónico
+        let mut renamed: HashMap<String, String> = HashMap::new();
+
+        // 1. Variantes dentro del mismo fragmento ("Ansiedad" y "ansiedad"): gana la primera
+        let mut entities: Vec<GraphEntity> = Vec::new();
+        let mut seen_keys: HashMap<String, usize> = HashMap::new();
+        for entity in extraction.entities {
+            let key = entity_key(&entity.name);
+            if key.is_empty() {
+                continue;
+            }
+            match seen_keys.get(&key) {
+                Some(&first) => {
+                    let first = &mut entities[first];
+                    first.merge_attributes(entity.attributes);
+                    renamed.insert(entity.name, first.name.clone());
+                },
+                None => {
+                    seen_keys.insert(key, entities.len());
+                    entities.push(entity);
+                },
+            }
+        }
+
+        // 2. Entidades existentes con la misma clave o con un alias registrado
+        let keys: Vec<String> = seen_keys.into_keys().collect();
+        let known = self.repo.find_entities_by_keys(&keys).await?;
+        let mut unresolved = Vec::new();
+        for (index, entity) in entities.iter_mut().enumerate() {
+            match known.get(&entity_key(&entity.name)) {
+                Some(canonical) if *canonical != entity.name => {
+                    renamed.insert(entity.name.clone(), canonical.clone());
+                    entity.name = canonical.clone();
+                },
+                Some(_) => {},
+                None => unresolved.push(index),
+            }
+        }
+
+        // 3. Entidades nuevas: candidatos cercanos de la misma categoría en el índice vectorial
+        let mut aliases = Vec::new();
+        let mut embeddings = Vec::new();
+        let mut suggestions = Vec::new();
+        if !unresolved.is_empty() {
+            let names: Vec<&str> = unresolved.iter().map(|&i| entities[i].name.as_str()).collect();
+            let batch = self.ai.read().await.generate_embeddings(&names).await;
+            match batch {
+                Ok(vectors) => {
+                    for (&index, embedding) in unresolved.iter().zip(vectors) {
+                        let entity = &mut entities[index];
+                        let candidates = self.repo.find_similar_entities(embedding.clone(), &entity.category, self.config.candidates).await?;
+                        match best_match(&self.config, &entity.name, &candidates) {
+                            Some((canonical, method)) if allow_similar && !self.config.is_exact(&entity.category) => {
+                                aliases.push(EntityAlias { alias: entity.name.clone(), canonical: canonical.clone(), method: method.to_string() });
+                                renamed.insert(entity.name.clone(), canonical.clone());
+                                entity.name = canonical;
+                            },
+                            Some((canonical, method)) => {
+                                suggestions.push(EntityAlias { alias: entity.name.clone(), canonical, method: method.to_string() });
+                                embeddings.push((entity.name.clone(), embedding));
+                            },
+                            None => embeddings.push((entity.name.clone(), embedding)),
+                        }
+                    }
+                },
+                // Sin embeddings solo se pierde la comparación por similitud
+                Err(e) => warn!("⚠️ Resolución de entidades sin embeddings: {}", e),
+            }
+        }
+
+        // Dos nombres pueden haber acabado en la misma entidad canónica
+        let mut unique: Vec<GraphEntity> = Vec::with_capacity(entities.len());
+        for entity in entities {
+            match unique.iter_mut().find(|kept| kept.name == entity.name) {
+                Some(kept) => kept.merge_attributes(entity.attributes),
+                None => unique.push(entity),
+            }
+        }
+        let entities = unique;
+
+        let canonical = |name: &String| renamed.get(name).cloned().unwrap_or_else(|| name.clone());
+        let mut seen_relations = HashSet::new();
+        let relations = extraction.relations.into_iter()
+            .map(|r| GraphRelation { source: canonical(&r.source), target: canonical(&r.target), ..r })
+            .filter(|r| r.source != r.target)
+            .filter(|r| seen_relations.insert((r.source.clone(), r.relation_type.clone(), r.target.clone())))
+            .collect();
+
+        Ok(Resolution { extraction: KnowledgeExtraction { entities, relations }, aliases, embeddings, suggestions })
+    }
+
+}
+
+/// Primer candidato (por similitud descendente) que supera el umbral de embeddings
+/// o, si ninguno lo hace, el de escritura casi idéntica
+fn best_match(config: &ResolutionConfig, name: &str, candidates: &[(String, f32)]) -> Option<(String, &'static str)> {
+    if let Some((candidate, _)) = candidates.iter().find(|(_, score)| *score >= config.similarity_threshold) {
+        return Some((candidate.clone(), "similitud"));
+    }
+    let key = entity_key(name);
+    candidates.iter()
+        .find(|(candidate, _)| strsim::normalized_levenshtein(&key, &entity_key(candidate)) >= config.fuzzy_threshold)
+        .map(|(candidate, _)| (candidate.clone(), "difusa"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entity_key_ignores_case_accents_and_punctuation() {
+        assert_eq!(entity_key("Juan  Pérez."), "juan perez");
+        assert_eq!(entity_key("ANSIEDAD"), entity_key("ansiedad"));
+        assert_eq!(entity_key("Taller de Música"), "taller de musica");
+        assert_eq!(entity_key("Club-Social"), "club social");
+        assert_eq!(entity_key(" ¡! "), "");
+    }
+
+    #[test]
+    fn best_match_prefers_similarity_then_spelling() {
+        let config = ResolutionConfig::default();
+        let candidates = vec![("Club Social".to_string(), 0.95), ("Taller de Arte".to_string(), 0.80)];
+        assert_eq!(best_match(&config, "Club social La Muralla", &candidates), Some(("Club Social".to_string(), "similitud")));
+
+        // Por debajo del umbral de embeddings solo vale una escritura casi idéntica
+        let candidates = vec![("Esquizofrenia".to_string(), 0.85)];
+        assert_eq!(best_match(&config, "Esquizofrenía", &candidates), Some(("Esquizofrenia".to_string(), "difusa")));
+        assert_eq!(best_match(&config, "Esquizofrenia paranoide", &candidates), None);
+        assert_eq!(best_match(&config, "Esquisofrenia", &candidates).map(|(_, method)| method), Some("difusa"));
+    }
+
+    #[test]
+    fn people_are_only_resolved_by_exact_name() {
+        let config = ResolutionConfig::default();
+        assert!(config.is_exact("Person"));
+        assert!(config.is_exact("person"));
+        assert!(!config.is_exact("Condition"));
+        let config = ResolutionConfig { exact_categories: Vec::new(), ..ResolutionConfig::default() };
+        assert!(!config.is_exact("Person"));
+    }
+}
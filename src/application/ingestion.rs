@@ -1,112 +1,507 @@
 use uuid::Uuid;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use futures::StreamExt;
+use crate::application::chunking::{strategy_for, TextChunk};
+use crate::application::tabular::extraction_for_chunk;
+use crate::application::ontology::Ontology;
+use crate::application::entity_resolution::{EntityResolver, ResolutionConfig};
 use crate::domain::{
     ports::{KGRepository, AIService},
-    // models::IngestionRequest, // Comentado para evitar warning
+    models::{SourceDocument, ChunkRecord, IngestionJob, JobStatus, IngestionPayload, ChunkLocation, PageSpan, TimeSpan, KnowledgeExtraction, ChunkingConfig, StructuredSpan, ChunkPreview},
     errors::AppError
 };
 
-// Reducir drásticamente para mejorar la precisión vectorial
-// 1500 caracteres ~= 300-400 tokens (Sweet spot para embeddings)
-const CHUNK_SIZE: usize = 1500; 
-const CHUNK_OVERLAP: usize = 200;
+/// Límites de la ingesta de un documento: fragmentos por petición de embeddings
+/// y extracciones por LLM simultáneas
+#[derive(Debug, Clone)]
+pub struct IngestionTuning {
+    pub embedding_batch_size: usize,
+    pub max_concurrent_extractions: usize,
+}
+
+impl Default for IngestionTuning {
+    fn default() -> Self {
+        Self { embedding_batch_size: 32, max_concurrent_extractions: 4 }
+    }
+}
+
+impl IngestionTuning {
+    /// `INGEST_EMBEDDING_BATCH_SIZE` e `INGEST_MAX_CONCURRENCY`; los valores ausentes o inválidos usan el defecto
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let read = |key: &str, default: usize| std::env::var(key).ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(default);
+        Self {
+            embedding_batch_size: read("INGEST_EMBEDDING_BATCH_SIZE", defaults.embedding_batch_size),
+            max_concurrent_extractions: read("INGEST_MAX_CONCURRENCY", defaults.max_concurrent_extractions),
+        }
+    }
+}
+
+/// Procedencia de las relaciones que no extrae el LLM (tabla con mapeo, vista previa revisada)
+const STRUCTURED_SOURCE: &str = "estructurado";
+
+/// Longitud máxima (caracteres) de una cita de evidencia
+const MAX_EVIDENCE_CHARS: usize = 400;
+
+/// Descarta las citas de evidencia que no aparecen en el fragmento (paráfrasis o invenciones
+/// del LLM) y recorta las largas
+fn ground_evidence(extraction: &mut KnowledgeExtraction, text: &str) {
+    let collapse = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+    let haystack = collapse(text).to_lowercase();
+    for relation in &mut extraction.relations {
+        relation.evidence = relation.evidence.take()
+            .map(|quote| collapse(&quote))
+            .filter(|quote| !quote.is_empty() && haystack.contains(&quote.to_lowercase()))
+            .map(|quote| quote.chars().take(MAX_EVIDENCE_CHARS).collect());
+    }
+}
+
+/// Fragmento nuevo ya guardado, pendiente de conectar al grafo
+struct SavedChunk {
+    index: usize,
+    id: Uuid,
+    char_start: usize,
+    char_end: usize,
+}
 
 pub struct IngestionService {
     repo: Arc<dyn KGRepository>,
     ai: Arc<RwLock<dyn AIService>>,
+    tuning: IngestionTuning,
+    ontology: Arc<Ontology>,
+    /// Prompt de extracción generado una vez a partir de la ontología
+    extraction_prompt: String,
+    resolver: EntityResolver,
 }
 
 impl IngestionService {
-    pub fn new(repo: Arc<dyn KGRepository>, ai: Arc<RwLock<dyn AIService>>) -> Self {
-        Self { repo, ai }
+    pub fn new(repo: Arc<dyn KGRepository>, ai: Arc<RwLock<dyn AIService>>, tuning: IngestionTuning, ontology: Arc<Ontology>, resolution: ResolutionConfig) -> Self {
+        let extraction_prompt = ontology.extraction_prompt();
+        let resolver = EntityResolver::new(repo.clone(), ai.clone(), resolution);
+        Self { repo, ai, tuning, ontology, extraction_prompt, resolver }
     }
 
-    /// Función auxiliar para dividir texto preservando palabras completas
-    // En split_text_into_chunks:
-    // Implementar lógica de ventana deslizante (sliding window)
-    fn split_text_into_chunks(&self, text: &str) -> Vec<String> {
-        let mut chunks = Vec::new();
-        let chars: Vec<char> = text.chars().collect();
-        let mut start = 0;
+    /// Resuelve las entidades contra las existentes (nombre, alias o similitud) y guarda
+    /// el grafo del fragmento con los nombres canónicos
+    async fn save_extraction(
+        &self,
+        chunk_id: Uuid,
+        extraction: KnowledgeExtraction,
+        extracted_by: &str,
+        step: usize,
+        total: usize,
+        progress_tx: &tokio::sync::mpsc::Sender<String>,
+    ) -> Result<(), AppError> {
+        // Lo estructurado llega con nombres exactos: un parecido no basta para fusionar
+        let resolution = self.resolver.resolve(extraction, extracted_by != STRUCTURED_SOURCE).await?;
+        if !resolution.aliases.is_empty() {
+            let details: Vec<String> = resolution.aliases.iter().take(5)
+                .map(|alias| format!("{} → {}", alias.alias, alias.canonical))
+                .collect();
+            let _ = progress_tx.send(format!("🔗 [{}/{}] {} entidades identificadas con otras ya existentes ({})", step, total, resolution.aliases.len(), details.join("; "))).await;
+        }
+        if !resolution.suggestions.is_empty() {
+            let details: Vec<String> = resolution.suggestions.iter().take(5)
+                .map(|alias| format!("{} ≈ {}", alias.alias, alias.canonical))
+                .collect();
+            let _ = progress_tx.send(format!("🧐 [{}/{}] {} posibles duplicados sin fusionar; revísalos en Curación ({})", step, total, resolution.suggestions.len(), details.join("; "))).await;
+        }
+        self.repo.save_graph(chunk_id, resolution.extraction, extracted_by).await?;
+        self.repo.save_entity_resolution(&resolution.aliases, &resolution.embeddings).await
+    }
 
-        while start < chars.len() {
-            let end = std::cmp::min(start + CHUNK_SIZE, chars.len());
-        
-            // Ajuste para no cortar palabras (buscar espacio hacia atrás)
-            let mut actual_end = end;
-            if actual_end < chars.len() {
-                while actual_end > start && !chars[actual_end].is_whitespace() {
-                    actual_end -= 1;
+    async fn mark_chunk_done(&self, job: &mut IngestionJob, index: usize) -> Result<(), AppError> {
+        job.completed_chunks = index + 1;
+        self.repo.update_ingestion_job(job).await
+    }
+
+    /// Embeddings de los fragmentos `indices` en una sola petición. Si el lote falla se reintenta
+    /// fragmento a fragmento, para que uno problemático no descarte a los demás (`None` = omitido).
+    /// El lock de IA se toma solo durante cada llamada: una reconfiguración no espera al documento entero.
+    async fn embed_batch(
+        &self,
+        chunks: &[TextChunk],
+        indices: &[usize],
+        progress_tx: &tokio::sync::mpsc::Sender<String>,
+    ) -> Vec<Option<Vec<f32>>> {
+        if indices.is_empty() {
+            return Vec::new();
+        }
+        let texts: Vec<&str> = indices.iter().map(|&i| chunks[i].text.as_str()).collect();
+        let batch = self.ai.read().await.generate_embeddings(&texts).await;
+        match batch {
+            Ok(embeddings) => embeddings.into_iter().map(Some).collect(),
+            Err(e) => {
+                let _ = progress_tx.send(format!("⚠️ Embeddings por lote fallaron ({}). Reintentando uno a uno...", e)).await;
+                let mut embeddings = Vec::with_capacity(texts.len());
+                for (text, &index) in texts.iter().zip(indices) {
+                    let single = self.ai.read().await.generate_embedding(text).await;
+                    match single {
+                        Ok(embedding) => embeddings.push(Some(embedding)),
+                        Err(e) => {
+                            let _ = progress_tx.send(format!("⚠️ Error embedding chunk {}: {}. Saltando...", index + 1, e)).await;
+                            embeddings.push(None);
+                        }
+                    }
                 }
+                embeddings
             }
-            if actual_end == start { actual_end = end; } // Fallback si la palabra es gigante
+        }
+    }
+
+    /// Extracción por LLM de un fragmento, ajustada a la ontología. La cabecera del documento
+    /// (p.ej. participantes de un correo) acompaña a cada fragmento.
+    async fn extract_chunk(
+        &self,
+        text: &str,
+        context: Option<&str>,
+        step: usize,
+        total: usize,
+        progress_tx: &tokio::sync::mpsc::Sender<String>,
+    ) -> Result<KnowledgeExtraction, AppError> {
+        let _ = progress_tx.send(format!("🕵️ [{}/{}] Extrayendo conocimiento...", step, total)).await;
+        let input = match context {
+            Some(context) => format!("{}\n\n{}", context, text),
+            None => text.to_string(),
+        };
+        let extraction = self.ai.read().await.extract_knowledge(&input, &self.extraction_prompt).await?;
 
-            let chunk_str: String = chars[start..actual_end].iter().collect();
-            chunks.push(chunk_str);
+        let (mut extraction, report) = self.ontology.conform(extraction);
+        ground_evidence(&mut extraction, text);
+        if !report.is_empty() {
+            let details: Vec<String> = report.remapped.iter().chain(&report.rejected).take(5).cloned().collect();
+            let _ = progress_tx.send(format!(
+                "🧭 [{}/{}] Ontología: {} ajustes, {} descartes ({})",
+                step, total, report.remapped.len(), report.rejected.len(), details.join("; ")
+            )).await;
+        }
+        Ok(extraction)
+    }
+
+    /// Hash SHA-256 (hex) del contenido, usado como huella del documento
+    pub fn content_hash(content: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(content))
+    }
 
-            // Avanzar restando el overlap para mantener contexto
-            start +=  std::cmp::max(1, (actual_end - start).saturating_sub(CHUNK_OVERLAP));
+    /// Traduce el rango de bytes de un chunk a offsets de caracteres y a las páginas
+    /// (o los tiempos de grabación) que abarca
+    fn locate_chunk(char_offsets: &[usize], pages: &[PageSpan], segments: &[TimeSpan], start: usize, end: usize) -> ChunkLocation {
+        let char_start = char_offsets.partition_point(|&b| b < start);
+        let char_end = char_offsets.partition_point(|&b| b < end);
+        let overlaps = |span_start: usize, span_end: usize| span_start < char_end.max(char_start + 1) && span_end > char_start;
+        let mut covered = pages.iter()
+            .filter(|p| overlaps(p.char_start, p.char_end))
+            .map(|p| p.page);
+        let page_start = covered.next();
+        let page_end = covered.next_back().or(page_start);
+        let mut heard = segments.iter().filter(|s| overlaps(s.char_start, s.char_end));
+        let first_segment = heard.next();
+        let last_segment = heard.next_back().or(first_segment);
+        ChunkLocation {
+            char_start,
+            char_end,
+            page_start,
+            page_end,
+            time_start: first_segment.map(|s| s.start_secs),
+            time_end: last_segment.map(|s| s.end_secs),
         }
-        chunks
     }
 
+    /// Modo vista previa: trocea y extrae igual que la ingesta, pero sin escribir nada.
+    /// Devuelve un tramo por fragmento, atado a su posición, con la extracción propuesta.
+    pub async fn propose(
+        &self,
+        payload: &IngestionPayload,
+        chunking: &ChunkingConfig,
+        progress_tx: &tokio::sync::mpsc::Sender<String>,
+    ) -> Result<Vec<StructuredSpan>, AppError> {
+        let chunks = strategy_for(chunking).split(&payload.content);
+        let char_offsets: Vec<usize> = payload.content.char_indices().map(|(b, _)| b).collect();
+        let total_chunks = chunks.len();
+        let _ = progress_tx.send(format!("🔪 Dividido en {} fragmentos.", total_chunks)).await;
+
+        let extractions: Vec<KnowledgeExtraction> = match &payload.structured {
+            Some(spans) => chunks.iter().enumerate()
+                .map(|(index, chunk)| {
+                    let location = Self::locate_chunk(&char_offsets, &[], &[], chunk.start, chunk.end);
+                    extraction_for_chunk(spans, index, location.char_start, location.char_end)
+                })
+                .collect(),
+            None => {
+                let results: Vec<Result<KnowledgeExtraction, AppError>> = futures::stream::iter(0..total_chunks)
+                    .map(|index| self.extract_chunk(&chunks[index].text, payload.context.as_deref(), index + 1, total_chunks, progress_tx))
+                    .buffered(self.tuning.max_concurrent_extractions.max(1))
+                    .collect()
+                    .await;
+                let mut extractions = Vec::with_capacity(results.len());
+                for (index, result) in results.into_iter().enumerate() {
+                    match result {
+                        Ok(extraction) => extractions.push(extraction),
+                        Err(e) => {
+                            // Igual que en la ingesta: el fragmento se guardaría sin entidades
+                            let _ = progress_tx.send(format!("⚠️ Error extrayendo entidades en parte {}: {}", index + 1, e)).await;
+                            extractions.push(KnowledgeExtraction::default());
+                        },
+                    }
+                }
+                extractions
+            },
+        };
+
+        Ok(chunks.iter().zip(extractions).enumerate()
+            .map(|(index, (chunk, extraction))| {
+                let location = Self::locate_chunk(&char_offsets, &[], &[], chunk.start, chunk.end);
+                StructuredSpan { char_start: location.char_start, char_end: location.char_end, extraction, chunk: Some(index) }
+            })
+            .collect())
+    }
+
+    /// Fragmentos de una vista previa guardada, con el grafo que se escribirá para cada uno
+    pub fn preview_chunks(payload: &IngestionPayload, chunking: &ChunkingConfig) -> Vec<ChunkPreview> {
+        let chunks = strategy_for(chunking).split(&payload.content);
+        let char_offsets: Vec<usize> = payload.content.char_indices().map(|(b, _)| b).collect();
+        let spans = payload.structured.as_deref().unwrap_or_default();
+        chunks.into_iter().enumerate()
+            .map(|(position, chunk)| {
+                let location = Self::locate_chunk(&char_offsets, &payload.pages, &payload.segments, chunk.start, chunk.end);
+                let extraction = extraction_for_chunk(spans, position, location.char_start, location.char_end);
+                ChunkPreview { position, text: chunk.text, location, extraction }
+            })
+            .collect()
+    }
+
+    /// Ejecuta el pipeline para un trabajo persistente. El avance se registra chunk a chunk
+    /// en `job`, de modo que un trabajo interrumpido retoma desde `job.completed_chunks`.
+    /// Si el trabajo se cancela entre chunks, `job.status` queda en `Cancelled`; una nueva versión
+    /// cancelada descarta lo que escribió y deja intacta la anterior.
     pub async fn ingest_with_progress(
         &self, 
-        content: String,
+        job: &mut IngestionJob,
+        payload: IngestionPayload,
         progress_tx: tokio::sync::mpsc::Sender<String>
     ) -> Result<Uuid, AppError> {
+        let origin = job.origin();
+
+        // 0. Documento del que este es una nueva versión: solo si se pidió sustituirlo
+        //    (mismo nombre y mismo autor). Al reanudar, el trabajo ya tiene su documento.
+        let previous = match &job.document_id {
+            Some(_) => None,
+            None if origin.replace => self.repo.find_document_version(&origin.filename, &origin.uploaded_by).await?,
+            None => None,
+        };
+
+        // Deduplicación: un archivo idéntico no se vuelve a procesar. Una nueva versión solo
+        // se compara con la que sustituye.
+        let duplicate = match (&job.document_id, origin.replace) {
+            (Some(_), _) => None,
+            (None, true) => previous.clone().filter(|doc| doc.content_hash == origin.content_hash),
+            (None, false) => self.repo.find_document_by_hash(&origin.content_hash).await?,
+        };
+        if let Some(mut existing) = duplicate {
+            let _ = progress_tx.send(format!("♻️ '{}' ya fue ingerido como '{}'. Se omite.", origin.filename, existing.filename)).await;
+            // Los metadatos de la nueva subida sí se aplican: se añaden a los del documento existente
+            let changed: Vec<String> = payload.metadata.iter()
+                .filter(|(key, value)| existing.metadata.get(*key) != Some(*value))
+                .map(|(key, _)| key.clone())
+                .collect();
+            if !changed.is_empty() {
+                existing.metadata.extend(payload.metadata);
+                self.repo.save_document(&existing).await?;
+                let _ = progress_tx.send(format!("🏷️ Metadatos actualizados en '{}': {}.", existing.filename, changed.join(", "))).await;
+            }
+            job.document_id = Some(existing.id.clone());
+            return Uuid::parse_str(&existing.id)
+                .map_err(|e| AppError::ParseError(format!("ID de documento inválido: {}", e)));
+        }
         
         // 1. Dividir el contenido en trozos (Chunks)
-        let chunks = self.split_text_into_chunks(&content);
+        let content = payload.content;
+        let chunks = strategy_for(&job.chunking).split(&content);
+        let char_offsets: Vec<usize> = content.char_indices().map(|(b, _)| b).collect();
         let total_chunks = chunks.len();
-        let doc_group_id = Uuid::new_v4(); // ID para agrupar (opcional en lógica futura)
+        let resume_from = job.completed_chunks.min(total_chunks);
+
+        // 2. Documento propio del trabajo (reanudación), nueva versión de uno existente o documento nuevo
+        let (document_id, stored_hash) = match (&job.document_id, &previous) {
+            (Some(own), _) => {
+                let id = Uuid::parse_str(own)
+                    .map_err(|e| AppError::ParseError(format!("ID de documento inválido: {}", e)))?;
+                let stored_hash = self.repo.get_document(own).await?
+                    .map(|detail| detail.document.content_hash)
+                    .unwrap_or_default();
+                (id, stored_hash)
+            },
+            (None, Some(doc)) => {
+                let id = Uuid::parse_str(&doc.id)
+                    .map_err(|e| AppError::ParseError(format!("ID de documento inválido: {}", e)))?;
+                let _ = progress_tx.send(format!("🔄 Nueva versión de '{}'. Solo se procesarán los fragmentos modificados.", origin.filename)).await;
+                (id, doc.content_hash.clone())
+            },
+            (None, None) => (Uuid::new_v4(), String::new()),
+        };
+        // Fragmentos ya guardados en ese documento: los de la versión anterior o los escritos
+        // antes de la interrupción
+        let mut known_chunks: HashMap<String, Vec<String>> = HashMap::new();
+        if job.document_id.is_some() || previous.is_some() {
+            for stored in self.repo.get_chunk_hashes(document_id).await? {
+                known_chunks.entry(stored.content_hash).or_default().push(stored.chunk_id);
+            }
+        }
+
+        // El hash definitivo solo se registra al terminar, para que un fallo a mitad permita reintentar
+        let mut document = SourceDocument {
+            id: document_id.to_string(),
+            filename: origin.filename,
+            mime_type: origin.mime_type,
+            content_hash: stored_hash,
+            uploaded_by: origin.uploaded_by,
+            ingested_at: chrono::Utc::now().to_rfc3339(),
+            chunk_count: total_chunks,
+            metadata: payload.metadata.clone(),
+        };
+        self.repo.save_document(&document).await?;
+
+        job.document_id = Some(document.id.clone());
+        job.total_chunks = total_chunks;
+        self.repo.update_ingestion_job(job).await?;
 
         let _ = progress_tx.send(format!("🔪 Documento largo detectado. Dividido en {} fragmentos.", total_chunks)).await;
+        if resume_from > 0 {
+            let _ = progress_tx.send(format!("⏯️ Reanudando desde el fragmento {}/{}.", resume_from + 1, total_chunks)).await;
+        }
+
+        // 3. Procesar los chunks por lotes: una petición de embeddings por lote
+        //    y extracción por LLM con concurrencia acotada
+        let hashes: Vec<String> = chunks.iter().map(|c| Self::content_hash(c.text.as_bytes())).collect();
+        // Chunks sin cambios respecto a la versión anterior (se reparten en orden)
+        let existing_ids: Vec<Option<String>> = hashes.iter()
+            .map(|hash| known_chunks.get_mut(hash).and_then(|ids| ids.pop()))
+            .collect();
 
-        // 2. Procesar cada chunk
-        for (index, chunk_text) in chunks.iter().enumerate() {
-            let current_step = index + 1;
-            let chunk_id = Uuid::new_v4();
-
-            // A. Vectorizar
-            let _ = progress_tx.send(format!("🧠 [{}/{}] Generando Embeddings...", current_step, total_chunks)).await;
-            
-            // Obtenemos lock para IA
-            let ai_guard = self.ai.read().await;
-            
-            // Manejo de error específico de Embeddings para no detener todo el proceso si uno falla
-            let embedding = match ai_guard.generate_embedding(chunk_text).await {
-                Ok(emb) => emb,
-                Err(e) => {
-                    let _ = progress_tx.send(format!("⚠️ Error embedding chunk {}: {}. Saltando...", current_step, e)).await;
-                    continue; 
+        // Fragmentos escritos en esta ejecución, para deshacer una nueva versión cancelada
+        let mut written: Vec<String> = Vec::new();
+        let pending: Vec<usize> = (resume_from..total_chunks).collect();
+        for batch in pending.chunks(self.tuning.embedding_batch_size.max(1)) {
+            let (first_step, last_step) = (batch[0] + 1, batch[batch.len() - 1] + 1);
+
+            // Cancelación solicitada vía DELETE /api/ingest/jobs/{id}
+            if let Some(stored) = self.repo.get_ingestion_job(&job.id).await? {
+                if stored.status == JobStatus::Cancelled {
+                    job.status = JobStatus::Cancelled;
+                    let _ = progress_tx.send(format!("🛑 Trabajo cancelado en el fragmento {}/{}.", first_step, total_chunks)).await;
+                    // Una versión a medias no sustituye a la anterior: se retira lo escrito y se
+                    // restauran los metadatos del documento
+                    if let Some(previous) = &previous {
+                        self.repo.retire_chunks(written).await?;
+                        self.repo.save_document(previous).await?;
+                        let _ = progress_tx.send(format!("↩️ Se conserva la versión anterior de '{}'.", previous.filename)).await;
+                    }
+                    return Ok(document_id);
                 }
-            };
-
-            // B. Guardar Chunk
-            // let _ = progress_tx.send(format!("💾 [{}/{}] Guardando datos...", current_step, total_chunks)).await;
-            self.repo.save_chunk(chunk_id, chunk_text, embedding).await?;
-
-            // C. Extracción Simbólica (LLM)
-            let _ = progress_tx.send(format!("🕵️ [{}/{}] Extrayendo conocimiento...", current_step, total_chunks)).await;
-            
-            match ai_guard.extract_knowledge(chunk_text).await {
-                Ok(extraction) => {
-                    let count = extraction.entities.len();
-                    let _ = progress_tx.send(format!("🕸️ [{}/{}] Conectando {} entidades al grafo...", current_step, total_chunks, count)).await;
-                    self.repo.save_graph(chunk_id, extraction).await?;
-                },
-                Err(e) => {
-                    let _ = progress_tx.send(format!("⚠️ Error extrayendo entidades en parte {}: {}", current_step, e)).await;
-                    // No detenemos el proceso, solo avisamos
+            }
+
+            // Chunk sin cambios: se conserva (embedding y entidades incluidos) y se reubica al
+            // terminar, para que hasta entonces la versión anterior mantenga su orden
+            let fresh: Vec<usize> = batch.iter().copied().filter(|&index| existing_ids[index].is_none()).collect();
+
+            // A. Vectorizar el lote
+            if !fresh.is_empty() {
+                let _ = progress_tx.send(format!("🧠 [{}-{}/{}] Generando Embeddings de {} fragmentos...", first_step, last_step, total_chunks, fresh.len())).await;
+            }
+            let embeddings = self.embed_batch(&chunks, &fresh, &progress_tx).await;
+
+            // B. Guardar Chunks
+            let mut saved = Vec::new();
+            for (&index, embedding) in fresh.iter().zip(embeddings) {
+                // Sin embedding el fragmento se omite, como antes con un error puntual
+                let Some(embedding) = embedding else { continue };
+                let chunk = &chunks[index];
+                let location = Self::locate_chunk(&char_offsets, &payload.pages, &payload.segments, chunk.start, chunk.end);
+                let saved_chunk = SavedChunk { index, id: Uuid::new_v4(), char_start: location.char_start, char_end: location.char_end };
+                self.repo.save_chunk(ChunkRecord {
+                    id: saved_chunk.id,
+                    document_id,
+                    position: index,
+                    content: chunk.text.clone(),
+                    content_hash: hashes[index].clone(),
+                    embedding,
+                    location,
+                    metadata: payload.metadata.clone(),
+                }).await?;
+                written.push(saved_chunk.id.to_string());
+                saved.push(saved_chunk);
+            }
+
+            // C. Tabla con mapeo de columnas o vista previa revisada: el grafo ya es conocido
+            //    y se guarda tal cual, sin LLM
+            if let Some(spans) = &payload.structured {
+                for chunk in &saved {
+                    let extraction = extraction_for_chunk(spans, chunk.index, chunk.char_start, chunk.char_end);
+                    let _ = progress_tx.send(format!("🕸️ [{}/{}] Conectando {} entidades ya conocidas al grafo...", chunk.index + 1, total_chunks, extraction.entities.len())).await;
+                    self.save_extraction(chunk.id, extraction, STRUCTURED_SOURCE, chunk.index + 1, total_chunks, &progress_tx).await?;
+                    self.mark_chunk_done(job, chunk.index).await?;
+                }
+                self.mark_chunk_done(job, batch[batch.len() - 1]).await?;
+                continue;
+            }
+
+            // C. Extracción Simbólica (LLM), en paralelo; los resultados se guardan en orden
+            let indices: Vec<usize> = saved.iter().map(|chunk| chunk.index).collect();
+            let mut extractions = futures::stream::iter(indices)
+                .map(|index| self.extract_chunk(&chunks[index].text, payload.context.as_deref(), index + 1, total_chunks, &progress_tx))
+                .buffered(self.tuning.max_concurrent_extractions.max(1));
+
+            let model = self.ai.read().await.get_config().model_name;
+            for chunk in &saved {
+                let current_step = chunk.index + 1;
+                match extractions.next().await {
+                    Some(Ok(extraction)) => {
+                        let count = extraction.entities.len();
+                        let _ = progress_tx.send(format!("🕸️ [{}/{}] Conectando {} entidades al grafo...", current_step, total_chunks, count)).await;
+                        self.save_extraction(chunk.id, extraction, &model, current_step, total_chunks, &progress_tx).await?;
+                    },
+                    Some(Err(e)) => {
+                        let _ = progress_tx.send(format!("⚠️ Error extrayendo entidades en parte {}: {}", current_step, e)).await;
+                        // No detenemos el proceso, solo avisamos
+                    },
+                    None => break,
                 }
-            };
+                self.mark_chunk_done(job, chunk.index).await?;
+            }
+            // Reutilizados y omitidos del final del lote
+            self.mark_chunk_done(job, batch[batch.len() - 1]).await?;
+        }
+
+        // 4. Reubicar los reutilizados y retirar los fragmentos que desaparecieron en la nueva
+        //    versión (solo si se pidió sustituirla)
+        let mut reused = 0;
+        for (index, existing_id) in existing_ids.iter().enumerate() {
+            if let Some(existing_id) = existing_id {
+                self.repo.update_chunk_position(existing_id, document_id, index).await?;
+                reused += 1;
+            }
         }
+        let retired: Vec<String> = if origin.replace {
+            known_chunks.into_values().flatten().collect()
+        } else {
+            Vec::new()
+        };
+        if reused > 0 || !retired.is_empty() {
+            let _ = progress_tx.send(format!("♻️ {} fragmentos reutilizados, {} retirados.", reused, retired.len())).await;
+        }
+        self.repo.retire_chunks(retired).await?;
+
+        document.content_hash = origin.content_hash;
+        self.repo.save_document(&document).await?;
 
         let _ = progress_tx.send("✅ ¡Todo el documento ha sido procesado!".to_string()).await;
 
-        // Retornamos el ID del último chunk procesado (o uno nuevo genérico)
-        Ok(doc_group_id)
+        Ok(document_id)
     }
 }
\ No newline at end of file
@@ -0,0 +1,241 @@
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tracing::{info, warn, error};
+use crate::domain::{
+    ports::{KGRepository, AIService},
+    models::{DocumentOrigin, IngestionJob, JobStatus, ChunkingConfig, IngestionPayload, IngestionPreview, ChunkExtractionEdit},
+    errors::AppError
+};
+use crate::application::ingestion::{IngestionService, IngestionTuning};
+use crate::application::chunking::ChunkingPolicy;
+use crate::application::ontology::Ontology;
+use crate::application::entity_resolution::ResolutionConfig;
+
+/// Trabajo encolado en memoria; el estado real vive en el nodo `IngestionJob` de Neo4j
+struct QueuedJob {
+    job_id: String,
+    progress_tx: Option<mpsc::Sender<String>>,
+}
+
+/// Cola de ingesta en segundo plano.
+/// Un único worker procesa los trabajos en orden; el cliente HTTP puede desconectarse
+/// sin que el trabajo se pierda, y los pendientes se reanudan al arrancar.
+pub struct IngestionJobQueue {
+    repo: Arc<dyn KGRepository>,
+    sender: mpsc::UnboundedSender<QueuedJob>,
+    chunking_policy: ChunkingPolicy,
+    /// Las vistas previas se calculan fuera del worker, sin esperar a la cola
+    previewer: IngestionService,
+}
+
+impl IngestionJobQueue {
+    pub fn start(
+        repo: Arc<dyn KGRepository>,
+        ai: Arc<RwLock<dyn AIService>>,
+        chunking_policy: ChunkingPolicy,
+        tuning: IngestionTuning,
+        ontology: Arc<Ontology>,
+        resolution: ResolutionConfig,
+    ) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<QueuedJob>();
+        let worker_repo = repo.clone();
+        let previewer = IngestionService::new(repo.clone(), ai.clone(), tuning.clone(), ontology.clone(), resolution.clone());
+
+        tokio::spawn(async move {
+            let service = IngestionService::new(worker_repo.clone(), ai, tuning, ontology, resolution);
+            while let Some(queued) = receiver.recv().await {
+                if let Err(e) = Self::run_job(&worker_repo, &service, queued).await {
+                    error!("🔥 Error gestionando trabajo de ingesta: {}", e);
+                }
+            }
+        });
+
+        Self { repo, sender, chunking_policy, previewer }
+    }
+
+    /// Persiste un nuevo trabajo y lo pone en cola. `progress_tx` recibe el avance mientras siga abierto.
+    /// Sin `chunking` explícito se aplica la estrategia configurada para el formato del archivo.
+    pub async fn enqueue(
+        &self,
+        origin: DocumentOrigin,
+        payload: IngestionPayload,
+        chunking: Option<ChunkingConfig>,
+        progress_tx: Option<mpsc::Sender<String>>,
+    ) -> Result<IngestionJob, AppError> {
+        let chunking = chunking.unwrap_or_else(|| self.chunking_policy.for_filename(&origin.filename));
+        let job = IngestionJob::new(origin, chunking);
+        self.repo.create_ingestion_job(&job, &payload).await?;
+        self.sender
+            .send(QueuedJob { job_id: job.id.clone(), progress_tx })
+            .map_err(|e| AppError::ConfigError(format!("Cola de ingesta detenida: {}", e)))?;
+        info!("📥 Trabajo de ingesta {} encolado ('{}').", job.id, job.filename);
+        Ok(job)
+    }
+
+    /// Ingesta en modo vista previa: trocea y extrae sin tocar el grafo y guarda la propuesta
+    /// en un trabajo `Preview`, que solo se ejecuta al confirmarlo con `commit_preview`
+    pub async fn preview(
+        &self,
+        origin: DocumentOrigin,
+        mut payload: IngestionPayload,
+        chunking: Option<ChunkingConfig>,
+        progress_tx: &mpsc::Sender<String>,
+    ) -> Result<IngestionJob, AppError> {
+        let chunking = chunking.unwrap_or_else(|| self.chunking_policy.for_filename(&origin.filename));
+        if let Some(existing) = self.repo.find_document_by_hash(&origin.content_hash).await? {
+            let _ = progress_tx.send(format!("♻️ '{}' ya fue ingerido como '{}': al confirmar se omitirá.", origin.filename, existing.filename)).await;
+        }
+        payload.structured = Some(self.previewer.propose(&payload, &chunking, progress_tx).await?);
+
+        let mut job = IngestionJob::new(origin, chunking);
+        job.status = JobStatus::Preview;
+        self.repo.create_ingestion_job(&job, &payload).await?;
+        info!("🔍 Vista previa {} guardada ('{}').", job.id, job.filename);
+        Ok(job)
+    }
+
+    /// Vista previa pendiente con sus fragmentos y extracciones propuestas
+    pub async fn get_preview(&self, id: &str) -> Result<Option<IngestionPreview>, AppError> {
+        let Some(job) = self.repo.get_ingestion_job(id).await? else {
+            return Ok(None);
+        };
+        Self::ensure_preview(&job)?;
+        let payload = self.repo.get_ingestion_job_payload(id).await?
+            .ok_or_else(|| AppError::ValidationError(format!("Contenido de la vista previa {} no disponible", id)))?;
+        let chunks = IngestionService::preview_chunks(&payload, &job.chunking);
+        Ok(Some(IngestionPreview { job, chunks }))
+    }
+
+    /// Confirma una vista previa: aplica las extracciones revisadas y encola el trabajo.
+    /// La ingesta guarda esas extracciones tal cual, sin volver a llamar al LLM.
+    pub async fn commit_preview(&self, id: &str, edits: Vec<ChunkExtractionEdit>) -> Result<Option<IngestionJob>, AppError> {
+        let Some(mut job) = self.repo.get_ingestion_job(id).await? else {
+            return Ok(None);
+        };
+        Self::ensure_preview(&job)?;
+
+        if !edits.is_empty() {
+            let mut spans = self.repo.get_ingestion_job_payload(id).await?
+                .and_then(|payload| payload.structured)
+                .ok_or_else(|| AppError::ValidationError(format!("Contenido de la vista previa {} no disponible", id)))?;
+            for edit in edits {
+                edit.extraction.validate()?;
+                let span = spans.iter_mut()
+                    .find(|span| span.chunk == Some(edit.position))
+                    .ok_or_else(|| AppError::ValidationError(format!("La vista previa no tiene el fragmento {}", edit.position)))?;
+                span.extraction = edit.extraction;
+            }
+            self.repo.update_ingestion_job_structured(id, &spans).await?;
+        }
+
+        job.status = JobStatus::Queued;
+        self.repo.update_ingestion_job(&job).await?;
+        self.sender
+            .send(QueuedJob { job_id: job.id.clone(), progress_tx: None })
+            .map_err(|e| AppError::ConfigError(format!("Cola de ingesta detenida: {}", e)))?;
+        info!("📥 Vista previa {} confirmada ('{}').", job.id, job.filename);
+        Ok(Some(job))
+    }
+
+    fn ensure_preview(job: &IngestionJob) -> Result<(), AppError> {
+        if job.status != JobStatus::Preview {
+            return Err(AppError::ValidationError(format!("El trabajo {} no es una vista previa pendiente (estado {})", job.id, job.status)));
+        }
+        Ok(())
+    }
+
+    /// Estrategia configurada para un archivo; útil cuando el nombre del documento no conserva su extensión
+    pub fn chunking_for(&self, filename: &str) -> ChunkingConfig {
+        self.chunking_policy.for_filename(filename)
+    }
+
+    /// Estrategia para una transcripción de audio o vídeo: siempre alineada con los segmentos
+    pub fn chunking_for_transcript(&self, requested: Option<&ChunkingConfig>) -> ChunkingConfig {
+        self.chunking_policy.for_transcript(requested)
+    }
+
+    /// Re-encola los trabajos que quedaron en cola o a medias tras un reinicio
+    pub async fn resume_unfinished(&self) -> Result<usize, AppError> {
+        let pending = self.repo.list_unfinished_ingestion_jobs().await?;
+        for job in &pending {
+            info!("⏯️ Reanudando trabajo {} ('{}') desde el fragmento {}.", job.id, job.filename, job.completed_chunks);
+            self.sender
+                .send(QueuedJob { job_id: job.id.clone(), progress_tx: None })
+                .map_err(|e| AppError::ConfigError(format!("Cola de ingesta detenida: {}", e)))?;
+        }
+        Ok(pending.len())
+    }
+
+    /// Cancela un trabajo activo; si ya terminó, elimina su registro.
+    /// Devuelve el trabajo tal como estaba antes de la operación.
+    pub async fn cancel_or_remove(&self, id: &str) -> Result<Option<IngestionJob>, AppError> {
+        let Some(mut job) = self.repo.get_ingestion_job(id).await? else {
+            return Ok(None);
+        };
+        let snapshot = job.clone();
+
+        if job.status.is_active() {
+            job.status = JobStatus::Cancelled;
+            self.repo.update_ingestion_job(&job).await?;
+        } else {
+            self.repo.delete_ingestion_job(id).await?;
+        }
+        Ok(Some(snapshot))
+    }
+
+    async fn run_job(
+        repo: &Arc<dyn KGRepository>,
+        service: &IngestionService,
+        queued: QueuedJob,
+    ) -> Result<(), AppError> {
+        // Sin cliente conectado el avance se descarta (el receptor ya no existe)
+        let progress_tx = queued.progress_tx.unwrap_or_else(|| mpsc::channel(1).0);
+
+        let Some(mut job) = repo.get_ingestion_job(&queued.job_id).await? else {
+            warn!("⚠️ Trabajo {} desaparecido antes de ejecutarse.", queued.job_id);
+            return Ok(());
+        };
+        if !job.status.is_active() {
+            let _ = progress_tx.send(format!("🛑 Trabajo {} en estado {}. No se ejecuta.", job.id, job.status)).await;
+            return Ok(());
+        }
+
+        let Some(payload) = repo.get_ingestion_job_payload(&job.id).await? else {
+            job.status = JobStatus::Failed;
+            job.error = Some("Contenido del trabajo no disponible".to_string());
+            return repo.update_ingestion_job(&job).await;
+        };
+
+        job.status = JobStatus::Running;
+        repo.update_ingestion_job(&job).await?;
+
+        // En subidas de varios archivos el stream mezcla trabajos: cada mensaje lleva el nombre de su documento
+        let (job_tx, mut job_rx) = mpsc::channel::<String>(20);
+        let label = job.filename.clone();
+        let client_tx = progress_tx.clone();
+        let forwarder = tokio::spawn(async move {
+            while let Some(message) = job_rx.recv().await {
+                let _ = client_tx.send(format!("[{}] {}", label, message)).await;
+            }
+        });
+        let result = service.ingest_with_progress(&mut job, payload, job_tx).await;
+        // Todo el avance llega al cliente antes del resultado final
+        let _ = forwarder.await;
+
+        match result {
+            Ok(_) if job.status == JobStatus::Cancelled => {
+                info!("🛑 Trabajo {} cancelado.", job.id);
+            },
+            Ok(_) => {
+                job.status = JobStatus::Done;
+                let _ = progress_tx.send(format!("[{}] DONE", job.filename)).await;
+            },
+            Err(e) => {
+                job.status = JobStatus::Failed;
+                job.error = Some(e.to_string());
+                let _ = progress_tx.send(format!("[{}] ❌ Error GraphRAG: {}", job.filename, e)).await;
+            },
+        }
+        repo.update_ingestion_job(&job).await
+    }
+}
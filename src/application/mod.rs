@@ -1,4 +1,10 @@
 pub mod dtos;
+pub mod chunking;
 pub mod ingestion;
+pub mod ingestion_jobs;
+pub mod tabular;
+pub mod ontology;
+pub mod entity_resolution;
+pub mod web_ingestion;
 pub mod reasoning; // <-- NUEVO
 pub mod agent_service;
\ No newline at end of file
@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use serde::Deserialize;
+use crate::domain::models::{GraphEntity, GraphRelation, KnowledgeExtraction, is_valid_relation_type, normalize_attributes, category_label};
+use crate::domain::errors::AppError;
+
+/// Ontología incluida en el binario: se usa si `config/ontology.yaml` no existe o es inválido
+const DEFAULT_ONTOLOGY: &str = include_str!("../../config/ontology.yaml");
+
+/// Categorías y relaciones que el LLM puede extraer (config/ontology.yaml).
+/// Genera el prompt de extracción y valida cada extracción antes de guardarla.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Ontology {
+    #[serde(default)]
+    pub role: String,
+    pub categories: Vec<CategoryDefinition>,
+    #[serde(default)]
+    pub relations: Vec<RelationDefinition>,
+    /// Destino de las categorías desconocidas; sin valor, esas entidades se descartan
+    #[serde(default)]
+    pub unknown_category: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CategoryDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Atributos sugeridos al LLM (p.ej. `dosis`); se aceptan también otros
+    #[serde(default)]
+    pub attributes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RelationDefinition {
+    #[serde(rename = "type")]
+    pub relation_type: String,
+    #[serde(default)]
+    pub description: String,
+    /// Categorías admitidas como origen (vacío = cualquiera)
+    #[serde(default)]
+    pub domain: Vec<String>,
+    /// Categorías admitidas como destino (vacío = cualquiera)
+    #[serde(default)]
+    pub range: Vec<String>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub attributes: Vec<String>,
+}
+
+/// Cambios aplicados al validar una extracción, en texto legible para el progreso
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OntologyReport {
+    pub remapped: Vec<String>,
+    pub rejected: Vec<String>,
+}
+
+impl OntologyReport {
+    pub fn is_empty(&self) -> bool {
+        self.remapped.is_empty() && self.rejected.is_empty()
+    }
+}
+
+impl Default for Ontology {
+    fn default() -> Self {
+        Self::from_yaml_str(DEFAULT_ONTOLOGY).expect("config/ontology.yaml incluido en el binario es válido")
+    }
+}
+
+impl Ontology {
+    pub fn from_yaml_file(path: &str) -> Result<Self, AppError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|_| AppError::ConfigError(format!("Ontology not found: {}", path)))?;
+        Self::from_yaml_str(&content)
+    }
+
+    pub fn from_yaml_str(content: &str) -> Result<Self, AppError> {
+        let ontology: Self = serde_yaml::from_str(content)
+            .map_err(|e| AppError::ParseError(format!("YAML Error in ontology: {}", e)))?;
+        ontology.validate()?;
+        Ok(ontology)
+    }
+
+    /// Coherencia interna: nombres únicos, tipos de relación válidos en Cypher y
+    /// `domain`/`range`/`unknown_category` referidos a categorías declaradas
+    fn validate(&self) -> Result<(), AppError> {
+        if self.categories.is_empty() {
+            return Err(AppError::ValidationError("La ontología no declara ninguna categoría".to_string()));
+        }
+        let mut seen = HashMap::new();
+        for category in &self.categories {
+            // Cada categoría es también una etiqueta de Neo4j
+            if category_label(&category.name).is_none() {
+                return Err(AppError::ValidationError(format!("La categoría '{}' no sirve como etiqueta (usa letras y dígitos, y no Entity, Document...)", category.name)));
+            }
+            for label in std::iter::once(&category.name).chain(&category.aliases) {
+                if let Some(previous) = seen.insert(label.trim().to_lowercase(), &category.name) {
+                    return Err(AppError::ValidationError(format!("'{}' aparece en las categorías {} y {}", label, previous, category.name)));
+                }
+            }
+        }
+        let is_category = |name: &String| self.categories.iter().any(|c| &c.name == name);
+        if let Some(fallback) = self.unknown_category.as_ref().filter(|c| !is_category(c)) {
+            return Err(AppError::ValidationError(format!("unknown_category '{}' no es una categoría declarada", fallback)));
+        }
+
+        let mut seen = HashMap::new();
+        for relation in &self.relations {
+            if relation.relation_type != normalize_relation_type(&relation.relation_type) || !is_valid_relation_type(&relation.relation_type) {
+                return Err(AppError::ValidationError(format!("Tipo de relación inválido: '{}' (usa MAYÚSCULAS_CON_GUIONES)", relation.relation_type)));
+            }
+            if let Some(category) = relation.domain.iter().chain(&relation.range).find(|c| !is_category(c)) {
+                return Err(AppError::ValidationError(format!("La relación {} usa la categoría no declarada '{}'", relation.relation_type, category)));
+            }
+            for label in std::iter::once(&relation.relation_type).chain(&relation.aliases) {
+                if let Some(previous) = seen.insert(normalize_relation_type(label), &relation.relation_type) {
+                    return Err(AppError::ValidationError(format!("'{}' aparece en las relaciones {} y {}", label, previous, relation.relation_type)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Instrucciones del LLM para extraer entidades y relaciones según la ontología
+    pub fn extraction_prompt(&self) -> String {
+        let mut prompt = String::new();
+        if !self.role.trim().is_empty() {
+            prompt.push_str(self.role.trim());
+            prompt.push_str("\n\n");
+        }
+
+        prompt.push_str("ONTOLOGÍA ESTRICTA (usa exactamente estos nombres de categoría):\n");
+        let hint = |attributes: &[String]| if attributes.is_empty() { String::new() } else { format!(" [atributos: {}]", attributes.join(", ")) };
+        for category in &self.categories {
+            prompt.push_str(&format!("- {}: {}{}\n", category.name, category.description, hint(&category.attributes)));
+        }
+
+        if !self.relations.is_empty() {
+            prompt.push_str("\nRELACIONES PERMITIDAS (usa exactamente estos tipos, en el sentido origen -> destino):\n");
+            for relation in &self.relations {
+                let side = |categories: &[String]| if categories.is_empty() { "Cualquiera".to_string() } else { categories.join(" | ") };
+                prompt.push_str(&format!("- {} ({} -> {}): {}{}\n", relation.relation_type, side(&relation.domain), side(&relation.range), relation.description, hint(&relation.attributes)));
+            }
+        }
+
+        prompt.push_str("\nINSTRUCCIONES:\n");
+        prompt.push_str("1. Extrae entidades relevantes, cada una con una de las categorías anteriores.\n");
+        prompt.push_str("2. Extrae solo relaciones de la lista, entre entidades extraídas y respetando origen y destino.\n");
+        prompt.push_str("3. En cada relación, copia en `evidence` la frase literal del texto que la afirma e indica en `confidence` (0 a 1) tu seguridad.\n");
+        prompt.push_str("4. Datos concretos (fechas, dosis, puntuaciones, edades) van en `attributes` de la entidad o relación, nunca en el nombre: \"sertralina 50mg\" es la entidad \"Sertralina\" y la dosis un atributo. Usa números para cantidades y fechas ISO (2024-03).\n");
+        prompt.push_str("5. Output JSON puro.\n\n");
+        prompt.push_str(r#"Output Format:
+{
+    "entities": [{"name": "Nombre Único", "category": "Categoría", "attributes": {"clave": "valor o número"}}],
+    "relations": [{"source": "Nombre1", "target": "Nombre2", "relation_type": "TIPO", "evidence": "frase literal del texto", "confidence": 0.9, "attributes": {"clave": "valor o número"}}]
+}
+"#);
+        prompt
+    }
+
+    /// Ajusta una extracción a la ontología: categorías y tipos por su nombre canónico,
+    /// categorías desconocidas reasignadas (o descartadas) y relaciones fuera de la lista,
+    /// con extremos no extraídos o que no cumplen `domain`/`range` descartadas.
+    /// Una relación al revés (destino -> origen válido) se invierte.
+    pub fn conform(&self, extraction: KnowledgeExtraction) -> (KnowledgeExtraction, OntologyReport) {
+        let mut report = OntologyReport::default();
+        let mut conformed = KnowledgeExtraction::default();
+        let mut categories: HashMap<String, String> = HashMap::new();
+
+        for entity in extraction.entities {
+            let name = entity.name.trim().to_string();
+            if name.is_empty() {
+                report.rejected.push("Entidad sin nombre".to_string());
+                continue;
+            }
+            if categories.contains_key(&name) {
+                continue;
+            }
+            let category = match self.category_for(&entity.category) {
+                Some(canonical) => {
+                    if canonical != entity.category {
+                        report.remapped.push(format!("'{}': categoría {} → {}", name, entity.category, canonical));
+                    }
+                    canonical.to_string()
+                },
+                None => match &self.unknown_category {
+                    Some(fallback) => {
+                        report.remapped.push(format!("'{}': categoría desconocida {} → {}", name, entity.category, fallback));
+                        fallback.clone()
+                    },
+                    None => {
+                        report.rejected.push(format!("'{}': categoría desconocida {}", name, entity.category));
+                        continue;
+                    },
+                },
+            };
+            categories.insert(name.clone(), category.clone());
+            conformed.entities.push(GraphEntity { name, category, attributes: normalize_attributes(entity.attributes) });
+        }
+
+        for relation in extraction.relations {
+            let (source, target) = (relation.source.trim().to_string(), relation.target.trim().to_string());
+            let label = format!("{} -[{}]-> {}", source, relation.relation_type, target);
+            let Some(definition) = self.relation_for(&relation.relation_type) else {
+                report.rejected.push(format!("{}: tipo no permitido", label));
+                continue;
+            };
+            let (Some(source_category), Some(target_category)) = (categories.get(&source), categories.get(&target)) else {
+                report.rejected.push(format!("{}: extremo sin entidad válida", label));
+                continue;
+            };
+            let (source, target) = if definition.accepts(source_category, target_category) {
+                (source, target)
+            } else if definition.accepts(target_category, source_category) {
+                report.remapped.push(format!("{}: invertida", label));
+                (target, source)
+            } else {
+                report.rejected.push(format!("{}: {} -> {} no cumple {}", label, source_category, target_category, definition.relation_type));
+                continue;
+            };
+            if definition.relation_type != relation.relation_type {
+                report.remapped.push(format!("{}: tipo → {}", label, definition.relation_type));
+            }
+            conformed.relations.push(GraphRelation {
+                source,
+                target,
+                relation_type: definition.relation_type.clone(),
+                confidence: relation.confidence.map(|c| c.clamp(0.0, 1.0)),
+                evidence: relation.evidence,
+                attributes: normalize_attributes(relation.attributes),
+            });
+        }
+
+        (conformed, report)
+    }
+
+    fn category_for(&self, raw: &str) -> Option<&str> {
+        let wanted = raw.trim().to_lowercase();
+        self.categories.iter()
+            .find(|c| c.name.to_lowercase() == wanted || c.aliases.iter().any(|a| a.to_lowercase() == wanted))
+            .map(|c| c.name.as_str())
+    }
+
+    fn relation_for(&self, raw: &str) -> Option<&RelationDefinition> {
+        let wanted = normalize_relation_type(raw);
+        self.relations.iter().find(|r| r.relation_type == wanted || r.aliases.iter().any(|a| normalize_relation_type(a) == wanted))
+    }
+}
+
+impl RelationDefinition {
+    fn accepts(&self, source_category: &str, target_category: &str) -> bool {
+        let allows = |categories: &[String], category: &str| categories.is_empty() || categories.iter().any(|c| c == category);
+        allows(&self.domain, source_category) && allows(&self.range, target_category)
+    }
+}
+
+/// Forma en la que se guarda un tipo de relación: `tratado con` -> `TRATADO_CON`
+fn normalize_relation_type(raw: &str) -> String {
+    raw.trim().replace([' ', '-'], "_").to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::AttributeValue;
+
+    fn entity(name: &str, category: &str) -> GraphEntity {
+        GraphEntity { name: name.to_string(), category: category.to_string(), ..Default::default() }
+    }
+
+    fn relation(source: &str, relation_type: &str, target: &str) -> GraphRelation {
+        GraphRelation { source: source.to_string(), target: target.to_string(), relation_type: relation_type.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn keeps_typed_attributes_with_normalized_keys() {
+        let raw = r#"{
+            "entities": [{"name": "Ana", "category": "Person", "attributes": {"Edad": 34, "PHQ-9": 14.5, "notas": " "}}],
+            "relations": [{"source": "Ana", "target": "Sertralina", "relation_type": "TREATED_WITH", "attributes": {"dosis": 50, "unidad": "mg", "Fecha de inicio": "2024-03", "activo": true}}]
+        }"#;
+        let mut extraction: KnowledgeExtraction = serde_json::from_str(raw).unwrap();
+        extraction.entities.push(entity("Sertralina", "Intervention"));
+        let (conformed, _) = Ontology::default().conform(extraction);
+
+        let ana = &conformed.entities[0].attributes;
+        assert_eq!(ana.get("edad"), Some(&AttributeValue::Integer(34)));
+        assert_eq!(ana.get("phq_9"), Some(&AttributeValue::Number(14.5)));
+        assert!(!ana.contains_key("notas"));
+        let treatment = &conformed.relations[0].attributes;
+        assert_eq!(treatment.get("dosis"), Some(&AttributeValue::Integer(50)));
+        assert_eq!(treatment.get("fecha_de_inicio"), Some(&AttributeValue::Text("2024-03".to_string())));
+        assert_eq!(treatment.get("activo"), Some(&AttributeValue::Boolean(true)));
+        assert!(Ontology::default().extraction_prompt().contains("[atributos: dosis, unidad, frecuencia, fecha_inicio]"));
+    }
+
+    #[test]
+    fn conforms_extractions_to_the_configured_ontology() {
+        let ontology = Ontology::default();
+        let prompt = ontology.extraction_prompt();
+        assert!(prompt.contains("- Person: Pacientes"));
+        assert!(prompt.contains("- PARTICIPATES_IN (Person -> Intervention)"));
+
+        let (conformed, report) = ontology.conform(KnowledgeExtraction {
+            entities: vec![
+                entity("Ana", "paciente"),
+                entity("Taller de Arte", "Intervention"),
+                entity("Ansiedad", "Condition"),
+                entity("Madrid", "Lugar"),
+            ],
+            relations: vec![
+                relation("Ana", "participa en", "Taller de Arte"),
+                relation("Ansiedad", "HAS_CONDITION", "Ana"),
+                relation("Ana", "VIVE_EN", "Madrid"),
+                relation("Taller de Arte", "HAS_CONDITION", "Ansiedad"),
+            ],
+        });
+
+        let categories: Vec<(&str, &str)> = conformed.entities.iter().map(|e| (e.name.as_str(), e.category.as_str())).collect();
+        assert_eq!(categories, vec![("Ana", "Person"), ("Taller de Arte", "Intervention"), ("Ansiedad", "Condition")]);
+        let relations: Vec<(&str, &str, &str)> = conformed.relations.iter()
+            .map(|r| (r.source.as_str(), r.relation_type.as_str(), r.target.as_str()))
+            .collect();
+        assert_eq!(relations, vec![("Ana", "PARTICIPATES_IN", "Taller de Arte"), ("Ana", "HAS_CONDITION", "Ansiedad")]);
+        assert_eq!(report.rejected.len(), 3);
+        assert!(report.remapped.iter().any(|r| r.contains("invertida")));
+
+        // Con categoría de reserva, lo desconocido se reasigna en lugar de descartarse
+        let lenient = Ontology { unknown_category: Some("CommunityResource".to_string()), ..Ontology::default() };
+        let (conformed, _) = lenient.conform(KnowledgeExtraction { entities: vec![entity("Madrid", "Lugar")], relations: Vec::new() });
+        assert_eq!(conformed.entities[0].category, "CommunityResource");
+    }
+
+    #[test]
+    fn rejects_inconsistent_ontology_files() {
+        let undeclared = "categories:\n  - {name: Person}\nrelations:\n  - {type: TREATS, domain: [Person], range: [Drug]}\n";
+        assert!(Ontology::from_yaml_str(undeclared).is_err());
+        let lowercase = "categories:\n  - {name: Person}\nrelations:\n  - {type: knows}\n";
+        assert!(Ontology::from_yaml_str(lowercase).is_err());
+        let leading_digit = "categories:\n  - {name: Person}\nrelations:\n  - {type: 1_KNOWS}\n";
+        assert!(Ontology::from_yaml_str(leading_digit).is_err());
+        let duplicated = "categories:\n  - {name: Person, aliases: [Persona]}\n  - {name: Persona}\n";
+        assert!(Ontology::from_yaml_str(duplicated).is_err());
+        let reserved = "categories:\n  - {name: Document}\n";
+        assert!(Ontology::from_yaml_str(reserved).is_err());
+    }
+
+    #[test]
+    fn derives_neo4j_labels_from_categories() {
+        assert_eq!(category_label("CommunityResource").as_deref(), Some("CommunityResource"));
+        assert_eq!(category_label("Recurso comunitario").as_deref(), Some("RecursoComunitario"));
+        assert_eq!(category_label("Condición").as_deref(), Some("Condicion"));
+        assert_eq!(category_label("3D"), None);
+        assert_eq!(category_label("Entity"), None);
+        assert_eq!(category_label("¿?"), None);
+    }
+}
@@ -57,7 +57,7 @@ impl ReasoningService {
         // 3. Consultar IA
         let ai_guard = self.ai.read().await;
         
-        // Usamos generate_inference que ya maneja la limpieza de JSON
+        // generate_inference pide salida con esquema y corrige el JSON inválido
         let response_json = ai_guard.generate_inference(&prompt).await?;
         
         // 4. Guardar en Base de Datos
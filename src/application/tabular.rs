@@ -0,0 +1,297 @@
+use serde::{Deserialize, Serialize};
+use crate::domain::models::{GraphEntity, GraphRelation, IngestionPayload, KnowledgeExtraction, StructuredSpan, is_valid_relation_type};
+use crate::domain::errors::AppError;
+
+/// Directorio de los mapeos con nombre (`mapping_name` en /api/ingest)
+pub const MAPPINGS_DIR: &str = "config/tabular";
+
+/// Mapeo declarativo de las columnas de una tabla (CSV, XLSX, XLS, ODS) al grafo.
+/// Cada fila se convierte directamente en entidades y relaciones, sin extracción por LLM.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TabularMapping {
+    /// Hoja a leer en libros de cálculo (por defecto, la primera)
+    #[serde(default)]
+    pub sheet: Option<String>,
+    #[serde(default)]
+    pub entities: Vec<EntityColumn>,
+    #[serde(default)]
+    pub relations: Vec<ColumnRelation>,
+    /// Tablas de doble entrada: las columnas marcadas de una fila son entidades relacionadas con ella
+    #[serde(default)]
+    pub matrix: Vec<MatrixRelation>,
+}
+
+/// Columna cuyos valores son entidades de una categoría
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EntityColumn {
+    pub column: String,
+    pub category: String,
+    /// Separador de celdas con varios valores (p.ej. ";")
+    #[serde(default)]
+    pub separator: Option<String>,
+}
+
+/// Relación entre las entidades de dos columnas de la misma fila
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ColumnRelation {
+    pub source: String,
+    pub target: String,
+    #[serde(rename = "type")]
+    pub relation_type: String,
+}
+
+/// Relación de la entidad de `source` con la cabecera de cada columna marcada en la fila
+/// (celda no vacía distinta de "0", "no", "n", "false", "-")
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MatrixRelation {
+    pub source: String,
+    pub columns: Vec<String>,
+    pub category: String,
+    #[serde(rename = "type")]
+    pub relation_type: String,
+}
+
+impl TabularMapping {
+    pub fn from_yaml_str(content: &str) -> Result<Self, AppError> {
+        let mapping: Self = serde_yaml::from_str(content)
+            .map_err(|e| AppError::ParseError(format!("YAML Error in tabular mapping: {}", e)))?;
+        mapping.validate()?;
+        Ok(mapping)
+    }
+
+    /// Carga `config/tabular/{name}.yaml`
+    pub fn from_named(name: &str) -> Result<Self, AppError> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(AppError::ValidationError(format!("Nombre de mapeo inválido: {}", name)));
+        }
+        let path = format!("{}/{}.yaml", MAPPINGS_DIR, name);
+        let content = std::fs::read_to_string(&path)
+            .map_err(|_| AppError::ConfigError(format!("Tabular mapping not found: {}", path)))?;
+        Self::from_yaml_str(&content)
+    }
+
+    /// Coherencia interna: las relaciones usan columnas declaradas como entidades
+    /// y los tipos de relación son identificadores válidos en Cypher
+    fn validate(&self) -> Result<(), AppError> {
+        if self.entities.is_empty() {
+            return Err(AppError::ValidationError("El mapeo no declara ninguna columna de entidades".to_string()));
+        }
+        let is_entity = |column: &str| self.entities.iter().any(|e| e.column == column);
+        let endpoints = self.relations.iter()
+            .flat_map(|r| [&r.source, &r.target])
+            .chain(self.matrix.iter().map(|m| &m.source));
+        for column in endpoints {
+            if !is_entity(column) {
+                return Err(AppError::ValidationError(format!("La columna '{}' de una relación no está declarada como entidad", column)));
+            }
+        }
+        let types = self.relations.iter().map(|r| &r.relation_type)
+            .chain(self.matrix.iter().map(|m| &m.relation_type));
+        for relation_type in types {
+            if !is_valid_relation_type(relation_type) {
+                return Err(AppError::ValidationError(format!("Tipo de relación inválido: '{}'", relation_type)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Convierte las filas (la primera es la cabecera) en contenido textual, una línea por fila,
+    /// y el grafo exacto de cada fila como tramo estructurado.
+    pub fn apply(&self, rows: Vec<Vec<String>>) -> Result<IngestionPayload, AppError> {
+        let mut rows = rows.into_iter();
+        let headers = rows.next()
+            .ok_or_else(|| AppError::ValidationError("Tabla vacía".to_string()))?;
+        let column = |name: &str| headers.iter().position(|h| h.trim() == name)
+            .ok_or_else(|| AppError::ValidationError(format!("Columna '{}' no encontrada en la cabecera", name)));
+
+        let entity_columns = self.entities.iter()
+            .map(|e| Ok((e, column(&e.column)?)))
+            .collect::<Result<Vec<_>, AppError>>()?;
+        let matrix_columns = self.matrix.iter()
+            .map(|m| Ok((m, m.columns.iter().map(|c| Ok((c.as_str(), column(c)?))).collect::<Result<Vec<_>, AppError>>()?)))
+            .collect::<Result<Vec<_>, AppError>>()?;
+
+        let mut content = String::new();
+        let mut spans = Vec::new();
+        let mut offset = 0;
+        for row in rows {
+            let cell = |index: usize| row.get(index).map(|c| c.trim()).unwrap_or("");
+            let line: Vec<String> = headers.iter().enumerate()
+                .filter(|(i, _)| !cell(*i).is_empty())
+                .map(|(i, header)| format!("{}: {}", header.trim(), cell(i)))
+                .collect();
+            if line.is_empty() {
+                continue;
+            }
+
+            // Valores de cada columna de entidad en esta fila
+            let values = |name: &str| -> Vec<String> {
+                entity_columns.iter()
+                    .find(|(e, _)| e.column == name)
+                    .map(|(e, index)| split_values(cell(*index), e.separator.as_deref()))
+                    .unwrap_or_default()
+            };
+
+            let mut extraction = KnowledgeExtraction { entities: Vec::new(), relations: Vec::new() };
+            for (entity, index) in &entity_columns {
+                for name in split_values(cell(*index), entity.separator.as_deref()) {
+                    push_entity(&mut extraction, name, &entity.category);
+                }
+            }
+            for relation in &self.relations {
+                for source in values(&relation.source) {
+                    for target in values(&relation.target) {
+                        extraction.relations.push(GraphRelation {
+                            source: source.clone(),
+                            target,
+                            relation_type: relation.relation_type.clone(),
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+            for (matrix, columns) in &matrix_columns {
+                let sources = values(&matrix.source);
+                for (header, index) in columns {
+                    if !is_marked(cell(*index)) {
+                        continue;
+                    }
+                    push_entity(&mut extraction, header.to_string(), &matrix.category);
+                    for source in &sources {
+                        extraction.relations.push(GraphRelation {
+                            source: source.clone(),
+                            target: header.to_string(),
+                            relation_type: matrix.relation_type.clone(),
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+
+            let line = line.join(" | ");
+            // La fila completa es la evidencia de las relaciones que salen de ella
+            for relation in &mut extraction.relations {
+                relation.evidence = Some(line.clone());
+            }
+            let len = line.chars().count();
+            spans.push(StructuredSpan { char_start: offset, char_end: offset + len, extraction, chunk: None });
+            content.push_str(&line);
+            content.push('\n');
+            offset += len + 1;
+        }
+
+        if spans.is_empty() {
+            return Err(AppError::ValidationError("La tabla no tiene filas de datos".to_string()));
+        }
+        Ok(IngestionPayload { content, structured: Some(spans), ..Default::default() })
+    }
+}
+
+/// Grafo del fragmento `position`, que ocupa `[char_start, char_end)`: el revisado para esa
+/// posición si la vista previa lo fijó; si no, la unión de los tramos que se solapan con él
+pub fn extraction_for_chunk(spans: &[StructuredSpan], position: usize, char_start: usize, char_end: usize) -> KnowledgeExtraction {
+    if let Some(span) = spans.iter().find(|s| s.chunk == Some(position)) {
+        return span.extraction.clone();
+    }
+    let mut merged = KnowledgeExtraction::default();
+    for span in spans.iter().filter(|s| s.chunk.is_none() && s.char_start < char_end && s.char_end > char_start) {
+        for entity in &span.extraction.entities {
+            push_entity(&mut merged, entity.name.clone(), &entity.category);
+        }
+        merged.relations.extend(span.extraction.relations.iter().cloned());
+    }
+    merged
+}
+
+fn split_values(cell: &str, separator: Option<&str>) -> Vec<String> {
+    match separator {
+        Some(sep) => cell.split(sep).map(str::trim).filter(|v| !v.is_empty()).map(str::to_string).collect(),
+        None if cell.is_empty() => Vec::new(),
+        None => vec![cell.to_string()],
+    }
+}
+
+fn push_entity(extraction: &mut KnowledgeExtraction, name: String, category: &str) {
+    if !extraction.entities.iter().any(|e| e.name == name) {
+        extraction.entities.push(GraphEntity { name, category: category.to_string(), ..Default::default() });
+    }
+}
+
+fn is_marked(cell: &str) -> bool {
+    !cell.is_empty() && !matches!(cell.to_lowercase().as_str(), "0" | "no" | "false" | "-" | "n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(data: &[&[&str]]) -> Vec<Vec<String>> {
+        data.iter().map(|r| r.iter().map(|c| c.to_string()).collect()).collect()
+    }
+
+    #[test]
+    fn maps_rows_to_entities_relations_and_matrix_marks() {
+        let mapping = TabularMapping::from_named("asistencia_talleres").expect("mapeo de ejemplo");
+        let payload = mapping.apply(rows(&[
+            &["Participante", "Centro", "Educador", "Cocina", "Informática", "Búsqueda de empleo"],
+            &["Ana", "Centro Norte", "Luis; Marta", "x", "", "no"],
+            &["", "", "", "", "", ""],
+            &["Pedro", "Centro Sur", "", "", "1", ""],
+        ])).expect("payload");
+
+        assert_eq!(payload.content.lines().next(), Some("Participante: Ana | Centro: Centro Norte | Educador: Luis; Marta | Cocina: x | Búsqueda de empleo: no"));
+        let spans = payload.structured.expect("tramos");
+        assert_eq!(spans.len(), 2);
+
+        let ana = &spans[0].extraction;
+        let names: Vec<&str> = ana.entities.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Ana", "Centro Norte", "Luis", "Marta", "Cocina"]);
+        let relations: Vec<(&str, &str, &str)> = ana.relations.iter()
+            .map(|r| (r.source.as_str(), r.relation_type.as_str(), r.target.as_str()))
+            .collect();
+        assert!(relations.contains(&("Ana", "ATENDIDO_EN", "Centro Norte")));
+        assert!(relations.contains(&("Marta", "ACOMPAÑA_A", "Ana")));
+        assert!(relations.contains(&("Ana", "ASISTE_A", "Cocina")));
+        assert!(!relations.iter().any(|r| r.2 == "Búsqueda de empleo"));
+        // La fila es la evidencia de cada relación que sale de ella
+        assert!(ana.relations.iter().all(|r| r.evidence.as_deref() == payload.content.lines().next()));
+
+        // Los tramos apuntan a la línea de su fila en el contenido
+        let content: Vec<char> = payload.content.chars().collect();
+        let pedro: String = content[spans[1].char_start..spans[1].char_end].iter().collect();
+        assert!(pedro.starts_with("Participante: Pedro"));
+        let merged = extraction_for_chunk(&spans, 1, spans[1].char_start, spans[1].char_end);
+        assert!(merged.entities.iter().any(|e| e.name == "Informática" && e.category == "Taller"));
+        assert!(!merged.entities.iter().any(|e| e.name == "Ana"));
+
+        // Una extracción revisada en la vista previa sustituye a la del mapeo solo en su fragmento
+        let mut reviewed = spans.clone();
+        reviewed.push(StructuredSpan {
+            char_start: spans[1].char_start,
+            char_end: spans[1].char_end,
+            extraction: KnowledgeExtraction {
+                entities: vec![GraphEntity { name: "Pedro R.".to_string(), category: "Persona".to_string(), ..Default::default() }],
+                relations: Vec::new(),
+            },
+            chunk: Some(1),
+        });
+        let bound = extraction_for_chunk(&reviewed, 1, spans[1].char_start, spans[1].char_end);
+        assert_eq!(bound.entities.len(), 1);
+        assert_eq!(bound.entities[0].name, "Pedro R.");
+        let other = extraction_for_chunk(&reviewed, 0, spans[0].char_start, spans[1].char_end);
+        assert!(other.entities.iter().any(|e| e.name == "Ana"));
+        assert!(!other.entities.iter().any(|e| e.name == "Pedro R."));
+    }
+
+    #[test]
+    fn rejects_relations_on_undeclared_columns_and_missing_headers() {
+        let invalid = "entities:\n  - {column: A, category: X}\nrelations:\n  - {source: A, target: B, type: REL}\n";
+        assert!(TabularMapping::from_yaml_str(invalid).is_err());
+        let leading_digit = "entities:\n  - {column: A, category: X}\n  - {column: B, category: Y}\nrelations:\n  - {source: A, target: B, type: 2_REL}\n";
+        assert!(TabularMapping::from_yaml_str(leading_digit).is_err());
+
+        let mapping = TabularMapping::from_yaml_str("entities:\n  - {column: Nombre, category: Persona}\n").unwrap();
+        assert!(mapping.apply(rows(&[&["Otra"], &["valor"]])).is_err());
+        assert!(TabularMapping::from_named("../secretos").is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use std::sync::Arc;
+use std::time::Duration;
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+use tokio::task;
+use tracing::{info, warn};
+use crate::domain::{
+    ports::KGRepository,
+    models::{DocumentOrigin, IngestionPayload, UrlIngestionRequest, WebSource, parse_metadata},
+    errors::AppError
+};
+use crate::application::ingestion_jobs::IngestionJobQueue;
+use crate::infrastructure::transmutation::{DocumentTransmuter, SupportedFormat};
+use crate::infrastructure::web::{self, WebFetcher};
+
+/// Cada cuánto se revisa qué fuentes programadas toca re-descargar
+const REFRESH_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Ingesta desde la web: descarga páginas (o las de un sitemap), extrae su texto y las encola
+/// como documentos cuyo nombre es la URL. Así, re-descargar una página cambiada crea una nueva
+/// versión del mismo documento y una sin cambios se descarta por su huella.
+pub struct WebIngestionService {
+    fetcher: WebFetcher,
+    repo: Arc<dyn KGRepository>,
+    jobs: Arc<IngestionJobQueue>,
+}
+
+impl WebIngestionService {
+    pub fn new(fetcher: WebFetcher, repo: Arc<dyn KGRepository>, jobs: Arc<IngestionJobQueue>) -> Self {
+        Self { fetcher, repo, jobs }
+    }
+
+    /// Valida la petición (URL http(s) y metadatos) antes de empezar a descargar
+    pub fn prepare(&self, request: &UrlIngestionRequest, uploaded_by: &str) -> Result<WebSource, AppError> {
+        web::parse_http_url(&request.url)?;
+        let metadata = parse_metadata(&request.metadata)?;
+        Ok(WebSource::new(request, metadata, uploaded_by))
+    }
+
+    /// Primera descarga de una fuente; si pide re-descarga periódica queda registrada.
+    /// Devuelve cuántos documentos se han encolado.
+    pub async fn ingest(&self, mut source: WebSource, progress_tx: &mpsc::Sender<String>) -> Result<usize, AppError> {
+        let result = self.fetch_source(&mut source, Some(progress_tx)).await;
+        if let Some(hours) = source.refresh_hours {
+            self.repo.save_web_source(&source).await?;
+            let _ = progress_tx.send(format!("🔁 Fuente {} programada: se re-descarga cada {} h.", source.id, hours)).await;
+        }
+        result
+    }
+
+    /// Arranca la tarea que re-descarga las fuentes programadas cuando vence su periodo
+    pub fn spawn_refresh_scheduler(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REFRESH_CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.refresh_due().await {
+                    warn!("⚠️ Error revisando fuentes web: {}", e);
+                }
+            }
+        });
+    }
+
+    async fn refresh_due(&self) -> Result<(), AppError> {
+        let now = chrono::Utc::now();
+        let due: Vec<WebSource> = self.repo.list_web_sources().await?
+            .into_iter()
+            .filter(|source| source.is_due(now))
+            .collect();
+        for mut source in due {
+            info!("🔁 Re-descargando fuente web {} ({}).", source.id, source.url);
+            match self.fetch_source(&mut source, None).await {
+                Ok(queued) => info!("🔁 {}: {} página(s) nuevas o cambiadas.", source.url, queued),
+                Err(e) => warn!("⚠️ Fuente web {} falló: {}", source.url, e),
+            }
+            self.repo.save_web_source(&source).await?;
+        }
+        Ok(())
+    }
+
+    /// Descarga la fuente y anota en ella cuándo y con qué resultado
+    async fn fetch_source(&self, source: &mut WebSource, progress_tx: Option<&mpsc::Sender<String>>) -> Result<usize, AppError> {
+        source.last_fetched_at = Some(chrono::Utc::now().to_rfc3339());
+        let result = self.ingest_pages(source, progress_tx).await;
+        source.last_error = result.as_ref().err().map(|e| e.to_string());
+        result
+    }
+
+    async fn ingest_pages(&self, source: &WebSource, progress_tx: Option<&mpsc::Sender<String>>) -> Result<usize, AppError> {
+        let progress = |msg: String| async move {
+            if let Some(tx) = progress_tx {
+                let _ = tx.send(msg).await;
+            }
+        };
+
+        let urls = if source.sitemap {
+            progress(format!("🗺️ Leyendo sitemap {}...", source.url)).await;
+            let urls = self.fetcher.sitemap_urls(&source.url).await?;
+            progress(format!("🗺️ {} página(s) en el sitemap (máximo {}).", urls.len(), self.fetcher.limits().max_pages)).await;
+            urls
+        } else {
+            vec![source.url.clone()]
+        };
+
+        let total = urls.len();
+        let mut queued = 0;
+        for (index, url) in urls.iter().enumerate() {
+            progress(format!("🌐 [{}/{}] {}", index + 1, total, url)).await;
+            match self.ingest_page(source, url, progress_tx).await {
+                Ok(true) => queued += 1,
+                Ok(false) => {},
+                // Una página suelta que falla es un error de la fuente; en un sitemap se sigue con las demás
+                Err(e) if !source.sitemap => return Err(e),
+                Err(e) => progress(format!("❌ {}", e)).await,
+            }
+        }
+        Ok(queued)
+    }
+
+    /// Descarga y encola una página; `false` si no ha cambiado desde la última ingesta
+    async fn ingest_page(&self, source: &WebSource, url: &str, progress_tx: Option<&mpsc::Sender<String>>) -> Result<bool, AppError> {
+        let page = self.fetcher.fetch(url).await?;
+
+        let is_html = page.content_type.is_empty() || page.content_type == "text/html" || page.content_type == "application/xhtml+xml";
+        // Los enlaces a documentos (PDF, DOCX...) se extraen por la extensión de su ruta
+        let path = url::Url::parse(&page.url).map(|u| u.path().to_string()).unwrap_or_default();
+        if !is_html && SupportedFormat::from_filename(&path).is_none() {
+            return Err(AppError::ValidationError(format!("{}: tipo de contenido no soportado ({})", page.url, page.content_type)));
+        }
+        let bytes = page.bytes;
+        let text = task::spawn_blocking(move || {
+            if is_html {
+                // Las páginas no siempre declaran UTF-8: mejor texto con algún carácter perdido que nada
+                DocumentTransmuter::parse_html(String::from_utf8_lossy(&bytes).as_bytes())
+            } else {
+                DocumentTransmuter::transmute(&path, &bytes).map(|doc| doc.text)
+            }
+        }).await
+        .map_err(|e| AppError::ParseError(format!("Error Thread: {}", e)))?
+        .map_err(|e| AppError::ParseError(format!("{}: {}", page.url, e)))?;
+
+        if text.trim().len() <= 5 {
+            return Err(AppError::ValidationError(format!("{}: sin contenido válido", page.url)));
+        }
+
+        // Huella del texto extraído y no de los bytes: el marcado dinámico (scripts, fechas de
+        // generación...) cambia en cada descarga sin que cambie el contenido. Se compara solo con
+        // la versión anterior de esta misma URL: otra página con el mismo texto no cuenta
+        let content_hash = format!("{:x}", Sha256::digest(text.as_bytes()));
+        let previous = self.repo.find_document_version(&page.url, &source.uploaded_by).await?;
+        if previous.is_some_and(|doc| doc.content_hash == content_hash) {
+            if let Some(tx) = progress_tx {
+                let _ = tx.send("⏭️ Sin cambios desde la última descarga.".to_string()).await;
+            }
+            return Ok(false);
+        }
+
+        let mut metadata = source.metadata.clone();
+        metadata.insert("source_url".to_string(), page.url.clone());
+        let origin = DocumentOrigin {
+            filename: page.url,
+            mime_type: if page.content_type.is_empty() { "text/html".to_string() } else { page.content_type },
+            uploaded_by: source.uploaded_by.clone(),
+            content_hash,
+            // Volver a descargar una URL actualiza su documento
+            replace: true,
+        };
+        let payload = IngestionPayload { content: text, metadata, ..Default::default() };
+        let job = self.jobs.enqueue(origin, payload, source.chunking.clone(), progress_tx.cloned()).await?;
+        if let Some(tx) = progress_tx {
+            let _ = tx.send(format!("🆔 Trabajo {} en cola ({}).", job.id, job.filename)).await;
+        }
+        Ok(true)
+    }
+}
@@ -21,6 +21,9 @@ pub enum AppError {
     #[error("Validation error: {0}")]
     ValidationError(String),
     
+    #[error("Not found: {0}")]
+    NotFound(String),
+    
     #[error("Parsing error: {0}")]
     ParseError(String),
     
@@ -35,6 +38,7 @@ impl IntoResponse for AppError {
 
         let (status, error_message) = match self {
             AppError::ValidationError(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            AppError::NotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
             AppError::SafetyGuardError => (StatusCode::FORBIDDEN, self.to_string()),
             AppError::ConfigError(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
             AppError::ParseError(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Error procesando datos".to_string()),
@@ -3,6 +3,10 @@ use secrecy::SecretString;
 use utoipa::{ToSchema, IntoParams}; 
 use validator::Validate;
 use std::fmt;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+use crate::domain::errors::AppError;
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
 
 // --- 1. SEGURIDAD & USUARIOS (NUEVO) ---
 
@@ -62,36 +66,733 @@ pub struct AIConfig {
     pub embedding_dim: usize,
     #[validate(url)]
     pub base_url: Option<String>, 
+    /// Idioma de las transcripciones (ISO-639-1, p.ej. "es"); sin valor, Whisper lo detecta
+    #[serde(default)]
+    pub transcription_language: Option<String>,
 }
 
 // --- 3. CORE DEL GRAFO (GraphRAG) ---
 
-#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Default)]
 pub struct GraphEntity {
     pub name: String,
     pub category: String, 
+    /// Datos del texto sobre la entidad (edad, puntuación PHQ-9...)
+    #[serde(default, skip_serializing_if = "Attributes::is_empty")]
+    pub attributes: Attributes,
 }
 
-#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
-pub struct GraphRelation {
+impl GraphEntity {
+    /// Añade los atributos de otra mención de la misma entidad sin pisar los que ya tiene
+    pub fn merge_attributes(&mut self, attributes: Attributes) {
+        for (key, value) in attributes {
+            self.attributes.entry(key).or_insert(value);
+        }
+    }
+}
+
+/// Valor tipado de un atributo; las fechas van como texto ISO ("2024-03", "2024-03-15")
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    Boolean(bool),
+    Integer(i64),
+    Number(f64),
+    Text(String),
+}
+
+/// Propiedades clave/valor de entidades y relaciones. En Neo4j se guardan como
+/// propiedades `attr_<clave>` del nodo o de la arista.
+pub type Attributes = BTreeMap<String, AttributeValue>;
+
+/// Normaliza la clave de un atributo a un nombre de propiedad válido:
+/// "Fecha de diagnóstico" -> "fecha_de_diagnostico". `None` si no queda nada.
+pub fn attribute_key(raw: &str) -> Option<String> {
+    let key = entity_key(raw).replace(' ', "_");
+    (!key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')).then_some(key)
+}
+
+/// Atributos con claves normalizadas, sin textos vacíos (lo que devuelve el LLM)
+pub fn normalize_attributes(attributes: Attributes) -> Attributes {
+    attributes.into_iter()
+        .filter(|(_, value)| !matches!(value, AttributeValue::Text(text) if text.trim().is_empty()))
+        .filter_map(|(key, value)| attribute_key(&key).map(|key| (key, value)))
+        .collect()
+}
+
+/// Clave de comparación de nombres de entidad: minúsculas, sin tildes ni signos y con los
+/// espacios normalizados ("Juan  Pérez." -> "juan perez")
+pub fn entity_key(name: &str) -> String {
+    let folded: String = name.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Etiquetas propias del sistema que una categoría no puede ocupar
+const RESERVED_LABELS: [&str; 9] = ["Entity", "EntityAlias", "Document", "DocumentChunk", "IngestionJob", "WebSource", "User", "Conversation", "Message"];
+
+/// Etiqueta Neo4j de una categoría: palabras sin tildes ni signos en PascalCase
+/// ("Recurso comunitario" -> `RecursoComunitario`). `None` si no queda un identificador
+/// válido o coincide con una etiqueta del sistema.
+pub fn category_label(category: &str) -> Option<String> {
+    let label: String = entity_key(category).split(' ')
+        .filter(|word| word.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|first| first.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+        })
+        .collect();
+    // Las que ya vienen en PascalCase ("CommunityResource") se conservan tal cual
+    let label = if category.chars().all(|c| c.is_ascii_alphanumeric()) { category.to_string() } else { label };
+    let valid = label.chars().next().is_some_and(|c| c.is_ascii_alphabetic()) && label.chars().all(|c| c.is_ascii_alphanumeric());
+    (valid && !RESERVED_LABELS.contains(&label.as_str())).then_some(label)
+}
+
+/// Nombre alternativo de una entidad, enlazado con `ALIAS_OF` a su nodo canónico
+#[derive(Debug, Serialize, ToSchema, Clone, PartialEq)]
+pub struct EntityAlias {
+    pub alias: String,
+    pub canonical: String,
+    /// Cómo se identificó: "similitud", "difusa" o "fusión"
+    pub method: String,
+}
+
+/// Fusión manual de dos entidades (POST /api/curation/merge)
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct EntityMergeRequest {
+    /// Entidad que desaparece; su nombre queda como alias
     pub source: String,
+    /// Entidad que conserva relaciones y menciones de ambas
     pub target: String,
-    pub relation_type: String, 
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Default)]
+pub struct GraphRelation {
+    pub source: String,
+    pub target: String,
+    pub relation_type: String, 
+    /// Seguridad del extractor en la relación (0-1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+    /// Fragmento literal del texto que afirma la relación
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evidence: Option<String>,
+    /// Datos de la relación (dosis, fecha de diagnóstico...)
+    #[serde(default, skip_serializing_if = "Attributes::is_empty")]
+    pub attributes: Attributes,
+}
+
+/// Procedencia acumulada de una relación del grafo: qué fragmentos la afirman y con qué respaldo
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Default)]
+pub struct RelationProvenance {
+    /// IDs de los `DocumentChunk` que la afirman
+    pub source_chunks: Vec<String>,
+    /// Veces que se ha extraído (una por fragmento)
+    pub occurrences: usize,
+    /// Primera y última extracción (RFC 3339)
+    pub extracted_at: Option<String>,
+    pub last_extracted_at: Option<String>,
+    /// Modelo de la última extracción ("estructurado" si no intervino el LLM)
+    pub model: Option<String>,
+    /// Mayor confianza declarada por el extractor
+    pub confidence: Option<f32>,
+    /// Citas literales que la respaldan (las primeras distintas)
+    pub evidence: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Default)]
 pub struct KnowledgeExtraction {
     pub entities: Vec<GraphEntity>,
     pub relations: Vec<GraphRelation>,
 }
 
+impl KnowledgeExtraction {
+    /// Comprobaciones para extracciones escritas a mano (vista previa revisada): el tipo de
+    /// relación se interpola en Cypher y debe ser un identificador
+    pub fn validate(&self) -> Result<(), AppError> {
+        if let Some(entity) = self.entities.iter().find(|e| e.name.trim().is_empty() || e.category.trim().is_empty()) {
+            return Err(AppError::ValidationError(format!("Entidad sin nombre o sin categoría: {:?}", entity)));
+        }
+        let keys = self.entities.iter().flat_map(|e| e.attributes.keys())
+            .chain(self.relations.iter().flat_map(|r| r.attributes.keys()));
+        for key in keys {
+            validate_metadata_key(key).map_err(|_| AppError::ValidationError(format!("Clave de atributo inválida: '{}' (usa letras, dígitos y _)", key)))?;
+        }
+        for relation in &self.relations {
+            let relation_type = &relation.relation_type;
+            if !is_valid_relation_type(relation_type) {
+                return Err(AppError::ValidationError(format!("Tipo de relación inválido: '{}'", relation_type)));
+            }
+            if relation.source.trim().is_empty() || relation.target.trim().is_empty() {
+                return Err(AppError::ValidationError(format!("Relación '{}' sin origen o destino", relation_type)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Empieza por letra y sigue con letras, dígitos, `_` y espacios: se guarda como
+/// `TIPO_EN_MAYUSCULAS` dentro del Cypher
+pub fn is_valid_relation_type(relation_type: &str) -> bool {
+    relation_type.trim().chars().next().is_some_and(char::is_alphabetic)
+        && relation_type.chars().all(|c| c.is_alphanumeric() || c == '_' || c == ' ')
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct IngestionRequest {
     #[validate(length(min = 10))]
     pub content: String,
+    /// Objeto plano (fuente, autor, fecha, programa...); en /api/ingest llega como campo multipart `metadata`
     pub metadata: serde_json::Value,
 }
 
+/// Metadatos de un documento como texto por clave. Se guardan en el `Document` y en sus
+/// `DocumentChunk` (propiedades `meta_<clave>`) para poder filtrar la búsqueda.
+pub type DocumentMetadata = BTreeMap<String, String>;
+
+/// Valida y normaliza los metadatos recibidos: objeto JSON plano con claves alfanuméricas
+/// (o `_`) y valores escalares. Los `null` se descartan.
+pub fn parse_metadata(value: &serde_json::Value) -> Result<DocumentMetadata, AppError> {
+    let object = match value {
+        serde_json::Value::Null => return Ok(DocumentMetadata::new()),
+        serde_json::Value::Object(object) => object,
+        _ => return Err(AppError::ValidationError("Los metadatos deben ser un objeto JSON".to_string())),
+    };
+    let mut metadata = DocumentMetadata::new();
+    for (key, value) in object {
+        validate_metadata_key(key)?;
+        let text = match value {
+            serde_json::Value::Null => continue,
+            serde_json::Value::String(s) => s.trim().to_string(),
+            serde_json::Value::Number(n) => n.to_string(),
+            serde_json::Value::Bool(b) => b.to_string(),
+            _ => return Err(AppError::ValidationError(format!("El metadato '{}' debe ser un valor simple", key))),
+        };
+        metadata.insert(key.clone(), text);
+    }
+    Ok(metadata)
+}
+
+/// Las claves acaban en nombres de propiedad de Neo4j
+pub fn validate_metadata_key(key: &str) -> Result<(), AppError> {
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(AppError::ValidationError(format!("Clave de metadato inválida: '{}'", key)));
+    }
+    Ok(())
+}
+
+/// Filtro de búsqueda por metadatos. Los valores se comparan como texto,
+/// así que las fechas ISO se ordenan bien: `{"from": "2025", "before": "2026"}` es "en 2025".
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Default)]
+pub struct MetadataFilter {
+    /// Igualdad exacta por clave, p.ej. `{"program": "Inclusión"}`
+    #[serde(default)]
+    pub equals: BTreeMap<String, String>,
+    /// Rangos por clave, p.ej. `{"date": {"from": "2025-01-01", "before": "2026-01-01"}}`
+    #[serde(default)]
+    pub ranges: BTreeMap<String, MetadataRange>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Default)]
+pub struct MetadataRange {
+    /// Límite inferior inclusivo
+    #[serde(default)]
+    pub from: Option<String>,
+    /// Límite superior exclusivo
+    #[serde(default)]
+    pub before: Option<String>,
+}
+
+impl MetadataFilter {
+    pub fn validate(&self) -> Result<(), AppError> {
+        self.equals.keys().chain(self.ranges.keys()).try_for_each(|key| validate_metadata_key(key))
+    }
+}
+
+// --- 3.1 DOCUMENTOS FUENTE ---
+
+/// Origen de un documento a ingerir (quién lo sube y qué es)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DocumentOrigin {
+    pub filename: String,
+    pub mime_type: String,
+    pub uploaded_by: String,
+    /// SHA-256 de los bytes originales subidos (no del texto extraído)
+    pub content_hash: String,
+    /// Sustituye al documento anterior con el mismo nombre y del mismo autor: solo se procesan
+    /// los fragmentos modificados y se retiran los que desaparecen. Sin él, siempre es un documento nuevo.
+    #[serde(default)]
+    pub replace: bool,
+}
+
+/// Nodo `Document` persistido: agrupa los `DocumentChunk` vía `PART_OF`
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct SourceDocument {
+    pub id: String,
+    pub filename: String,
+    pub mime_type: String,
+    pub content_hash: String,
+    pub uploaded_by: String,
+    pub ingested_at: String,
+    pub chunk_count: usize,
+    #[serde(default)]
+    pub metadata: DocumentMetadata,
+}
+
+/// Tramo del texto extraído que procede de una página del original (offsets en caracteres)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PageSpan {
+    pub page: u32,
+    pub char_start: usize,
+    pub char_end: usize,
+}
+
+/// Tramo del texto extraído que procede de un segmento de una grabación (tiempos en segundos)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TimeSpan {
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub char_start: usize,
+    pub char_end: usize,
+}
+
+/// Transcripción de audio por segmentos (Whisper `verbose_json`)
+#[derive(Debug, Clone)]
+pub struct Transcription {
+    pub text: String,
+    /// Idioma pedido o detectado por el modelo
+    pub language: Option<String>,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TranscriptSegment {
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub text: String,
+}
+
+/// Ubicación de un chunk dentro del documento original
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Default, PartialEq)]
+pub struct ChunkLocation {
+    pub char_start: usize,
+    pub char_end: usize,
+    pub page_start: Option<u32>,
+    pub page_end: Option<u32>,
+    /// Segundos de la grabación que abarca el chunk (transcripciones de audio)
+    #[serde(default)]
+    pub time_start: Option<f64>,
+    #[serde(default)]
+    pub time_end: Option<f64>,
+}
+
+impl ChunkLocation {
+    /// Etiqueta legible para citas ("pág. 4", "págs. 4-5")
+    pub fn page_label(&self) -> Option<String> {
+        match (self.page_start, self.page_end) {
+            (Some(start), Some(end)) if end > start => Some(format!("págs. {}-{}", start, end)),
+            (Some(start), _) => Some(format!("pág. {}", start)),
+            _ => None,
+        }
+    }
+
+    /// Etiqueta de tiempo para citas de grabaciones ("00:01:05-00:02:40")
+    pub fn time_label(&self) -> Option<String> {
+        let start = self.time_start?;
+        let end = self.time_end.unwrap_or(start);
+        Some(format!("{}-{}", format_timestamp(start), format_timestamp(end)))
+    }
+}
+
+/// Segundos como "hh:mm:ss"
+pub fn format_timestamp(secs: f64) -> String {
+    let total = secs.max(0.0) as u64;
+    format!("{:02}:{:02}:{:02}", total / 3600, total % 3600 / 60, total % 60)
+}
+
+/// Texto a ingerir junto con su mapa de páginas (persistido con el trabajo para poder reanudar)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct IngestionPayload {
+    pub content: String,
+    pub pages: Vec<PageSpan>,
+    /// Tiempos de los segmentos de una transcripción de audio
+    #[serde(default)]
+    pub segments: Vec<TimeSpan>,
+    /// Cabecera que acompaña a cada fragmento en la extracción (p.ej. remitente y destinatarios de un correo)
+    #[serde(default)]
+    pub context: Option<String>,
+    /// Grafo ya conocido por tramos del contenido (tablas con mapeo de columnas).
+    /// Con `Some`, cada fragmento guarda las entidades de sus tramos y no se llama al LLM.
+    #[serde(default)]
+    pub structured: Option<Vec<StructuredSpan>>,
+    /// Metadatos del documento (ver `parse_metadata`)
+    #[serde(default)]
+    pub metadata: DocumentMetadata,
+}
+
+/// Entidades y relaciones de un tramo del contenido (p.ej. una fila), en caracteres
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StructuredSpan {
+    pub char_start: usize,
+    pub char_end: usize,
+    pub extraction: KnowledgeExtraction,
+    /// Posición del fragmento al que pertenece en exclusiva (vista previa revisada);
+    /// sin valor, aplica a todo fragmento que se solape con el tramo
+    #[serde(default)]
+    pub chunk: Option<usize>,
+}
+
+/// Fragmento listo para persistir, con su posición dentro del documento
+#[derive(Debug, Clone)]
+pub struct ChunkRecord {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub position: usize,
+    pub content: String,
+    pub content_hash: String,
+    pub embedding: Vec<f32>,
+    pub location: ChunkLocation,
+    /// Metadatos del documento: el fragmento los lleva desde que se crea (`meta_*`), así los
+    /// filtros de búsqueda le aplican aunque el trabajo no llegue a terminar
+    pub metadata: DocumentMetadata,
+}
+
+/// Huella de un chunk ya persistido (para re-ingesta incremental)
+#[derive(Debug, Clone)]
+pub struct StoredChunkHash {
+    pub chunk_id: String,
+    pub content_hash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct DocumentChunkSummary {
+    pub id: String,
+    pub position: usize,
+    pub preview: String,
+    pub entities: Vec<String>,
+    pub location: ChunkLocation,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct DocumentDetail {
+    pub document: SourceDocument,
+    pub chunks: Vec<DocumentChunkSummary>,
+}
+
+// --- 3.2 ESTRATEGIAS DE CHUNKING ---
+
+/// Estrategia de troceado y sus parámetros (seleccionable por petición o por formato)
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum ChunkingConfig {
+    /// Ventana deslizante de caracteres que no corta palabras
+    FixedWindow { size: usize, overlap: usize },
+    /// Agrupa frases completas hasta `max_chars`
+    Sentence { max_chars: usize },
+    /// Agrupa párrafos (y filas, si un párrafo es demasiado largo) hasta `max_chars`
+    Paragraph { max_chars: usize },
+    /// Una sección por encabezado Markdown, repitiendo la ruta de encabezados en cada chunk
+    Markdown { max_chars: usize },
+    /// Ventana de tokens (cl100k_base) con solapamiento
+    Tokens { max_tokens: usize, overlap_tokens: usize },
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        // 1500 caracteres ~= 300-400 tokens (Sweet spot para embeddings)
+        ChunkingConfig::FixedWindow { size: 1500, overlap: 200 }
+    }
+}
+
+// --- 3.3 TRABAJOS DE INGESTA ---
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Failed,
+    Done,
+    Cancelled,
+    /// Vista previa a la espera de revisión: no se ejecuta hasta que se confirma
+    Preview,
+}
+
+impl fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JobStatus::Queued => write!(f, "Queued"),
+            JobStatus::Running => write!(f, "Running"),
+            JobStatus::Failed => write!(f, "Failed"),
+            JobStatus::Done => write!(f, "Done"),
+            JobStatus::Cancelled => write!(f, "Cancelled"),
+            JobStatus::Preview => write!(f, "Preview"),
+        }
+    }
+}
+
+impl JobStatus {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "Running" => JobStatus::Running,
+            "Failed" => JobStatus::Failed,
+            "Done" => JobStatus::Done,
+            "Cancelled" => JobStatus::Cancelled,
+            "Preview" => JobStatus::Preview,
+            _ => JobStatus::Queued,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        matches!(self, JobStatus::Queued | JobStatus::Running)
+    }
+}
+
+/// Registro persistente de un trabajo de ingesta (sobrevive a reinicios del servidor)
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct IngestionJob {
+    pub id: String,
+    pub filename: String,
+    pub mime_type: String,
+    pub uploaded_by: String,
+    pub content_hash: String,
+    /// Ver `DocumentOrigin::replace`
+    #[serde(default)]
+    pub replace: bool,
+    pub status: JobStatus,
+    pub chunking: ChunkingConfig,
+    pub document_id: Option<String>,
+    pub total_chunks: usize,
+    /// Chunks terminados en orden: un reinicio retoma desde aquí
+    pub completed_chunks: usize,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl IngestionJob {
+    pub fn new(origin: DocumentOrigin, chunking: ChunkingConfig) -> Self {
+        let now = chrono::Utc::now().to_rfc3339();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            filename: origin.filename,
+            mime_type: origin.mime_type,
+            uploaded_by: origin.uploaded_by,
+            content_hash: origin.content_hash,
+            replace: origin.replace,
+            status: JobStatus::Queued,
+            chunking,
+            document_id: None,
+            total_chunks: 0,
+            completed_chunks: 0,
+            error: None,
+            created_at: now.clone(),
+            updated_at: now,
+        }
+    }
+
+    pub fn origin(&self) -> DocumentOrigin {
+        DocumentOrigin {
+            filename: self.filename.clone(),
+            mime_type: self.mime_type.clone(),
+            uploaded_by: self.uploaded_by.clone(),
+            content_hash: self.content_hash.clone(),
+            replace: self.replace,
+        }
+    }
+}
+
+/// Fragmento de una vista previa con la extracción que se escribiría en el grafo
+#[derive(Debug, Serialize, ToSchema, Clone)]
+pub struct ChunkPreview {
+    pub position: usize,
+    pub text: String,
+    pub location: ChunkLocation,
+    pub extraction: KnowledgeExtraction,
+}
+
+/// Resultado de una ingesta en modo vista previa (GET /api/ingest/preview/{id})
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IngestionPreview {
+    pub job: IngestionJob,
+    pub chunks: Vec<ChunkPreview>,
+}
+
+/// Confirmación de una vista previa: las extracciones indicadas sustituyen a las propuestas;
+/// los fragmentos no incluidos se guardan tal como se previsualizaron
+#[derive(Debug, Deserialize, ToSchema, Default)]
+pub struct PreviewCommitRequest {
+    #[serde(default)]
+    pub chunks: Vec<ChunkExtractionEdit>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ChunkExtractionEdit {
+    pub position: usize,
+    pub extraction: KnowledgeExtraction,
+}
+
+// --- 3.4 FUENTES WEB ---
+
+/// Petición de /api/ingest/url: una página o un sitemap con las páginas a ingerir
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UrlIngestionRequest {
+    pub url: String,
+    /// La URL es un sitemap XML (o un índice de sitemaps): se ingiere cada página que lista
+    #[serde(default)]
+    pub sitemap: bool,
+    /// Re-descarga periódica en horas; las páginas cambiadas se ingieren como nueva versión
+    #[serde(default)]
+    pub refresh_hours: Option<u32>,
+    /// Objeto plano de metadatos (ver `parse_metadata`); se añade `source_url` con la URL de cada página
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+    #[serde(default)]
+    pub chunking: Option<ChunkingConfig>,
+}
+
+/// Página o sitemap registrado para re-descargarse cada `refresh_hours` (nodo `WebSource`)
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct WebSource {
+    pub id: String,
+    pub url: String,
+    pub sitemap: bool,
+    pub refresh_hours: Option<u32>,
+    pub uploaded_by: String,
+    pub metadata: DocumentMetadata,
+    pub chunking: Option<ChunkingConfig>,
+    pub created_at: String,
+    pub last_fetched_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+impl WebSource {
+    pub fn new(request: &UrlIngestionRequest, metadata: DocumentMetadata, uploaded_by: &str) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            url: request.url.trim().to_string(),
+            sitemap: request.sitemap,
+            refresh_hours: request.refresh_hours.filter(|hours| *hours > 0),
+            uploaded_by: uploaded_by.to_string(),
+            metadata,
+            chunking: request.chunking.clone(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            last_fetched_at: None,
+            last_error: None,
+        }
+    }
+
+    /// Toca re-descargarla: tiene periodo y ya pasó desde la última descarga
+    pub fn is_due(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        let Some(hours) = self.refresh_hours else { return false };
+        match self.last_fetched_at.as_deref().and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok()) {
+            Some(last) => now.signed_duration_since(last) >= chrono::Duration::hours(hours as i64),
+            None => true,
+        }
+    }
+}
+
+// --- 3.5 CURACIÓN ---
+
+/// Estado de revisión de entidades y relaciones. Sin revisión obligatoria todo nace aprobado
+/// (las propiedades ausentes cuentan como `Approved`); con ella, lo extraído nace `Pending`
+/// y la búsqueda, la visualización y la exportación lo ignoran hasta que se aprueba.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Copy, PartialEq)]
+pub enum ReviewStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ReviewStatus {
+    /// Valor de la propiedad `review_status` en Neo4j
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewStatus::Pending => "PENDING",
+            ReviewStatus::Approved => "APPROVED",
+            ReviewStatus::Rejected => "REJECTED",
+        }
+    }
+}
+
+/// Entidad extraída a la espera de revisión
+#[derive(Debug, Serialize, ToSchema, Clone)]
+pub struct PendingEntity {
+    pub id: String,
+    pub name: String,
+    pub category: String,
+    pub extracted_at: String,
+    /// Documentos cuyos fragmentos la mencionan
+    pub documents: Vec<String>,
+}
+
+/// Relación extraída a la espera de revisión
+#[derive(Debug, Serialize, ToSchema, Clone)]
+pub struct PendingRelation {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    pub relation_type: String,
+    pub extracted_at: String,
+    /// Algún extremo está pendiente o rechazado: aunque se apruebe no será visible hasta que lo estén ambos
+    pub blocked_by_entities: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema, Default)]
+pub struct CurationQueue {
+    pub entities: Vec<PendingEntity>,
+    pub relations: Vec<PendingRelation>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CurationAction {
+    Approve,
+    Reject,
+    /// Corrige los campos indicados y aprueba
+    Edit,
+}
+
+/// Decisión del curador sobre un elemento pendiente. En `Edit`, `name` y `category` aplican a
+/// entidades y `relation_type` a relaciones; los campos ausentes se conservan.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CurationDecision {
+    pub action: CurationAction,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub relation_type: Option<String>,
+}
+
+impl CurationDecision {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.action != CurationAction::Edit {
+            return Ok(());
+        }
+        if [&self.name, &self.category].into_iter().flatten().any(|v| v.trim().is_empty()) {
+            return Err(AppError::ValidationError("El nombre y la categoría no pueden quedar vacíos".to_string()));
+        }
+        if let Some(relation_type) = &self.relation_type {
+            if !is_valid_relation_type(relation_type) {
+                return Err(AppError::ValidationError(format!("Tipo de relación inválido: '{}'", relation_type)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Estado resultante: editar también aprueba
+    pub fn status(&self) -> ReviewStatus {
+        match self.action {
+            CurationAction::Reject => ReviewStatus::Rejected,
+            CurationAction::Approve | CurationAction::Edit => ReviewStatus::Approved,
+        }
+    }
+}
+
 // --- 4. VISUALIZACIÓN ---
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -99,6 +800,8 @@ pub struct VisNode {
     pub id: String,
     pub label: String,
     pub group: String,
+    #[serde(skip_serializing_if = "Attributes::is_empty")]
+    pub attributes: Attributes,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -106,6 +809,11 @@ pub struct VisEdge {
     pub from: String,
     pub to: String,
     pub label: String,
+    #[serde(skip_serializing_if = "Attributes::is_empty")]
+    pub attributes: Attributes,
+    /// Solo en el vecindario de un concepto
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<RelationProvenance>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -119,6 +827,9 @@ pub struct GraphDataResponse {
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ChatRequest {
     pub message: String,
+    /// Restringe la evidencia a fragmentos con estos metadatos
+    #[serde(default)]
+    pub filter: MetadataFilter,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -128,6 +839,8 @@ pub struct SourceReference {
     pub short_content: String,
     pub relevance: f32,
     pub concepts: Vec<String>,
+    pub document_name: Option<String>,
+    pub location: Option<ChunkLocation>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -141,6 +854,21 @@ pub struct HybridContext {
     pub chunk_id: String,
     pub content: String,
     pub connected_entities: Vec<String>, 
+    pub document_name: Option<String>,
+    pub location: Option<ChunkLocation>,
+}
+
+impl HybridContext {
+    /// Referencia de cita: "informe.pdf, pág. 4" o "sesion.mp3, 00:01:05-00:02:40"
+    pub fn citation(&self) -> Option<String> {
+        let page = self.location.as_ref().and_then(|l| l.page_label().or_else(|| l.time_label()));
+        match (&self.document_name, page) {
+            (Some(doc), Some(page)) => Some(format!("{}, {}", doc, page)),
+            (Some(doc), None) => Some(doc.clone()),
+            (None, Some(page)) => Some(page),
+            (None, None) => None,
+        }
+    }
 }
 
 // --- 6. INFERENCIA & EXPORTACIÓN ---
@@ -163,7 +891,16 @@ pub struct ExportedGraph {
     pub generated_at: String,
     pub domain: String,
     pub nodes: Vec<GraphEntity>,
-    pub edges: Vec<GraphRelation>,
+    pub edges: Vec<ExportedRelation>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportedRelation {
+    pub source: String,
+    pub target: String,
+    pub relation_type: String,
+    pub attributes: Attributes,
+    pub provenance: RelationProvenance,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -237,6 +974,9 @@ pub struct CypherToolConfig {
 pub struct AgentChatRequest {
     pub agent_id: String,
     pub message: String,
+    /// Restringe el contexto RAG a fragmentos con estos metadatos
+    #[serde(default)]
+    pub filter: MetadataFilter,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -254,12 +994,12 @@ pub enum MessageRole {
     System,
 }
 
-impl ToString for MessageRole {
-    fn to_string(&self) -> String {
+impl fmt::Display for MessageRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            MessageRole::User => "user".to_string(),
-            MessageRole::Assistant => "assistant".to_string(),
-            MessageRole::System => "system".to_string(),
+            MessageRole::User => write!(f, "user"),
+            MessageRole::Assistant => write!(f, "assistant"),
+            MessageRole::System => write!(f, "system"),
         }
     }
 }
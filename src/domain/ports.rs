@@ -2,22 +2,82 @@ use async_trait::async_trait;
 use crate::domain::models::{
     AIConfig, KnowledgeExtraction, GraphDataResponse, HybridContext, 
     InferredRelation, InferenceResult, ExportedGraph, User,
-    ChatHistoryMessage, MessageRole // Importante: importar los nuevos modelos
+    ChatHistoryMessage, MessageRole, // Importante: importar los nuevos modelos
+    SourceDocument, DocumentDetail, ChunkRecord, StoredChunkHash, IngestionJob, IngestionPayload, StructuredSpan, MetadataFilter, WebSource, Transcription,
+    CurationQueue, CurationDecision, EntityAlias
 };
+use std::collections::HashMap;
 use crate::domain::errors::AppError;
 use uuid::Uuid;
 
 #[async_trait]
 pub trait KGRepository: Send + Sync {
     // --- Capacidades Core: Grafo y Vectores ---
-    async fn save_chunk(&self, id: Uuid, content: &str, embedding: Vec<f32>) -> Result<(), AppError>;
-    async fn save_graph(&self, chunk_id: Uuid, data: KnowledgeExtraction) -> Result<(), AppError>;
+    async fn save_chunk(&self, chunk: ChunkRecord) -> Result<(), AppError>;
+    /// Guarda entidades y relaciones del fragmento; `extracted_by` (modelo) queda en la procedencia
+    async fn save_graph(&self, chunk_id: Uuid, data: KnowledgeExtraction, extracted_by: &str) -> Result<(), AppError>;
     async fn reset_database(&self) -> Result<(), AppError>;
     async fn create_indexes(&self, dim: usize) -> Result<(), AppError>;
     
+    // --- Capacidades Documentales: Procedencia de los chunks ---
+    async fn save_document(&self, document: &SourceDocument) -> Result<(), AppError>;
+    async fn list_documents(&self) -> Result<Vec<SourceDocument>, AppError>;
+    async fn get_document(&self, id: &str) -> Result<Option<DocumentDetail>, AppError>;
+    /// Borra el documento, sus chunks y las entidades que ningún otro chunk menciona
+    async fn delete_document(&self, id: &str) -> Result<bool, AppError>;
+
+    // --- Capacidades de Re-ingesta: Deduplicación por hash ---
+    async fn find_document_by_hash(&self, content_hash: &str) -> Result<Option<SourceDocument>, AppError>;
+    /// Última versión de un documento: mismo nombre y mismo autor
+    async fn find_document_version(&self, filename: &str, uploaded_by: &str) -> Result<Option<SourceDocument>, AppError>;
+    async fn get_chunk_hashes(&self, document_id: Uuid) -> Result<Vec<StoredChunkHash>, AppError>;
+    async fn update_chunk_position(&self, chunk_id: &str, document_id: Uuid, position: usize) -> Result<(), AppError>;
+    /// Elimina chunks que ya no existen en la nueva versión (y sus entidades huérfanas)
+    async fn retire_chunks(&self, chunk_ids: Vec<String>) -> Result<(), AppError>;
+
+    // --- Capacidades de Trabajos de Ingesta (persistentes) ---
+    async fn create_ingestion_job(&self, job: &IngestionJob, payload: &IngestionPayload) -> Result<(), AppError>;
+    async fn update_ingestion_job(&self, job: &IngestionJob) -> Result<(), AppError>;
+    async fn get_ingestion_job(&self, id: &str) -> Result<Option<IngestionJob>, AppError>;
+    async fn get_ingestion_job_payload(&self, id: &str) -> Result<Option<IngestionPayload>, AppError>;
+    /// Sustituye el grafo conocido del trabajo (vista previa revisada)
+    async fn update_ingestion_job_structured(&self, id: &str, spans: &[StructuredSpan]) -> Result<(), AppError>;
+    async fn list_ingestion_jobs(&self, limit: usize) -> Result<Vec<IngestionJob>, AppError>;
+    /// Trabajos en cola o a medio procesar (para reanudar tras un reinicio)
+    async fn list_unfinished_ingestion_jobs(&self) -> Result<Vec<IngestionJob>, AppError>;
+    async fn delete_ingestion_job(&self, id: &str) -> Result<(), AppError>;
+
+    // --- Capacidades de Fuentes Web: re-descarga programada ---
+    async fn save_web_source(&self, source: &WebSource) -> Result<(), AppError>;
+    async fn list_web_sources(&self) -> Result<Vec<WebSource>, AppError>;
+    async fn delete_web_source(&self, id: &str) -> Result<bool, AppError>;
+
+    // --- Capacidades de Curación: revisión humana de lo extraído ---
+    /// Entidades y relaciones `PENDING`, las más antiguas primero
+    async fn list_pending_curation(&self, limit: usize) -> Result<CurationQueue, AppError>;
+    /// Aplica la decisión registrando revisor y fecha; `false` si no existe ese elemento
+    async fn review_entity(&self, id: &str, decision: &CurationDecision, reviewer: &str) -> Result<bool, AppError>;
+    async fn review_relation(&self, id: &str, decision: &CurationDecision, reviewer: &str) -> Result<bool, AppError>;
+
+    // --- Capacidades de Resolución de Entidades: nombres canónicos y alias ---
+    /// Clave (`entity_key`) -> nombre de la entidad con esa clave o con un alias registrado
+    async fn find_entities_by_keys(&self, keys: &[String]) -> Result<HashMap<String, String>, AppError>;
+    /// Entidades de `category` con el nombre más parecido, de mayor a menor similitud
+    async fn find_similar_entities(&self, embedding: Vec<f32>, category: &str, limit: usize) -> Result<Vec<(String, f32)>, AppError>;
+    /// Registra alias (`ALIAS_OF`) y los embeddings de nombre de las entidades nuevas
+    async fn save_entity_resolution(&self, aliases: &[EntityAlias], embeddings: &[(String, Vec<f32>)]) -> Result<(), AppError>;
+    /// Traslada relaciones, menciones y alias de `source` a `target` y borra `source`,
+    /// cuyo nombre queda como alias; `false` si alguna de las dos no existe
+    async fn merge_entities(&self, source: &str, target: &str, merged_by: &str) -> Result<bool, AppError>;
+    /// Calcula la clave de las entidades anteriores a la resolución; devuelve cuántas
+    async fn backfill_entity_keys(&self) -> Result<usize, AppError>;
+    /// Añade la etiqueta de su categoría (`:Person`...) a las entidades que no la tienen
+    /// y crea el índice por nombre de cada etiqueta; devuelve cuántas se etiquetaron
+    async fn migrate_category_labels(&self, categories: &[String]) -> Result<usize, AppError>;
+
     // --- Capacidades RAG: Lectura ---
     async fn get_full_graph(&self) -> Result<GraphDataResponse, AppError>;
-    async fn find_hybrid_context(&self, embedding: Vec<f32>, limit: usize) -> Result<Vec<HybridContext>, AppError>;
+    async fn find_hybrid_context(&self, embedding: Vec<f32>, limit: usize, filter: &MetadataFilter) -> Result<Vec<HybridContext>, AppError>;
     async fn get_concept_neighborhood(&self, concept_name: &str) -> Result<GraphDataResponse, AppError>;
 
     // --- Capacidades IA: Razonamiento y Exportación ---
@@ -45,13 +105,26 @@ pub trait KGRepository: Send + Sync {
 
 #[async_trait]
 pub trait AIService: Send + Sync {
-    async fn extract_knowledge(&self, text: &str) -> Result<KnowledgeExtraction, AppError>;
+    /// Entidades y relaciones de `text` siguiendo `instructions` (prompt generado de la ontología)
+    async fn extract_knowledge(&self, text: &str, instructions: &str) -> Result<KnowledgeExtraction, AppError>;
     async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>, AppError>;
+    /// Embeddings de varios textos en una sola petición, en el mismo orden que `texts`
+    async fn generate_embeddings(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, AppError>;
     fn update_config(&mut self, config: AIConfig) -> Result<(), AppError>;
     fn get_config(&self) -> AIConfig;
     async fn generate_inference(&self, prompt: &str) -> Result<InferenceResult, AppError>;
     // NUEVO: Capacidad de ver (Vision)
     async fn describe_image(&self, image_bytes: &[u8], mime_type: &str) -> Result<String, AppError>;
+    /// Transcripción literal del texto de una imagen (OCR de páginas completas), sin describirla ni resumirla
+    async fn transcribe_image(&self, image_bytes: &[u8], mime_type: &str) -> Result<String, AppError>;
     // NUEVO: Capacidad de oír (Whisper)
-    async fn transcribe_audio(&self, audio_bytes: &[u8], filename: &str) -> Result<String, AppError>;
+    /// Transcripción por segmentos con tiempos. `language` (ISO-639-1) prevalece sobre el de
+    /// la configuración; sin ninguno, el idioma se detecta.
+    async fn transcribe_audio(&self, audio_bytes: &[u8], filename: &str, language: Option<&str>) -> Result<Transcription, AppError>;
+}
+
+/// Reconocimiento de texto (OCR) para páginas escaneadas sin capa de texto
+#[async_trait]
+pub trait OcrEngine: Send + Sync {
+    async fn recognize_page(&self, image_bytes: &[u8], mime_type: &str) -> Result<String, AppError>;
 }
\ No newline at end of file
@@ -1,2 +1,3 @@
 pub mod rig_client;
+pub mod vision_ocr;
 // pub mod extractors; // Descomentar si creaste este archivo
\ No newline at end of file
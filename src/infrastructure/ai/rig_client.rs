@@ -1,56 +1,342 @@
 use async_trait::async_trait;
-use rig::{
-    providers::openai::{self},
-    completion::Prompt,
-};
+use std::sync::atomic::{AtomicU8, Ordering};
 use secrecy::ExposeSecret;
-use serde_json::{json, from_str, Value}; 
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value}; 
 use crate::domain::{
-    models::{AIConfig, KnowledgeExtraction, InferenceResult}, 
+    models::{AIConfig, KnowledgeExtraction, InferenceResult, Transcription, TranscriptSegment, is_valid_relation_type}, 
     ports::AIService, 
     errors::AppError
 };
 use base64::{Engine as _, engine::general_purpose}; // Importar Base64
 
+/// Correcciones que se piden al modelo cuando su JSON no encaja con el esquema
+/// (`LLM_REPAIR_ATTEMPTS`, 0 para no reintentar)
+const DEFAULT_REPAIR_ATTEMPTS: usize = 2;
+
+/// Presupuesto de salida del OCR: una página densa transcrita literalmente supera los 1000 tokens
+const OCR_MAX_TOKENS: u32 = 4096;
+
+/// Cómo se pide la salida estructurada, de más a menos estricto. Se baja de nivel
+/// cuando el proveedor rechaza el `response_format` y se recuerda hasta cambiar la configuración.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputMode {
+    /// `response_format: json_schema` (OpenAI, Ollama, algunos modelos de Groq)
+    JsonSchema,
+    /// `response_format: json_object`: JSON válido, sin garantía de forma
+    JsonObject,
+    /// Sin restricción: solo las instrucciones del prompt y la corrección posterior
+    Text,
+}
+
+impl OutputMode {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::JsonSchema,
+            1 => Self::JsonObject,
+            _ => Self::Text,
+        }
+    }
+
+    fn fallback(self) -> Option<Self> {
+        match self {
+            Self::JsonSchema => Some(Self::JsonObject),
+            Self::JsonObject => Some(Self::Text),
+            Self::Text => None,
+        }
+    }
+}
+
+/// Esquema JSON que debe cumplir una respuesta (nombre para el proveedor + esquema)
+struct OutputSchema {
+    name: &'static str,
+    schema: Value,
+}
 
 pub struct RigAIService {
     config: AIConfig,
     http_client: reqwest::Client,
+    output_mode: AtomicU8,
+    repair_attempts: usize,
 }
 
 impl RigAIService {
     pub fn new(config: AIConfig) -> Self {
+        let repair_attempts = std::env::var("LLM_REPAIR_ATTEMPTS").ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_REPAIR_ATTEMPTS);
         Self { 
             config,
             http_client: reqwest::Client::new(),
+            output_mode: AtomicU8::new(OutputMode::JsonSchema as u8),
+            repair_attempts,
         }
     }
 
-    fn clean_json_response(&self, raw: &str) -> String {
-        let start = raw.find('{').unwrap_or(0);
-        let end = raw.rfind('}').map(|i| i + 1).unwrap_or(raw.len());
-        if start >= end { return raw.to_string(); }
-        raw[start..end].to_string()
+    /// Una imagen y unas instrucciones al modelo de visión
+    async fn vision_completion(&self, prompt: &str, image_bytes: &[u8], mime_type: &str, max_tokens: u32) -> Result<String, AppError> {
+        let api_key = self.config.api_key.expose_secret();
+        let base64_image = general_purpose::STANDARD.encode(image_bytes);
+        let data_url = format!("data:{};base64,{}", mime_type, base64_image);
+
+        let payload = json!({
+            "model": "gpt-4o", // Modelo con capacidades de visión
+            "messages": [
+                {
+                    "role": "user",
+                    "content": [
+                        { "type": "text", "text": prompt },
+                        { "type": "image_url", "image_url": { "url": data_url } }
+                    ]
+                }
+            ],
+            "max_tokens": max_tokens
+        });
+
+        let response = self.http_client.post("https://api.openai.com/v1/chat/completions")
+            .header("Authorization", format!("Bearer {}", api_key))
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| AppError::AIError(format!("Vision Request Failed: {}", e)))?;
+
+        let body: Value = response.json().await.map_err(|e| AppError::ParseError(e.to_string()))?;
+        if body["choices"][0]["finish_reason"].as_str() == Some("length") {
+            tracing::warn!("⚠️ Respuesta de visión truncada al límite de {} tokens", max_tokens);
+        }
+        
+        let content = body["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or(AppError::AIError("No content in Vision response".into()))?
+            .to_string();
+
+        Ok(content)
     }
-    
-    fn get_rig_client(&self) -> openai::Client {
-        let raw_base = self.config.base_url.as_deref().unwrap_or("https://api.openai.com/v1");
-        let no_slash = raw_base.trim_end_matches('/');
-        let clean_base = if no_slash.ends_with("/v1") {
-            no_slash.trim_end_matches("/v1")
-        } else {
-            no_slash
-        };
-        tracing::info!("🔌 Rig URL Fix: Original='{}' -> Ajustada='{}'", raw_base, clean_base);
+
+    /// Pide una respuesta JSON con forma de `T`, la valida contra el tipo (y `check`) y, si no
+    /// encaja, devuelve el error al modelo para que la corrija, como mucho `repair_attempts` veces
+    async fn structured_completion<T: DeserializeOwned>(
+        &self,
+        label: &str,
+        system: Option<&str>,
+        prompt: &str,
+        schema: &OutputSchema,
+        check: fn(&T) -> Result<(), String>,
+    ) -> Result<T, AppError> {
+        let mut messages = Vec::new();
+        if let Some(system) = system {
+            messages.push(json!({ "role": "system", "content": system }));
+        }
+        messages.push(json!({ "role": "user", "content": prompt }));
+
+        let mut repairs = 0;
+        loop {
+            let raw = self.chat_completion(label, &messages, schema).await?;
+            match parse_structured(&raw, check) {
+                Ok(value) => return Ok(value),
+                Err(error) if repairs < self.repair_attempts => {
+                    repairs += 1;
+                    tracing::warn!("🔧 JSON inválido en {} (corrección {}/{}): {}", label, repairs, self.repair_attempts, error);
+                    messages.push(json!({ "role": "assistant", "content": raw }));
+                    messages.push(json!({ "role": "user", "content": repair_prompt(&error) }));
+                },
+                Err(error) => return Err(AppError::ParseError(format!("Invalid JSON {} tras {} correcciones: {}", label, repairs, error))),
+            }
+        }
+    }
+
+    /// Una llamada a `/chat/completions` con la salida estructurada que admita el proveedor
+    async fn chat_completion(&self, label: &str, messages: &[Value], schema: &OutputSchema) -> Result<String, AppError> {
         let api_key = self.config.api_key.expose_secret();
-        openai::Client::from_url(api_key, clean_base)
+        let base_url = self.config.base_url.as_deref().unwrap_or("https://api.openai.com/v1");
+        let url = format!("{}/chat/completions", base_url.trim_end_matches('/'));
+
+        let mut mode = OutputMode::from_u8(self.output_mode.load(Ordering::Relaxed));
+        loop {
+            let mut payload = json!({
+                "model": self.config.model_name,
+                "messages": messages,
+            });
+            match mode {
+                // No estricto: los atributos son un objeto libre, que el modo estricto no admite
+                OutputMode::JsonSchema => payload["response_format"] = json!({
+                    "type": "json_schema",
+                    "json_schema": { "name": schema.name, "strict": false, "schema": schema.schema },
+                }),
+                OutputMode::JsonObject => payload["response_format"] = json!({ "type": "json_object" }),
+                OutputMode::Text => {},
+            }
+
+            let response = self.http_client.post(&url)
+                .header("Authorization", format!("Bearer {}", api_key))
+                .json(&payload)
+                .send()
+                .await
+                .map_err(|e| AppError::AIError(format!("{} failed: {}", label, e)))?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response.text().await.unwrap_or_default();
+                if let Some(next) = mode.fallback().filter(|_| rejects_response_format(status, &error_text)) {
+                    tracing::warn!("⚠️ El proveedor no admite la salida {:?}; se usará {:?}", mode, next);
+                    mode = next;
+                    self.output_mode.store(mode as u8, Ordering::Relaxed);
+                    continue;
+                }
+                return Err(AppError::AIError(format!("{} failed: {} - {}", label, status, error_text)));
+            }
+
+            let body: Value = response.json().await
+                .map_err(|e| AppError::ParseError(format!("{}: respuesta ilegible: {}", label, e)))?;
+            return body["choices"][0]["message"]["content"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| AppError::AIError(format!("{}: respuesta sin contenido", label)));
+        }
     }
 }
 
+/// Recorta lo que el modelo ponga alrededor del objeto JSON (texto, bloques ```json)
+fn clean_json_response(raw: &str) -> &str {
+    let start = raw.find('{').unwrap_or(0);
+    let end = raw.rfind('}').map(|i| i + 1).unwrap_or(raw.len());
+    if start >= end { return raw; }
+    &raw[start..end]
+}
+
+/// Deserializa en el tipo de dominio y aplica sus comprobaciones; el error se devuelve
+/// tal cual al modelo, así que dice dónde está el problema
+fn parse_structured<T: DeserializeOwned>(raw: &str, check: fn(&T) -> Result<(), String>) -> Result<T, String> {
+    let value: T = serde_json::from_str(clean_json_response(raw)).map_err(|e| e.to_string())?;
+    check(&value)?;
+    Ok(value)
+}
+
+fn repair_prompt(error: &str) -> String {
+    format!(
+        "Tu respuesta anterior no es válida: {}.\nDevuelve la respuesta completa corregida: solo el objeto JSON que cumple el esquema pedido, sin texto ni markdown alrededor.",
+        error
+    )
+}
+
+/// El proveedor (o el modelo) no acepta el `response_format` pedido
+fn rejects_response_format(status: reqwest::StatusCode, error_text: &str) -> bool {
+    let error_text = error_text.to_lowercase();
+    (status == reqwest::StatusCode::BAD_REQUEST || status == reqwest::StatusCode::UNPROCESSABLE_ENTITY)
+        && (error_text.contains("response_format") || error_text.contains("json_schema"))
+}
+
+fn attributes_schema() -> Value {
+    json!({
+        "type": "object",
+        "additionalProperties": { "type": ["string", "number", "integer", "boolean"] }
+    })
+}
+
+fn extraction_schema() -> OutputSchema {
+    OutputSchema {
+        name: "knowledge_extraction",
+        schema: json!({
+            "type": "object",
+            "properties": {
+                "entities": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string" },
+                            "category": { "type": "string" },
+                            "attributes": attributes_schema()
+                        },
+                        "required": ["name", "category"]
+                    }
+                },
+                "relations": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "source": { "type": "string" },
+                            "target": { "type": "string" },
+                            "relation_type": { "type": "string" },
+                            "evidence": { "type": "string" },
+                            "confidence": { "type": "number", "minimum": 0, "maximum": 1 },
+                            "attributes": attributes_schema()
+                        },
+                        "required": ["source", "target", "relation_type"]
+                    }
+                }
+            },
+            "required": ["entities", "relations"]
+        }),
+    }
+}
+
+fn inference_schema() -> OutputSchema {
+    OutputSchema {
+        name: "inference_result",
+        schema: json!({
+            "type": "object",
+            "properties": {
+                "new_relations": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "source": { "type": "string" },
+                            "target": { "type": "string" },
+                            "relation": { "type": "string" },
+                            "reasoning": { "type": "string" }
+                        },
+                        "required": ["source", "target", "relation", "reasoning"],
+                        "additionalProperties": false
+                    }
+                }
+            },
+            "required": ["new_relations"],
+            "additionalProperties": false
+        }),
+    }
+}
+
+/// Lo que serde no comprueba: campos vacíos y confianza fuera de rango.
+/// Tipos de relación y categorías los ajusta después la ontología.
+fn check_extraction(extraction: &KnowledgeExtraction) -> Result<(), String> {
+    for (i, entity) in extraction.entities.iter().enumerate() {
+        if entity.name.trim().is_empty() || entity.category.trim().is_empty() {
+            return Err(format!("entities[{}]: 'name' y 'category' no pueden estar vacíos", i));
+        }
+    }
+    for (i, relation) in extraction.relations.iter().enumerate() {
+        if relation.source.trim().is_empty() || relation.target.trim().is_empty() || relation.relation_type.trim().is_empty() {
+            return Err(format!("relations[{}]: 'source', 'target' y 'relation_type' no pueden estar vacíos", i));
+        }
+        if relation.confidence.is_some_and(|c| !(0.0..=1.0).contains(&c)) {
+            return Err(format!("relations[{}]: 'confidence' debe estar entre 0 y 1", i));
+        }
+    }
+    Ok(())
+}
+
+fn check_inference(result: &InferenceResult) -> Result<(), String> {
+    for (i, relation) in result.new_relations.iter().enumerate() {
+        if [&relation.source, &relation.target, &relation.relation].iter().any(|v| v.trim().is_empty()) {
+            return Err(format!("new_relations[{}]: 'source', 'target' y 'relation' no pueden estar vacíos", i));
+        }
+        // El tipo acaba dentro del Cypher como etiqueta de relación
+        if !is_valid_relation_type(&relation.relation) {
+            return Err(format!("new_relations[{}]: 'relation' solo admite letras, dígitos, '_' y espacios, empezando por letra", i));
+        }
+    }
+    Ok(())
+}
+
 #[async_trait]
 impl AIService for RigAIService {
     fn update_config(&mut self, config: AIConfig) -> Result<(), AppError> {
         self.config = config;
+        // Otro proveedor o modelo puede admitir la salida estructurada que el anterior rechazaba
+        self.output_mode.store(OutputMode::JsonSchema as u8, Ordering::Relaxed);
         Ok(())
     }
 
@@ -61,7 +347,16 @@ impl AIService for RigAIService {
 
 
     async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>, AppError> {
-        if text.trim().is_empty() {
+        self.generate_embeddings(&[text]).await?
+            .pop()
+            .ok_or_else(|| AppError::AIError("El JSON de respuesta no contiene 'data[0].embedding'".to_string()))
+    }
+
+    async fn generate_embeddings(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, AppError> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        if texts.iter().any(|text| text.trim().is_empty()) {
             return Err(AppError::ValidationError("Texto vacío para embedding".to_string()));
         }
         
@@ -69,11 +364,12 @@ impl AIService for RigAIService {
         let base_url = self.config.base_url.as_deref().unwrap_or("https://api.openai.com/v1");
         let url = format!("{}/embeddings", base_url.trim_end_matches('/'));
 
+        // La API de embeddings acepta un array de entradas: un único viaje por lote
         let response = self.http_client.post(&url)
             .header("Authorization", format!("Bearer {}", api_key))
             .header("Content-Type", "application/json")
             .json(&json!({
-                "input": text,
+                "input": texts,
                 "model": self.config.embedding_model
             }))
             .send()
@@ -89,75 +385,40 @@ impl AIService for RigAIService {
         let body: Value = response.json().await
             .map_err(|e| AppError::ParseError(format!("Error leyendo JSON de OpenAI: {}", e)))?;
 
-        let embedding_value = body.get("data")
-            .and_then(|d| d.get(0))
-            .and_then(|item| item.get("embedding"))
-            .ok_or_else(|| AppError::AIError("El JSON de respuesta no contiene 'data[0].embedding'".to_string()))?;
-
-        let vector: Vec<f32> = serde_json::from_value(embedding_value.clone())
-            .map_err(|e| AppError::ParseError(format!("El embedding no es un array de floats: {}", e)))?;
-
-        Ok(vector)
-    }
-
-    async fn extract_knowledge(&self, text: &str) -> Result<KnowledgeExtraction, AppError> {
-        let client = self.get_rig_client(); 
-
-        let ontology_prompt = r#"
-        Eres un experto auditor clínico y ontólogo. Tu trabajo es estructurar texto libre en un Grafo de Conocimiento.
-        
-        ONTOLOGÍA ESTRICTA:
-        - Person: Pacientes, profesionales, familiares.
-        - Condition: Diagnósticos, síntomas, estados emocionales (ej. Ansiedad, Esquizofrenia, Soledad).
-        - Intervention: Terapias, talleres, medicación, actividades (ej. Club Social, Taller de Arte).
-        - Outcome: Resultados observables (ej. Mejora autoestima, Adherencia tratamiento).
-        - CommunityResource: Entidades externas (ej. Ayuntamiento, Hospital, ONG).
-
-        INSTRUCCIONES:
-        1. Extrae entidades relevantes.
-        2. Extrae relaciones lógicas (CAUSES, TREATED_WITH, PARTICIPATES_IN, HAS_SYMPTOM).
-        3. Output JSON puro.
-
-        Output Format:
-        { 
-            "entities": [{"name": "Nombre Único", "category": "Categoría"}], 
-            "relations": [{"source": "Nombre1", "target": "Nombre2", "relation_type": "VERBO_CORTO"}] 
+        let items = body.get("data")
+            .and_then(Value::as_array)
+            .ok_or_else(|| AppError::AIError("El JSON de respuesta no contiene 'data'".to_string()))?;
+        if items.len() != texts.len() {
+            return Err(AppError::AIError(format!("Se pidieron {} embeddings y llegaron {}", texts.len(), items.len())));
         }
-        "#;
-
-        let agent = client.agent(&self.config.model_name)
-            .preamble(ontology_prompt)
-            .build();
-
-        let response = agent.prompt(text).await
-            .map_err(|e| AppError::AIError(format!("Extraction Failed: {}", e)))?;
 
-        let cleaned_json = self.clean_json_response(&response);
-        let extraction: KnowledgeExtraction = from_str(&cleaned_json)
-            .map_err(|e| AppError::ParseError(format!("Invalid JSON Extraction: {}", e)))?;
+        // Cada elemento trae su `index`: no se asume que lleguen en orden
+        let mut vectors: Vec<Option<Vec<f32>>> = vec![None; texts.len()];
+        for (position, item) in items.iter().enumerate() {
+            let index = item.get("index").and_then(Value::as_u64).map(|i| i as usize).unwrap_or(position);
+            let embedding_value = item.get("embedding")
+                .ok_or_else(|| AppError::AIError(format!("El JSON de respuesta no contiene 'data[{}].embedding'", position)))?;
+            let vector: Vec<f32> = serde_json::from_value(embedding_value.clone())
+                .map_err(|e| AppError::ParseError(format!("El embedding no es un array de floats: {}", e)))?;
+            if let Some(slot) = vectors.get_mut(index) {
+                *slot = Some(vector);
+            }
+        }
+        vectors.into_iter()
+            .enumerate()
+            .map(|(i, v)| v.ok_or_else(|| AppError::AIError(format!("Falta el embedding del texto {}", i))))
+            .collect()
+    }
 
-        Ok(extraction)
+    async fn extract_knowledge(&self, text: &str, instructions: &str) -> Result<KnowledgeExtraction, AppError> {
+        self.structured_completion("Extraction", Some(instructions), text, &extraction_schema(), check_extraction).await
     }
 
     async fn generate_inference(&self, prompt: &str) -> Result<InferenceResult, AppError> {
-        let client = self.get_rig_client();
-        let agent = client.agent(&self.config.model_name).build();
-        
-        let response = agent.prompt(prompt).await
-            .map_err(|e| AppError::AIError(format!("Inference failed: {}", e)))?;
-            
-        let cleaned = self.clean_json_response(&response);
-        let result: InferenceResult = serde_json::from_str(&cleaned)
-            .map_err(|e| AppError::ParseError(format!("Invalid JSON Inference: {}", e)))?;
-            
-        Ok(result)
+        self.structured_completion("Inference", None, prompt, &inference_schema(), check_inference).await
     }
 
     async fn describe_image(&self, image_bytes: &[u8], mime_type: &str) -> Result<String, AppError> {
-        let api_key = self.config.api_key.expose_secret();
-        let base64_image = general_purpose::STANDARD.encode(image_bytes);
-        let data_url = format!("data:{};base64,{}", mime_type, base64_image);
-
         let prompt = r#"
             Analiza esta imagen clínica/social. 
             - Si es texto manuscrito, transcríbelo íntegramente.
@@ -165,49 +426,40 @@ impl AIService for RigAIService {
             - Si es un gráfico médico, extrae los valores clave.
             NO des opiniones médicas, solo describe los datos objetivos y el contenido.
         "#;
+        self.vision_completion(prompt, image_bytes, mime_type, 1000).await
+    }
 
-        let payload = json!({
-            "model": "gpt-4o", // Modelo con capacidades de visión
-            "messages": [
-                {
-                    "role": "user",
-                    "content": [
-                        { "type": "text", "text": prompt },
-                        { "type": "image_url", "image_url": { "url": data_url } }
-                    ]
-                }
-            ],
-            "max_tokens": 1000
-        });
-
-        let response = self.http_client.post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| AppError::AIError(format!("Vision Request Failed: {}", e)))?;
-
-        let body: Value = response.json().await.map_err(|e| AppError::ParseError(e.to_string()))?;
-        
-        let description = body["choices"][0]["message"]["content"]
-            .as_str()
-            .ok_or(AppError::AIError("No content in Vision response".into()))?
-            .to_string();
-
-        Ok(description)
+    async fn transcribe_image(&self, image_bytes: &[u8], mime_type: &str) -> Result<String, AppError> {
+        let prompt = r#"
+            Transcribe literalmente todo el texto de esta página escaneada, en su idioma original.
+            - Copia el texto palabra por palabra: no resumas, no parafrasees, no corrijas ni traduzcas.
+            - Respeta el orden de lectura, los saltos de párrafo, los títulos y las listas.
+            - Las tablas, fila a fila, con las celdas separadas por " | ".
+            - Si una palabra es ilegible, escribe [ilegible] en su lugar.
+            Devuelve solo el texto transcrito, sin comentarios ni descripciones de la imagen.
+        "#;
+        self.vision_completion(prompt, image_bytes, mime_type, OCR_MAX_TOKENS).await
     }
 
-    async fn transcribe_audio(&self, audio_bytes: &[u8], filename: &str) -> Result<String, AppError> {
+    async fn transcribe_audio(&self, audio_bytes: &[u8], filename: &str, language: Option<&str>) -> Result<Transcription, AppError> {
         let api_key = self.config.api_key.expose_secret();
         
         // Crear Multipart para Whisper
         let part = reqwest::multipart::Part::bytes(audio_bytes.to_vec())
             .file_name(filename.to_string()); // Whisper necesita un nombre de archivo para deducir formato
 
-        let form = reqwest::multipart::Form::new()
+        // verbose_json devuelve los segmentos con sus tiempos, necesarios para citar la grabación
+        let mut form = reqwest::multipart::Form::new()
             .part("file", part)
             .text("model", "whisper-1")
-            .text("language", "es"); // Forzar español o dejar auto
+            .text("response_format", "verbose_json")
+            .text("timestamp_granularities[]", "segment");
+        let language = language.or(self.config.transcription_language.as_deref())
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && *l != "auto");
+        if let Some(language) = language {
+            form = form.text("language", language.to_string());
+        }
 
         let response = self.http_client.post("https://api.openai.com/v1/audio/transcriptions")
             .header("Authorization", format!("Bearer {}", api_key))
@@ -222,8 +474,57 @@ impl AIService for RigAIService {
             .as_str()
             .ok_or(AppError::AIError("No text in Whisper response".into()))?
             .to_string();
+        let segments = body["segments"].as_array()
+            .map(|segments| segments.iter()
+                .filter_map(|segment| Some(TranscriptSegment {
+                    start_secs: segment["start"].as_f64()?,
+                    end_secs: segment["end"].as_f64()?,
+                    text: segment["text"].as_str()?.trim().to_string(),
+                }))
+                .filter(|segment| !segment.text.is_empty())
+                .collect())
+            .unwrap_or_default();
+
+        Ok(Transcription {
+            text,
+            language: body["language"].as_str().map(str::to_string).or_else(|| language.map(str::to_string)),
+            segments,
+        })
+    }
 
-        Ok(text)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fenced_json_and_reports_what_to_repair() {
+        let raw = "Aquí tienes:\n```json\n{\"entities\": [{\"name\": \"Ana\", \"category\": \"Person\", \"attributes\": {\"edad\": 34}}], \"relations\": []}\n```";
+        let extraction = parse_structured(raw, check_extraction).unwrap();
+        assert_eq!(extraction.entities[0].name, "Ana");
+
+        // Falta un campo obligatorio: el error de serde nombra el campo
+        let error = parse_structured::<KnowledgeExtraction>(r#"{"entities": [{"name": "Ana"}], "relations": []}"#, check_extraction).unwrap_err();
+        assert!(error.contains("category"), "{}", error);
+
+        let error = parse_structured::<KnowledgeExtraction>(
+            r#"{"entities": [], "relations": [{"source": "Ana", "target": "Taller", "relation_type": "PARTICIPATES_IN", "confidence": 7}]}"#,
+            check_extraction,
+        ).unwrap_err();
+        assert!(error.starts_with("relations[0]"), "{}", error);
+
+        let error = parse_structured::<InferenceResult>(r#"{"new_relations": [{"source": "", "target": "B", "relation": "R", "reasoning": "-"}]}"#, check_inference).unwrap_err();
+        assert!(error.starts_with("new_relations[0]"), "{}", error);
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn falls_back_only_when_the_response_format_is_rejected() {
+        let rejected = r#"{"error": {"message": "Invalid parameter: 'response_format' of type 'json_schema' is not supported with this model."}}"#;
+        assert!(rejects_response_format(reqwest::StatusCode::BAD_REQUEST, rejected));
+        assert!(!rejects_response_format(reqwest::StatusCode::UNAUTHORIZED, rejected));
+        assert!(!rejects_response_format(reqwest::StatusCode::BAD_REQUEST, r#"{"error": "context length exceeded"}"#));
+        assert_eq!(OutputMode::JsonSchema.fallback(), Some(OutputMode::JsonObject));
+        assert_eq!(OutputMode::from_u8(OutputMode::JsonObject as u8), OutputMode::JsonObject);
+        assert_eq!(OutputMode::Text.fallback(), None);
+    }
+}
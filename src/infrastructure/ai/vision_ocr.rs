@@ -0,0 +1,27 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+use crate::domain::{
+    ports::{AIService, OcrEngine},
+    errors::AppError
+};
+
+/// OCR apoyado en el modelo de visión configurado (`AIService::transcribe_image`).
+/// Mantiene la referencia al servicio compartido para respetar los cambios de configuración en caliente.
+pub struct VisionOcrEngine {
+    ai: Arc<RwLock<dyn AIService>>,
+}
+
+impl VisionOcrEngine {
+    pub fn new(ai: Arc<RwLock<dyn AIService>>) -> Self {
+        Self { ai }
+    }
+}
+
+#[async_trait]
+impl OcrEngine for VisionOcrEngine {
+    async fn recognize_page(&self, image_bytes: &[u8], mime_type: &str) -> Result<String, AppError> {
+        let text = self.ai.read().await.transcribe_image(image_bytes, mime_type).await?;
+        Ok(text.trim().to_string())
+    }
+}
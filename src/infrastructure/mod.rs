@@ -1,5 +1,6 @@
 pub mod ai;
 pub mod persistence;
-pub mod transmutation; // <--- ESTA DEBE ESTAR ACTIVA
+pub mod transmutation; // <--- ESTA DEBE ESTAR ACTIVA
+pub mod web;
 // pub mod parsing;    <--- BORRA O COMENTA ESTA LÍNEA
 pub mod tools;
\ No newline at end of file
@@ -73,17 +73,12 @@ impl FileAgentRepository {
 
         match glob(&pattern) {
             Ok(paths) => {
-                for entry in paths {
-                    match entry {
-                        Ok(path) => {
-                            if let Ok(content) = fs::read_to_string(&path) {
-                                match serde_yaml::from_str::<ToolDefinition>(&content) {
-                                    Ok(tool) => tools.push(tool),
-                                    Err(e) => tracing::error!("❌ Error de formato YAML en {:?}: {}", path, e),
-                                }
-                            }
-                        },
-                        _ => {}
+                for path in paths.flatten() {
+                    if let Ok(content) = fs::read_to_string(&path) {
+                        match serde_yaml::from_str::<ToolDefinition>(&content) {
+                            Ok(tool) => tools.push(tool),
+                            Err(e) => tracing::error!("❌ Error de formato YAML en {:?}: {}", path, e),
+                        }
                     }
                 }
             },
@@ -1,26 +1,226 @@
 use async_trait::async_trait;
-use neo4rs::{Graph, query};
+use neo4rs::{Graph, BoltType, query};
 use uuid::Uuid;
 use std::sync::Arc;
-use std::collections::{HashSet, HashMap}; // Importamos HashMap
+use std::collections::{HashSet, HashMap, BTreeMap, BTreeSet}; // Importamos HashMap
 use serde_json::Value; // Importamos Value
 use crate::domain::{
     ports::KGRepository, 
     models::{
         KnowledgeExtraction, GraphDataResponse, VisNode, VisEdge, 
-        HybridContext, InferredRelation, GraphEntity, GraphRelation, 
-        ExportedGraph, User, UserRole, ChatHistoryMessage, MessageRole 
+        HybridContext, InferredRelation, GraphEntity, 
+        ExportedGraph, User, UserRole, ChatHistoryMessage, MessageRole,
+        SourceDocument, DocumentDetail, DocumentChunkSummary, ChunkRecord, StoredChunkHash,
+        IngestionJob, JobStatus, IngestionPayload, StructuredSpan, ChunkLocation, DocumentMetadata, MetadataFilter, WebSource,
+        CurationQueue, CurationDecision, CurationAction, PendingEntity, PendingRelation, ReviewStatus,
+        EntityAlias, entity_key, category_label, RelationProvenance, ExportedRelation, Attributes, AttributeValue
     }, 
     errors::AppError
 };
 
 pub struct Neo4jRepo {
     graph: Arc<Graph>,
+    /// Revisión humana obligatoria: lo extraído nace `PENDING` (ver `ReviewStatus`)
+    review_required: bool,
+}
+
+/// Citas de evidencia que se conservan por relación
+const MAX_EVIDENCE: usize = 5;
+
+/// Columnas de procedencia de la relación `r` (ver `Neo4jRepo::row_to_provenance`)
+const PROVENANCE_COLUMNS: &str = "r.source_chunks as source_chunks, r.occurrences as occurrences, r.extracted_at as extracted_at, r.last_extracted_at as last_extracted_at, r.model as model, r.confidence as confidence, r.evidence as evidence";
+
+/// Suma la procedencia de `r` a la relación ya existente `n` (fusión de entidades):
+/// unión de fragmentos, ocurrencias sumadas, mayor confianza y citas hasta `MAX_EVIDENCE`
+fn merge_provenance_set() -> String {
+    format!("n.source_chunks = coalesce(n.source_chunks, []) + [c IN coalesce(r.source_chunks, []) WHERE NOT c IN coalesce(n.source_chunks, [])], \
+        n.occurrences = coalesce(n.occurrences, 0) + coalesce(r.occurrences, 0), \
+        n.evidence = (coalesce(n.evidence, []) + [q IN coalesce(r.evidence, []) WHERE NOT q IN coalesce(n.evidence, [])])[..{}], \
+        n.confidence = CASE WHEN n.confidence IS NULL OR r.confidence > n.confidence THEN r.confidence ELSE n.confidence END, \
+        n.extracted_at = CASE WHEN n.extracted_at IS NULL OR r.extracted_at < n.extracted_at THEN r.extracted_at ELSE n.extracted_at END, \
+        n.last_extracted_at = CASE WHEN n.last_extracted_at IS NULL OR r.last_extracted_at > n.last_extracted_at THEN r.last_extracted_at ELSE n.last_extracted_at END", MAX_EVIDENCE)
+}
+
+/// Propiedades `attr_<clave>` de los atributos, para `SET n += $attributes`
+fn attribute_properties(attributes: &Attributes) -> HashMap<String, BoltType> {
+    attributes.iter().map(|(key, value)| {
+        let value: BoltType = match value {
+            AttributeValue::Boolean(b) => (*b).into(),
+            AttributeValue::Integer(i) => (*i).into(),
+            AttributeValue::Number(n) => (*n).into(),
+            AttributeValue::Text(s) => s.clone().into(),
+        };
+        (format!("attr_{}", key), value)
+    }).collect()
+}
+
+/// Expresión Cypher: atributos de `var` como lista de pares `[clave, valor]`
+fn attribute_pairs(var: &str) -> String {
+    format!("[k IN keys({0}) WHERE k STARTS WITH 'attr_' | [substring(k, 5), {0}[k]]]", var)
+}
+
+/// Condición Cypher: `var` (entidad o relación) está aprobado o es anterior a la curación
+fn live(var: &str) -> String {
+    format!("coalesce({}.review_status, 'APPROVED') = 'APPROVED'", var)
 }
 
 impl Neo4jRepo {
     pub fn new(graph: Arc<Graph>) -> Self {
-        Self { graph }
+        Self { graph, review_required: false }
+    }
+
+    pub fn with_review(mut self, required: bool) -> Self {
+        self.review_required = required;
+        self
+    }
+
+    /// Estado con el que nacen entidades y relaciones extraídas (`None` = aprobadas)
+    fn initial_review_status(&self) -> Option<String> {
+        self.review_required.then(|| ReviewStatus::Pending.as_str().to_string())
+    }
+
+    /// Ejecuta una consulta que devuelve una única columna `total`
+    async fn count(&self, cypher: &str) -> Result<i64, AppError> {
+        let mut stream = self.graph.execute(query(cypher)).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        match stream.next().await {
+            Ok(Some(row)) => Ok(row.get::<i64>("total").unwrap_or(0)),
+            _ => Ok(0),
+        }
+    }
+
+    fn row_to_attributes(row: &neo4rs::Row, column: &str) -> Attributes {
+        row.get::<Vec<(String, AttributeValue)>>(column)
+            .map(|pairs| pairs.into_iter().collect())
+            .unwrap_or_default()
+    }
+
+    fn row_to_provenance(row: &neo4rs::Row) -> RelationProvenance {
+        let source_chunks: Vec<String> = row.get("source_chunks").unwrap_or_default();
+        RelationProvenance {
+            // Relaciones anteriores a la procedencia: sin contador
+            occurrences: row.get::<i64>("occurrences").map(|n| n as usize).unwrap_or(source_chunks.len()),
+            source_chunks,
+            extracted_at: row.get("extracted_at").ok(),
+            last_extracted_at: row.get("last_extracted_at").ok(),
+            model: row.get("model").ok(),
+            confidence: row.get::<f64>("confidence").ok().map(|c| c as f32),
+            evidence: row.get("evidence").unwrap_or_default(),
+        }
+    }
+
+    /// Quita los fragmentos borrados de la procedencia de las relaciones que los citaban.
+    /// Las que se quedan sin ningún fragmento se borran, salvo que un curador las haya revisado.
+    async fn forget_relation_sources(&self, chunk_ids: &[String]) -> Result<(), AppError> {
+        if chunk_ids.is_empty() {
+            return Ok(());
+        }
+        let q = query("MATCH (:Entity)-[r]->(:Entity) WHERE any(id IN r.source_chunks WHERE id IN $ids) \
+            WITH r, [id IN r.source_chunks WHERE NOT id IN $ids] AS remaining \
+            SET r.source_chunks = remaining, r.occurrences = size(remaining) \
+            WITH r WHERE size(r.source_chunks) = 0 AND r.reviewed_by IS NULL \
+            DELETE r")
+            .param("ids", chunk_ids.to_vec());
+        self.graph.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn row_to_document(row: &neo4rs::Row) -> SourceDocument {
+        SourceDocument {
+            id: row.get("d.id").unwrap_or_default(),
+            filename: row.get("d.filename").unwrap_or_default(),
+            mime_type: row.get("d.mime_type").unwrap_or_default(),
+            content_hash: row.get("d.content_hash").unwrap_or_default(),
+            uploaded_by: row.get("d.uploaded_by").unwrap_or_default(),
+            ingested_at: row.get("d.ingested_at").unwrap_or_default(),
+            chunk_count: row.get::<i64>("chunk_count").unwrap_or(0) as usize,
+            metadata: row.get::<String>("d.metadata").ok()
+                .and_then(|raw| serde_json::from_str(&raw).ok())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Condiciones `WHERE` sobre las propiedades `meta_<clave>` de `chunk` y sus parámetros.
+    /// Las claves ya están validadas (`MetadataFilter::validate`), así que pueden ir en el Cypher.
+    fn metadata_conditions(filter: &MetadataFilter) -> (Vec<String>, Vec<(String, String)>) {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        for (i, (key, value)) in filter.equals.iter().enumerate() {
+            conditions.push(format!("chunk.meta_{} = $meta_eq_{}", key, i));
+            params.push((format!("meta_eq_{}", i), value.clone()));
+        }
+        for (i, (key, range)) in filter.ranges.iter().enumerate() {
+            if let Some(from) = &range.from {
+                conditions.push(format!("chunk.meta_{} >= $meta_from_{}", key, i));
+                params.push((format!("meta_from_{}", i), from.clone()));
+            }
+            if let Some(before) = &range.before {
+                conditions.push(format!("chunk.meta_{} < $meta_before_{}", key, i));
+                params.push((format!("meta_before_{}", i), before.clone()));
+            }
+        }
+        (conditions, params)
+    }
+
+    /// Lee `char_start`, `char_end`, `page_start`, `page_end`, `time_start` y `time_end` (con el prefijo de columna dado)
+    fn row_to_location(row: &neo4rs::Row, prefix: &str) -> ChunkLocation {
+        ChunkLocation {
+            char_start: row.get::<i64>(&format!("{}char_start", prefix)).unwrap_or(0) as usize,
+            char_end: row.get::<i64>(&format!("{}char_end", prefix)).unwrap_or(0) as usize,
+            page_start: row.get::<i64>(&format!("{}page_start", prefix)).ok().map(|p| p as u32),
+            page_end: row.get::<i64>(&format!("{}page_end", prefix)).ok().map(|p| p as u32),
+            time_start: row.get::<f64>(&format!("{}time_start", prefix)).ok(),
+            time_end: row.get::<f64>(&format!("{}time_end", prefix)).ok(),
+        }
+    }
+
+    fn row_to_job(row: &neo4rs::Row) -> IngestionJob {
+        let status: String = row.get("j.status").unwrap_or_default();
+        IngestionJob {
+            id: row.get("j.id").unwrap_or_default(),
+            filename: row.get("j.filename").unwrap_or_default(),
+            mime_type: row.get("j.mime_type").unwrap_or_default(),
+            uploaded_by: row.get("j.uploaded_by").unwrap_or_default(),
+            content_hash: row.get("j.content_hash").unwrap_or_default(),
+            replace: row.get("j.replace").unwrap_or(false),
+            status: JobStatus::parse(&status),
+            chunking: row.get::<String>("j.chunking").ok()
+                .and_then(|raw| serde_json::from_str(&raw).ok())
+                .unwrap_or_default(),
+            document_id: row.get::<String>("j.document_id").ok().filter(|s| !s.is_empty()),
+            total_chunks: row.get::<i64>("j.total_chunks").unwrap_or(0) as usize,
+            completed_chunks: row.get::<i64>("j.completed_chunks").unwrap_or(0) as usize,
+            error: row.get::<String>("j.error").ok().filter(|s| !s.is_empty()),
+            created_at: row.get("j.created_at").unwrap_or_default(),
+            updated_at: row.get("j.updated_at").unwrap_or_default(),
+        }
+    }
+
+    fn row_to_web_source(row: &neo4rs::Row) -> WebSource {
+        WebSource {
+            id: row.get("w.id").unwrap_or_default(),
+            url: row.get("w.url").unwrap_or_default(),
+            sitemap: row.get("w.sitemap").unwrap_or(false),
+            refresh_hours: row.get::<i64>("w.refresh_hours").ok().filter(|h| *h > 0).map(|h| h as u32),
+            uploaded_by: row.get("w.uploaded_by").unwrap_or_default(),
+            metadata: row.get::<String>("w.metadata").ok()
+                .and_then(|raw| serde_json::from_str(&raw).ok())
+                .unwrap_or_default(),
+            chunking: row.get::<String>("w.chunking").ok()
+                .and_then(|raw| serde_json::from_str(&raw).ok()),
+            created_at: row.get("w.created_at").unwrap_or_default(),
+            last_fetched_at: row.get::<String>("w.last_fetched_at").ok().filter(|s| !s.is_empty()),
+            last_error: row.get::<String>("w.last_error").ok().filter(|s| !s.is_empty()),
+        }
+    }
+
+    async fn find_document_where(&self, condition: &str, params: &[(&str, &str)]) -> Result<Option<SourceDocument>, AppError> {
+        let cypher = format!("MATCH (d:Document) WHERE {} OPTIONAL MATCH (c:DocumentChunk)-[:PART_OF]->(d) WITH d, count(c) as chunk_count RETURN d.id, d.filename, d.mime_type, d.content_hash, d.uploaded_by, d.ingested_at, d.metadata, chunk_count ORDER BY d.ingested_at DESC LIMIT 1", condition);
+        let q = params.iter().fold(query(&cypher), |q, (key, value)| q.param(key, *value));
+        let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        match stream.next().await {
+            Ok(Some(row)) => Ok(Some(Self::row_to_document(&row))),
+            _ => Ok(None),
+        }
     }
 }
 
@@ -91,6 +291,13 @@ impl KGRepository for Neo4jRepo {
         let q = format!("CREATE VECTOR INDEX chunk_embeddings IF NOT EXISTS FOR (c:DocumentChunk) ON (c.embedding) OPTIONS {{indexConfig: {{ `vector.dimensions`: {}, `vector.similarity_function`: 'cosine' }} }}", dim);
         self.graph.run(query(&q)).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
         self.graph.run(query("CREATE CONSTRAINT entity_name IF NOT EXISTS FOR (e:Entity) REQUIRE e.name IS UNIQUE")).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        self.graph.run(query("CREATE INDEX entity_curation_id IF NOT EXISTS FOR (e:Entity) ON (e.curation_id)")).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        self.graph.run(query("CREATE INDEX entity_name_key IF NOT EXISTS FOR (e:Entity) ON (e.name_key)")).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let q_entities = format!("CREATE VECTOR INDEX entity_embeddings IF NOT EXISTS FOR (e:Entity) ON (e.embedding) OPTIONS {{indexConfig: {{ `vector.dimensions`: {}, `vector.similarity_function`: 'cosine' }} }}", dim);
+        self.graph.run(query(&q_entities)).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        self.graph.run(query("CREATE CONSTRAINT entity_alias_key IF NOT EXISTS FOR (a:EntityAlias) REQUIRE a.key IS UNIQUE")).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        self.graph.run(query("CREATE CONSTRAINT document_id IF NOT EXISTS FOR (d:Document) REQUIRE d.id IS UNIQUE")).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        self.graph.run(query("CREATE CONSTRAINT ingestion_job_id IF NOT EXISTS FOR (j:IngestionJob) REQUIRE j.id IS UNIQUE")).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
         self.graph.run(query("CREATE CONSTRAINT user_unique IF NOT EXISTS FOR (u:User) REQUIRE u.username IS UNIQUE")).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
         Ok(())
     }
@@ -101,21 +308,68 @@ impl KGRepository for Neo4jRepo {
     }
 
     // --- INGESTA Y ESCRITURA ---
-    async fn save_chunk(&self, id: Uuid, content: &str, embedding: Vec<f32>) -> Result<(), AppError> {
-        let q = query("CREATE (c:DocumentChunk {id: $id, content: $content, embedding: $embedding})").param("id", id.to_string()).param("content", content).param("embedding", embedding);
+    async fn save_chunk(&self, chunk: ChunkRecord) -> Result<(), AppError> {
+        let properties: HashMap<String, String> = chunk.metadata.iter()
+            .map(|(key, value)| (format!("meta_{}", key), value.clone()))
+            .collect();
+        let q = query("MATCH (d:Document {id: $doc_id}) CREATE (c:DocumentChunk {id: $id, content: $content, content_hash: $content_hash, embedding: $embedding, char_start: $char_start, char_end: $char_end, page_start: $page_start, page_end: $page_end, time_start: $time_start, time_end: $time_end})-[:PART_OF {position: $position}]->(d) SET c += $properties")
+            .param("doc_id", chunk.document_id.to_string())
+            .param("id", chunk.id.to_string())
+            .param("content", chunk.content)
+            .param("content_hash", chunk.content_hash)
+            .param("embedding", chunk.embedding)
+            .param("char_start", chunk.location.char_start as i64)
+            .param("char_end", chunk.location.char_end as i64)
+            .param("page_start", chunk.location.page_start.map(i64::from))
+            .param("page_end", chunk.location.page_end.map(i64::from))
+            .param("time_start", chunk.location.time_start)
+            .param("time_end", chunk.location.time_end)
+            .param("position", chunk.position as i64)
+            .param("properties", properties);
         self.graph.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
         Ok(())
     }
 
-    async fn save_graph(&self, chunk_id: Uuid, data: KnowledgeExtraction) -> Result<(), AppError> {
+    async fn save_graph(&self, chunk_id: Uuid, data: KnowledgeExtraction, extracted_by: &str) -> Result<(), AppError> {
+        // Solo lo que se crea ahora queda pendiente: lo ya revisado conserva su decisión
+        let status = self.initial_review_status();
+        let extracted_at = chrono::Utc::now().to_rfc3339();
         let mut txn = self.graph.start_txn().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
         for entity in &data.entities {
-            let q = query("MERGE (e:Entity {name: $name}) ON CREATE SET e.category = $category").param("name", entity.name.as_str()).param("category", entity.category.as_str());
+            // Los atributos nuevos se añaden a los que ya tuviera la entidad (mismo nombre, valor nuevo)
+            // La etiqueta de categoría acompaña a `category`: solo al crear la entidad
+            let cypher = format!("MERGE (e:Entity {{name: $name}}) ON CREATE SET e.name_key = $key, e.category = $category, e.review_status = $status, e.curation_id = randomUUID(), e.extracted_at = $extracted_at{} SET e += $attributes",
+                category_label(&entity.category).map(|label| format!(", e:`{}`", label)).unwrap_or_default());
+            let q = query(&cypher)
+                .param("name", entity.name.as_str())
+                .param("attributes", attribute_properties(&entity.attributes))
+                .param("key", entity_key(&entity.name))
+                .param("category", entity.category.as_str())
+                .param("status", status.clone())
+                .param("extracted_at", extracted_at.as_str());
             txn.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
         }
         for rel in data.relations {
-            let cypher = format!("MATCH (a:Entity {{name: $source}}), (b:Entity {{name: $target}}) MERGE (a)-[:{}]->(b)", rel.relation_type.replace(" ", "_").to_uppercase());
-            let q = query(&cypher).param("source", rel.source.as_str()).param("target", rel.target.as_str());
+            // Procedencia: cada fragmento cuenta una vez; se guarda la mayor confianza y las primeras citas
+            let cypher = format!("MATCH (a:Entity {{name: $source}}), (b:Entity {{name: $target}}) MERGE (a)-[r:`{}`]->(b) ON CREATE SET r.review_status = $status, r.curation_id = randomUUID(), r.extracted_at = $extracted_at \
+                WITH r, NOT $cid IN coalesce(r.source_chunks, []) AS fresh \
+                SET r.source_chunks = CASE WHEN fresh THEN coalesce(r.source_chunks, []) + $cid ELSE r.source_chunks END, \
+                    r.occurrences = coalesce(r.occurrences, 0) + CASE WHEN fresh THEN 1 ELSE 0 END, \
+                    r.last_extracted_at = $extracted_at, r.model = $model, \
+                    r.confidence = CASE WHEN r.confidence IS NULL OR $confidence > r.confidence THEN $confidence ELSE r.confidence END, \
+                    r.evidence = CASE WHEN $evidence IS NULL OR $evidence IN coalesce(r.evidence, []) OR size(coalesce(r.evidence, [])) >= {} THEN r.evidence ELSE coalesce(r.evidence, []) + $evidence END, \
+                    r += $attributes",
+                rel.relation_type.replace(" ", "_").to_uppercase(), MAX_EVIDENCE);
+            let q = query(&cypher)
+                .param("source", rel.source.as_str())
+                .param("target", rel.target.as_str())
+                .param("status", status.clone())
+                .param("extracted_at", extracted_at.as_str())
+                .param("cid", chunk_id.to_string())
+                .param("model", extracted_by)
+                .param("confidence", rel.confidence.map(f64::from))
+                .param("attributes", attribute_properties(&rel.attributes))
+                .param("evidence", rel.evidence);
             txn.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
         }
         let q_link = query("MATCH (c:DocumentChunk {id: $cid}), (e:Entity) WHERE e.name IN $names MERGE (c)-[:MENTIONS]->(e)");
@@ -125,9 +379,589 @@ impl KGRepository for Neo4jRepo {
         Ok(())
     }
 
+    // --- DOCUMENTOS FUENTE ---
+    async fn save_document(&self, document: &SourceDocument) -> Result<(), AppError> {
+        // Las claves de la versión anterior que ya no vienen se ponen a null: `SET +=` las borra
+        let q_previous = query("MATCH (d:Document {id: $id}) RETURN d.metadata").param("id", document.id.as_str());
+        let mut stream = self.graph.execute(q_previous).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let previous: DocumentMetadata = match stream.next().await {
+            Ok(Some(row)) => row.get::<String>("d.metadata").ok()
+                .and_then(|raw| serde_json::from_str(&raw).ok())
+                .unwrap_or_default(),
+            _ => DocumentMetadata::new(),
+        };
+        let mut properties: HashMap<String, Option<String>> = previous.keys()
+            .map(|key| (format!("meta_{}", key), None))
+            .collect();
+        properties.extend(document.metadata.iter().map(|(key, value)| (format!("meta_{}", key), Some(value.clone()))));
+
+        // Los fragmentos heredan los metadatos del documento para filtrar la búsqueda vectorial
+        let q = query("MERGE (d:Document {id: $id}) SET d.filename = $filename, d.mime_type = $mime_type, d.content_hash = $content_hash, d.uploaded_by = $uploaded_by, d.ingested_at = $ingested_at, d.metadata = $metadata SET d += $properties WITH d MATCH (c:DocumentChunk)-[:PART_OF]->(d) SET c += $properties")
+            .param("id", document.id.as_str())
+            .param("filename", document.filename.as_str())
+            .param("mime_type", document.mime_type.as_str())
+            .param("content_hash", document.content_hash.as_str())
+            .param("uploaded_by", document.uploaded_by.as_str())
+            .param("ingested_at", document.ingested_at.as_str())
+            .param("metadata", serde_json::to_string(&document.metadata).unwrap_or_default())
+            .param("properties", properties);
+        self.graph.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn list_documents(&self) -> Result<Vec<SourceDocument>, AppError> {
+        let q = query("MATCH (d:Document) OPTIONAL MATCH (c:DocumentChunk)-[:PART_OF]->(d) WITH d, count(c) as chunk_count RETURN d.id, d.filename, d.mime_type, d.content_hash, d.uploaded_by, d.ingested_at, d.metadata, chunk_count ORDER BY d.ingested_at DESC");
+        let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let mut documents = Vec::new();
+        while let Ok(Some(row)) = stream.next().await {
+            documents.push(Self::row_to_document(&row));
+        }
+        Ok(documents)
+    }
+
+    async fn get_document(&self, id: &str) -> Result<Option<DocumentDetail>, AppError> {
+        let q = query("MATCH (d:Document {id: $id}) OPTIONAL MATCH (c:DocumentChunk)-[:PART_OF]->(d) WITH d, count(c) as chunk_count RETURN d.id, d.filename, d.mime_type, d.content_hash, d.uploaded_by, d.ingested_at, d.metadata, chunk_count").param("id", id);
+        let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let document = match stream.next().await {
+            Ok(Some(row)) => Self::row_to_document(&row),
+            _ => return Ok(None),
+        };
+
+        let q_chunks = query("MATCH (c:DocumentChunk)-[p:PART_OF]->(d:Document {id: $id}) OPTIONAL MATCH (c)-[:MENTIONS]->(e:Entity) RETURN c.id as id, p.position as position, c.content as content, c.char_start as char_start, c.char_end as char_end, c.page_start as page_start, c.page_end as page_end, c.time_start as time_start, c.time_end as time_end, collect(DISTINCT e.name) as entities ORDER BY position").param("id", id);
+        let mut stream_chunks = self.graph.execute(q_chunks).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let mut chunks = Vec::new();
+        while let Ok(Some(row)) = stream_chunks.next().await {
+            let content: String = row.get("content").unwrap_or_default();
+            chunks.push(DocumentChunkSummary {
+                id: row.get("id").unwrap_or_default(),
+                position: row.get::<i64>("position").unwrap_or(0) as usize,
+                preview: content.chars().take(200).collect(),
+                entities: row.get("entities").unwrap_or_default(),
+                location: Self::row_to_location(&row, ""),
+            });
+        }
+        Ok(Some(DocumentDetail { document, chunks }))
+    }
+
+    async fn delete_document(&self, id: &str) -> Result<bool, AppError> {
+        let q_exists = query("MATCH (d:Document {id: $id}) RETURN d.id").param("id", id);
+        let mut stream = self.graph.execute(q_exists).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if !matches!(stream.next().await, Ok(Some(_))) {
+            return Ok(false);
+        }
+
+        let q_chunks = query("MATCH (c:DocumentChunk)-[:PART_OF]->(:Document {id: $id}) RETURN c.id as id").param("id", id);
+        let mut stream = self.graph.execute(q_chunks).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let mut chunk_ids = Vec::new();
+        while let Ok(Some(row)) = stream.next().await {
+            if let Ok(chunk_id) = row.get::<String>("id") {
+                chunk_ids.push(chunk_id);
+            }
+        }
+        self.forget_relation_sources(&chunk_ids).await?;
+
+        // Los trabajos de ingesta del documento guardan otra copia de su texto
+        let q_jobs = query("MATCH (j:IngestionJob {document_id: $id}) DETACH DELETE j").param("id", id);
+        self.graph.run(q_jobs).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        // Cascada: chunks del documento y entidades que quedan sin ninguna mención
+        let q = query("
+            MATCH (d:Document {id: $id})
+            OPTIONAL MATCH (c:DocumentChunk)-[:PART_OF]->(d)
+            OPTIONAL MATCH (c)-[:MENTIONS]->(e:Entity)
+            WITH d, collect(DISTINCT c) as chunks, collect(DISTINCT e) as entities
+            FOREACH (c IN chunks | DETACH DELETE c)
+            DETACH DELETE d
+            WITH entities
+            UNWIND entities as e
+            WITH e WHERE NOT (e)<-[:MENTIONS]-(:DocumentChunk)
+            DETACH DELETE e
+        ").param("id", id);
+        self.graph.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(true)
+    }
+
+    // --- RE-INGESTA INCREMENTAL ---
+    async fn find_document_by_hash(&self, content_hash: &str) -> Result<Option<SourceDocument>, AppError> {
+        self.find_document_where("d.content_hash = $hash", &[("hash", content_hash)]).await
+    }
+
+    async fn find_document_version(&self, filename: &str, uploaded_by: &str) -> Result<Option<SourceDocument>, AppError> {
+        self.find_document_where("d.filename = $filename AND d.uploaded_by = $uploaded_by", &[("filename", filename), ("uploaded_by", uploaded_by)]).await
+    }
+
+    async fn get_chunk_hashes(&self, document_id: Uuid) -> Result<Vec<StoredChunkHash>, AppError> {
+        let q = query("MATCH (c:DocumentChunk)-[:PART_OF]->(d:Document {id: $id}) RETURN c.id as id, c.content_hash as hash").param("id", document_id.to_string());
+        let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let mut hashes = Vec::new();
+        while let Ok(Some(row)) = stream.next().await {
+            hashes.push(StoredChunkHash {
+                chunk_id: row.get("id").unwrap_or_default(),
+                content_hash: row.get("hash").unwrap_or_default(),
+            });
+        }
+        Ok(hashes)
+    }
+
+    async fn update_chunk_position(&self, chunk_id: &str, document_id: Uuid, position: usize) -> Result<(), AppError> {
+        let q = query("MATCH (c:DocumentChunk {id: $cid})-[p:PART_OF]->(d:Document {id: $did}) SET p.position = $position")
+            .param("cid", chunk_id)
+            .param("did", document_id.to_string())
+            .param("position", position as i64);
+        self.graph.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn retire_chunks(&self, chunk_ids: Vec<String>) -> Result<(), AppError> {
+        if chunk_ids.is_empty() {
+            return Ok(());
+        }
+        self.forget_relation_sources(&chunk_ids).await?;
+        let q = query("
+            MATCH (c:DocumentChunk) WHERE c.id IN $ids
+            OPTIONAL MATCH (c)-[:MENTIONS]->(e:Entity)
+            WITH collect(DISTINCT c) as chunks, collect(DISTINCT e) as entities
+            FOREACH (c IN chunks | DETACH DELETE c)
+            WITH entities
+            UNWIND entities as e
+            WITH e WHERE NOT (e)<-[:MENTIONS]-(:DocumentChunk)
+            DETACH DELETE e
+        ").param("ids", chunk_ids);
+        self.graph.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    // --- TRABAJOS DE INGESTA ---
+    async fn create_ingestion_job(&self, job: &IngestionJob, payload: &IngestionPayload) -> Result<(), AppError> {
+        let q = query("CREATE (j:IngestionJob {id: $id, filename: $filename, mime_type: $mime_type, uploaded_by: $uploaded_by, content_hash: $content_hash, replace: $replace, status: $status, chunking: $chunking, document_id: '', total_chunks: 0, completed_chunks: 0, error: '', created_at: $created_at, updated_at: $updated_at, content: $content, pages: $pages, segments: $segments, context: $context, structured: $structured, metadata: $metadata})")
+            .param("id", job.id.as_str())
+            .param("filename", job.filename.as_str())
+            .param("mime_type", job.mime_type.as_str())
+            .param("uploaded_by", job.uploaded_by.as_str())
+            .param("content_hash", job.content_hash.as_str())
+            .param("replace", job.replace)
+            .param("status", job.status.to_string())
+            .param("chunking", serde_json::to_string(&job.chunking).unwrap_or_default())
+            .param("created_at", job.created_at.as_str())
+            .param("updated_at", job.updated_at.as_str())
+            .param("content", payload.content.as_str())
+            .param("pages", serde_json::to_string(&payload.pages).unwrap_or_default())
+            .param("segments", serde_json::to_string(&payload.segments).unwrap_or_default())
+            .param("context", payload.context.clone())
+            .param("structured", payload.structured.as_ref().and_then(|spans| serde_json::to_string(spans).ok()))
+            .param("metadata", serde_json::to_string(&payload.metadata).unwrap_or_default());
+        self.graph.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn update_ingestion_job(&self, job: &IngestionJob) -> Result<(), AppError> {
+        // Una cancelación es definitiva: el worker no puede pisarla con su siguiente checkpoint.
+        // Un trabajo terminado ya no se reanuda: se borra su copia del documento (texto y metadatos)
+        let q = query("MATCH (j:IngestionJob {id: $id}) SET j.status = CASE WHEN j.status = 'Cancelled' THEN j.status ELSE $status END, j.document_id = $document_id, j.total_chunks = $total, j.completed_chunks = $completed, j.error = $error, j.updated_at = $updated_at \
+            WITH j WHERE j.status IN ['Done', 'Failed', 'Cancelled'] REMOVE j.content, j.pages, j.segments, j.context, j.structured, j.metadata")
+            .param("id", job.id.as_str())
+            .param("status", job.status.to_string())
+            .param("document_id", job.document_id.clone().unwrap_or_default())
+            .param("total", job.total_chunks as i64)
+            .param("completed", job.completed_chunks as i64)
+            .param("error", job.error.clone().unwrap_or_default())
+            .param("updated_at", chrono::Utc::now().to_rfc3339());
+        self.graph.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn get_ingestion_job(&self, id: &str) -> Result<Option<IngestionJob>, AppError> {
+        let q = query("MATCH (j:IngestionJob {id: $id}) RETURN j.id, j.filename, j.mime_type, j.uploaded_by, j.content_hash, j.replace, j.status, j.chunking, j.document_id, j.total_chunks, j.completed_chunks, j.error, j.created_at, j.updated_at").param("id", id);
+        let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        match stream.next().await {
+            Ok(Some(row)) => Ok(Some(Self::row_to_job(&row))),
+            _ => Ok(None),
+        }
+    }
+
+    async fn get_ingestion_job_payload(&self, id: &str) -> Result<Option<IngestionPayload>, AppError> {
+        let q = query("MATCH (j:IngestionJob {id: $id}) RETURN j.content, j.pages, j.segments, j.context, j.structured, j.metadata").param("id", id);
+        let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        match stream.next().await {
+            Ok(Some(row)) => Ok(row.get::<String>("j.content").ok().map(|content| IngestionPayload {
+                content,
+                pages: row.get::<String>("j.pages").ok()
+                    .and_then(|raw| serde_json::from_str(&raw).ok())
+                    .unwrap_or_default(),
+                segments: row.get::<String>("j.segments").ok()
+                    .and_then(|raw| serde_json::from_str(&raw).ok())
+                    .unwrap_or_default(),
+                context: row.get("j.context").ok(),
+                structured: row.get::<String>("j.structured").ok()
+                    .and_then(|raw| serde_json::from_str(&raw).ok()),
+                metadata: row.get::<String>("j.metadata").ok()
+                    .and_then(|raw| serde_json::from_str(&raw).ok())
+                    .unwrap_or_default(),
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    async fn update_ingestion_job_structured(&self, id: &str, spans: &[StructuredSpan]) -> Result<(), AppError> {
+        let structured = serde_json::to_string(spans).map_err(|e| AppError::ParseError(e.to_string()))?;
+        let q = query("MATCH (j:IngestionJob {id: $id}) SET j.structured = $structured, j.updated_at = $updated_at")
+            .param("id", id)
+            .param("structured", structured)
+            .param("updated_at", chrono::Utc::now().to_rfc3339());
+        self.graph.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn list_ingestion_jobs(&self, limit: usize) -> Result<Vec<IngestionJob>, AppError> {
+        let q = query("MATCH (j:IngestionJob) RETURN j.id, j.filename, j.mime_type, j.uploaded_by, j.content_hash, j.replace, j.status, j.chunking, j.document_id, j.total_chunks, j.completed_chunks, j.error, j.created_at, j.updated_at ORDER BY j.created_at DESC LIMIT $limit").param("limit", limit as i64);
+        let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let mut jobs = Vec::new();
+        while let Ok(Some(row)) = stream.next().await {
+            jobs.push(Self::row_to_job(&row));
+        }
+        Ok(jobs)
+    }
+
+    async fn list_unfinished_ingestion_jobs(&self) -> Result<Vec<IngestionJob>, AppError> {
+        let q = query("MATCH (j:IngestionJob) WHERE j.status IN ['Queued', 'Running'] RETURN j.id, j.filename, j.mime_type, j.uploaded_by, j.content_hash, j.replace, j.status, j.chunking, j.document_id, j.total_chunks, j.completed_chunks, j.error, j.created_at, j.updated_at ORDER BY j.created_at");
+        let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let mut jobs = Vec::new();
+        while let Ok(Some(row)) = stream.next().await {
+            jobs.push(Self::row_to_job(&row));
+        }
+        Ok(jobs)
+    }
+
+    async fn delete_ingestion_job(&self, id: &str) -> Result<(), AppError> {
+        let q = query("MATCH (j:IngestionJob {id: $id}) DELETE j").param("id", id);
+        self.graph.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    // --- FUENTES WEB ---
+    async fn save_web_source(&self, source: &WebSource) -> Result<(), AppError> {
+        let q = query("MERGE (w:WebSource {id: $id}) SET w.url = $url, w.sitemap = $sitemap, w.refresh_hours = $refresh_hours, w.uploaded_by = $uploaded_by, w.metadata = $metadata, w.chunking = $chunking, w.created_at = $created_at, w.last_fetched_at = $last_fetched_at, w.last_error = $last_error")
+            .param("id", source.id.as_str())
+            .param("url", source.url.as_str())
+            .param("sitemap", source.sitemap)
+            .param("refresh_hours", source.refresh_hours.map(i64::from).unwrap_or(0))
+            .param("uploaded_by", source.uploaded_by.as_str())
+            .param("metadata", serde_json::to_string(&source.metadata).unwrap_or_default())
+            .param("chunking", source.chunking.as_ref().and_then(|c| serde_json::to_string(c).ok()))
+            .param("created_at", source.created_at.as_str())
+            .param("last_fetched_at", source.last_fetched_at.clone().unwrap_or_default())
+            .param("last_error", source.last_error.clone().unwrap_or_default());
+        self.graph.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn list_web_sources(&self) -> Result<Vec<WebSource>, AppError> {
+        let q = query("MATCH (w:WebSource) RETURN w.id, w.url, w.sitemap, w.refresh_hours, w.uploaded_by, w.metadata, w.chunking, w.created_at, w.last_fetched_at, w.last_error ORDER BY w.created_at");
+        let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let mut sources = Vec::new();
+        while let Ok(Some(row)) = stream.next().await {
+            sources.push(Self::row_to_web_source(&row));
+        }
+        Ok(sources)
+    }
+
+    async fn delete_web_source(&self, id: &str) -> Result<bool, AppError> {
+        let q = query("MATCH (w:WebSource {id: $id}) DELETE w RETURN count(w) as deleted").param("id", id);
+        let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let deleted = match stream.next().await {
+            Ok(Some(row)) => row.get::<i64>("deleted").unwrap_or(0),
+            _ => 0,
+        };
+        Ok(deleted > 0)
+    }
+
+    // --- CURACIÓN ---
+    async fn list_pending_curation(&self, limit: usize) -> Result<CurationQueue, AppError> {
+        let q_entities = query("
+            MATCH (e:Entity {review_status: 'PENDING'})
+            OPTIONAL MATCH (e)<-[:MENTIONS]-(:DocumentChunk)-[:PART_OF]->(d:Document)
+            RETURN e.curation_id as id, e.name as name, e.category as category, e.extracted_at as extracted_at, collect(DISTINCT d.filename) as documents
+            ORDER BY extracted_at LIMIT $limit
+        ").param("limit", limit as i64);
+        let mut stream = self.graph.execute(q_entities).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let mut queue = CurationQueue::default();
+        while let Ok(Some(row)) = stream.next().await {
+            queue.entities.push(PendingEntity {
+                id: row.get("id").unwrap_or_default(),
+                name: row.get("name").unwrap_or_default(),
+                category: row.get("category").unwrap_or_default(),
+                extracted_at: row.get("extracted_at").unwrap_or_default(),
+                documents: row.get("documents").unwrap_or_default(),
+            });
+        }
+
+        let cypher = format!("
+            MATCH (a:Entity)-[r]->(b:Entity) WHERE r.review_status = 'PENDING'
+            RETURN r.curation_id as id, a.name as source, type(r) as relation_type, b.name as target, r.extracted_at as extracted_at, NOT ({} AND {}) as blocked
+            ORDER BY extracted_at LIMIT $limit
+        ", live("a"), live("b"));
+        let mut stream = self.graph.execute(query(&cypher).param("limit", limit as i64)).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        while let Ok(Some(row)) = stream.next().await {
+            queue.relations.push(PendingRelation {
+                id: row.get("id").unwrap_or_default(),
+                source: row.get("source").unwrap_or_default(),
+                target: row.get("target").unwrap_or_default(),
+                relation_type: row.get("relation_type").unwrap_or_default(),
+                extracted_at: row.get("extracted_at").unwrap_or_default(),
+                blocked_by_entities: row.get("blocked").unwrap_or(false),
+            });
+        }
+        Ok(queue)
+    }
+
+    async fn review_entity(&self, id: &str, decision: &CurationDecision, reviewer: &str) -> Result<bool, AppError> {
+        // Lectura, comprobación del nombre y cambio en una sola transacción; si otro cambio de
+        // nombre concurrente se adelanta, la restricción `entity_name` rechaza el commit
+        let mut txn = self.graph.start_txn().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let q_current = query("MATCH (e:Entity {curation_id: $id}) RETURN e.name as name, e.category as category").param("id", id);
+        let mut stream = txn.execute(q_current).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let (current, current_category): (String, String) = match stream.next(txn.handle()).await {
+            Ok(Some(row)) => (row.get("name").unwrap_or_default(), row.get("category").unwrap_or_default()),
+            _ => return Ok(false),
+        };
+
+        let edit = decision.action == CurationAction::Edit;
+        let new_name = decision.name.as_deref().map(str::trim).filter(|name| edit && *name != current);
+        let name_taken = |name: &str| AppError::ValidationError(format!("Ya existe una entidad llamada '{}'", name));
+        if let Some(name) = new_name {
+            let q_taken = query("MATCH (e:Entity {name: $name}) RETURN e.name").param("name", name);
+            let mut stream = txn.execute(q_taken).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            if matches!(stream.next(txn.handle()).await, Ok(Some(_))) {
+                return Err(name_taken(name));
+            }
+        }
+
+        // Un cambio de categoría cambia también la etiqueta
+        let new_category = decision.category.as_deref().map(str::trim).filter(|category| edit && *category != current_category);
+        let relabel = new_category.map(|category| {
+            let remove = category_label(&current_category).map(|label| format!(" REMOVE e:`{}`", label)).unwrap_or_default();
+            let add = category_label(category).map(|label| format!(" SET e:`{}`", label)).unwrap_or_default();
+            remove + &add
+        }).unwrap_or_default();
+        let cypher = format!("MATCH (e:Entity {{curation_id: $id}}) SET e.name = coalesce($name, e.name), e.name_key = coalesce($key, e.name_key), e.category = coalesce($category, e.category), e.review_status = $status, e.reviewed_by = $reviewer, e.reviewed_at = $reviewed_at{}", relabel);
+        let q = query(&cypher)
+            .param("id", id)
+            .param("name", new_name.map(str::to_string))
+            .param("key", new_name.map(entity_key))
+            .param("category", new_category.map(str::to_string))
+            .param("status", decision.status().as_str())
+            .param("reviewer", reviewer)
+            .param("reviewed_at", chrono::Utc::now().to_rfc3339());
+        let renamed = match txn.run(q).await {
+            Ok(()) => txn.commit().await,
+            Err(e) => Err(e),
+        };
+        renamed.map_err(|e| match new_name {
+            Some(name) if e.to_string().contains("ConstraintValidationFailed") => name_taken(name),
+            _ => AppError::DatabaseError(e.to_string()),
+        })?;
+        Ok(true)
+    }
+
+    async fn review_relation(&self, id: &str, decision: &CurationDecision, reviewer: &str) -> Result<bool, AppError> {
+        let q_current = query("MATCH (:Entity)-[r]->(:Entity) WHERE r.curation_id = $id RETURN type(r) as relation_type").param("id", id);
+        let mut stream = self.graph.execute(q_current).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let current: String = match stream.next().await {
+            Ok(Some(row)) => row.get("relation_type").unwrap_or_default(),
+            _ => return Ok(false),
+        };
+
+        let new_type = decision.relation_type.as_deref()
+            .filter(|_| decision.action == CurationAction::Edit)
+            .map(|t| t.trim().replace(' ', "_").to_uppercase())
+            .filter(|t| *t != current);
+        // El tipo de una relación no se puede cambiar en Neo4j: se recrea con sus propiedades.
+        // Si ya existía una relación de ese tipo entre ambas entidades, conserva la suya y suma la procedencia.
+        let cypher = match &new_type {
+            Some(relation_type) => format!("MATCH (a:Entity)-[r]->(b:Entity) WHERE r.curation_id = $id MERGE (a)-[n:`{}`]->(b) ON CREATE SET n += properties(r) ON MATCH SET {} SET n.review_status = $status, n.reviewed_by = $reviewer, n.reviewed_at = $reviewed_at DELETE r", relation_type, merge_provenance_set()),
+            None => "MATCH (:Entity)-[r]->(:Entity) WHERE r.curation_id = $id SET r.review_status = $status, r.reviewed_by = $reviewer, r.reviewed_at = $reviewed_at".to_string(),
+        };
+        let q = query(&cypher)
+            .param("id", id)
+            .param("status", decision.status().as_str())
+            .param("reviewer", reviewer)
+            .param("reviewed_at", chrono::Utc::now().to_rfc3339());
+        self.graph.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(true)
+    }
+
+    // --- RESOLUCIÓN DE ENTIDADES ---
+    async fn find_entities_by_keys(&self, keys: &[String]) -> Result<HashMap<String, String>, AppError> {
+        // El nombre exacto prevalece sobre el alias
+        let q = query("UNWIND $keys AS key OPTIONAL MATCH (e:Entity {name_key: key}) WITH key, head(collect(e.name)) AS direct OPTIONAL MATCH (:EntityAlias {key: key})-[:ALIAS_OF]->(c:Entity) WITH key, coalesce(direct, head(collect(c.name))) AS name WHERE name IS NOT NULL RETURN key, name")
+            .param("keys", keys.to_vec());
+        let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let mut found = HashMap::new();
+        while let Ok(Some(row)) = stream.next().await {
+            if let (Ok(key), Ok(name)) = (row.get::<String>("key"), row.get::<String>("name")) {
+                found.insert(key, name);
+            }
+        }
+        Ok(found)
+    }
+
+    async fn find_similar_entities(&self, embedding: Vec<f32>, category: &str, limit: usize) -> Result<Vec<(String, f32)>, AppError> {
+        // El índice devuelve los k más cercanos antes de filtrar por categoría: se piden más
+        let cypher = format!("CALL db.index.vector.queryNodes('entity_embeddings', {}, $embedding) YIELD node AS e, score WHERE e.category = $category RETURN e.name AS name, score ORDER BY score DESC LIMIT {}", limit * 4, limit);
+        let q = query(&cypher).param("embedding", embedding).param("category", category);
+        let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let mut candidates = Vec::new();
+        while let Ok(Some(row)) = stream.next().await {
+            if let (Ok(name), Ok(score)) = (row.get::<String>("name"), row.get::<f64>("score")) {
+                candidates.push((name, score as f32));
+            }
+        }
+        Ok(candidates)
+    }
+
+    async fn save_entity_resolution(&self, aliases: &[EntityAlias], embeddings: &[(String, Vec<f32>)]) -> Result<(), AppError> {
+        if aliases.is_empty() && embeddings.is_empty() {
+            return Ok(());
+        }
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut txn = self.graph.start_txn().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        for alias in aliases {
+            // Un alias apunta a una sola entidad: la última identificación manda
+            let q = query("MATCH (e:Entity {name: $canonical}) MERGE (a:EntityAlias {key: $key}) ON CREATE SET a.name = $alias, a.created_at = $now SET a.method = $method WITH a, e OPTIONAL MATCH (a)-[old:ALIAS_OF]->(other:Entity) WHERE other <> e DELETE old WITH DISTINCT a, e MERGE (a)-[:ALIAS_OF]->(e)")
+                .param("canonical", alias.canonical.as_str())
+                .param("key", entity_key(&alias.alias))
+                .param("alias", alias.alias.as_str())
+                .param("method", alias.method.as_str())
+                .param("now", now.as_str());
+            txn.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+        for (name, embedding) in embeddings {
+            let q = query("MATCH (e:Entity {name: $name}) WHERE e.embedding IS NULL SET e.embedding = $embedding")
+                .param("name", name.as_str())
+                .param("embedding", embedding.clone());
+            txn.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+        txn.commit().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn merge_entities(&self, source: &str, target: &str, merged_by: &str) -> Result<bool, AppError> {
+        if source == target {
+            return Err(AppError::ValidationError("Una entidad no se puede fusionar consigo misma".to_string()));
+        }
+        let cypher = format!("MATCH (s:Entity {{name: $source}}), (t:Entity {{name: $target}}) RETURN {} AS source_attributes, {} AS target_attributes", attribute_pairs("s"), attribute_pairs("t"));
+        let q_exists = query(&cypher)
+            .param("source", source)
+            .param("target", target);
+        let mut stream = self.graph.execute(q_exists).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        // Atributos de `source` que `target` no tiene: los de `target` prevalecen
+        let inherited = match stream.next().await {
+            Ok(Some(row)) => {
+                let existing = Self::row_to_attributes(&row, "target_attributes");
+                let mut inherited = Self::row_to_attributes(&row, "source_attributes");
+                inherited.retain(|key, _| !existing.contains_key(key));
+                inherited
+            },
+            _ => return Ok(false),
+        };
+
+        // Sin APOC el tipo de relación no puede ser un parámetro: se recrea tipo a tipo
+        let mut types = Vec::new();
+        for (direction, cypher) in [
+            ("out", "MATCH (:Entity {name: $source})-[r]->(:Entity) RETURN DISTINCT type(r) AS relation_type"),
+            ("in", "MATCH (:Entity)-[r]->(:Entity {name: $source}) RETURN DISTINCT type(r) AS relation_type"),
+        ] {
+            let mut stream = self.graph.execute(query(cypher).param("source", source)).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            while let Ok(Some(row)) = stream.next().await {
+                if let Ok(relation_type) = row.get::<String>("relation_type") {
+                    types.push((direction, relation_type.replace('`', "")));
+                }
+            }
+        }
+
+        let mut txn = self.graph.start_txn().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        // Las relaciones entre ambas entidades desaparecen: serían bucles sobre `target`.
+        // Si `target` ya tenía la relación, conserva su revisión y suma la procedencia de la de `source`.
+        let on_match = merge_provenance_set();
+        for (direction, relation_type) in &types {
+            let cypher = if *direction == "out" {
+                format!("MATCH (s:Entity {{name: $source}})-[r:`{0}`]->(x:Entity), (t:Entity {{name: $target}}) WHERE x <> t AND x <> s MERGE (t)-[n:`{0}`]->(x) ON CREATE SET n += properties(r) ON MATCH SET {1}", relation_type, on_match)
+            } else {
+                format!("MATCH (x:Entity)-[r:`{0}`]->(s:Entity {{name: $source}}), (t:Entity {{name: $target}}) WHERE x <> t AND x <> s MERGE (x)-[n:`{0}`]->(t) ON CREATE SET n += properties(r) ON MATCH SET {1}", relation_type, on_match)
+            };
+            txn.run(query(&cypher).param("source", source).param("target", target)).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+        let statements = [
+            "MATCH (c:DocumentChunk)-[:MENTIONS]->(:Entity {name: $source}), (t:Entity {name: $target}) MERGE (c)-[:MENTIONS]->(t)",
+            "MATCH (a:EntityAlias)-[r:ALIAS_OF]->(:Entity {name: $source}), (t:Entity {name: $target}) MERGE (a)-[:ALIAS_OF]->(t) DELETE r",
+            "MATCH (s:Entity {name: $source}), (t:Entity {name: $target}) MERGE (a:EntityAlias {key: $key}) ON CREATE SET a.name = s.name, a.created_at = $now SET a.method = 'fusión', a.merged_by = $merged_by, t.embedding = coalesce(t.embedding, s.embedding) WITH a, t OPTIONAL MATCH (a)-[old:ALIAS_OF]->(other:Entity) WHERE other <> t DELETE old WITH DISTINCT a, t MERGE (a)-[:ALIAS_OF]->(t)",
+            "MATCH (t:Entity {name: $target}) SET t += $attributes",
+            "MATCH (s:Entity {name: $source}) DETACH DELETE s",
+        ];
+        for cypher in statements {
+            let q = query(cypher)
+                .param("source", source)
+                .param("target", target)
+                .param("key", entity_key(source))
+                .param("merged_by", merged_by)
+                .param("attributes", attribute_properties(&inherited))
+                .param("now", chrono::Utc::now().to_rfc3339());
+            txn.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+        txn.commit().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(true)
+    }
+
+    async fn backfill_entity_keys(&self) -> Result<usize, AppError> {
+        let q = query("MATCH (e:Entity) WHERE e.name_key IS NULL RETURN e.name AS name");
+        let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let mut names = Vec::new();
+        while let Ok(Some(row)) = stream.next().await {
+            if let Ok(name) = row.get::<String>("name") {
+                names.push(name);
+            }
+        }
+        if names.is_empty() {
+            return Ok(0);
+        }
+        let keys: Vec<String> = names.iter().map(|name| entity_key(name)).collect();
+        let q = query("UNWIND range(0, size($names) - 1) AS i MATCH (e:Entity {name: $names[i]}) SET e.name_key = $keys[i]")
+            .param("names", names.clone())
+            .param("keys", keys);
+        self.graph.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(names.len())
+    }
+
+    async fn migrate_category_labels(&self, categories: &[String]) -> Result<usize, AppError> {
+        // Categorías de la ontología y las que ya existen en el grafo (tablas, ontologías anteriores)
+        let mut all: Vec<String> = categories.to_vec();
+        let mut stream = self.graph.execute(query("MATCH (e:Entity) RETURN DISTINCT e.category AS category")).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        while let Ok(Some(row)) = stream.next().await {
+            if let Ok(category) = row.get::<String>("category") {
+                if !all.contains(&category) {
+                    all.push(category);
+                }
+            }
+        }
+
+        let mut labelled = 0;
+        for category in &all {
+            let Some(label) = category_label(category) else { continue };
+            let index = format!("CREATE INDEX {}_name IF NOT EXISTS FOR (n:`{}`) ON (n.name)", label.to_lowercase(), label);
+            self.graph.run(query(&index)).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            let cypher = format!("MATCH (e:Entity {{category: $category}}) WHERE NOT e:`{0}` SET e:`{0}` RETURN count(e) AS labelled", label);
+            let mut stream = self.graph.execute(query(&cypher).param("category", category.as_str())).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            if let Ok(Some(row)) = stream.next().await {
+                labelled += row.get::<i64>("labelled").unwrap_or(0) as usize;
+            }
+        }
+        Ok(labelled)
+    }
+
     // --- LECTURA Y VISUALIZACIÓN ---
     async fn get_full_graph(&self) -> Result<GraphDataResponse, AppError> {
-        let q = query("MATCH (n:Entity)-[r]->(m:Entity) RETURN n.name, n.category, type(r), m.name, m.category LIMIT 1000");
+        let cypher = format!("MATCH (n:Entity)-[r]->(m:Entity) WHERE {} AND {} AND {} RETURN n.name, n.category, {} as n_attributes, type(r), {} as r_attributes, m.name, m.category, {} as m_attributes LIMIT 1000", live("n"), live("r"), live("m"), attribute_pairs("n"), attribute_pairs("r"), attribute_pairs("m"));
+        let q = query(&cypher);
         let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
         let mut nodes_vec = Vec::new();
         let mut edges_vec = Vec::new();
@@ -138,16 +972,27 @@ impl KGRepository for Neo4jRepo {
             let r_type: String = row.get("type(r)").unwrap_or_else(|_| "RELATED".to_string());
             let m_name: String = row.get("m.name").unwrap_or_else(|_| "Unknown".to_string());
             let m_cat: String = row.get("m.category").unwrap_or_else(|_| "Concept".to_string());
-            if unique_nodes.insert(n_name.clone()) { nodes_vec.push(VisNode { id: n_name.clone(), label: n_name.clone(), group: n_cat }); }
-            if unique_nodes.insert(m_name.clone()) { nodes_vec.push(VisNode { id: m_name.clone(), label: m_name.clone(), group: m_cat }); }
-            edges_vec.push(VisEdge { from: n_name, to: m_name, label: r_type });
+            if unique_nodes.insert(n_name.clone()) { nodes_vec.push(VisNode { id: n_name.clone(), label: n_name.clone(), group: n_cat, attributes: Self::row_to_attributes(&row, "n_attributes") }); }
+            if unique_nodes.insert(m_name.clone()) { nodes_vec.push(VisNode { id: m_name.clone(), label: m_name.clone(), group: m_cat, attributes: Self::row_to_attributes(&row, "m_attributes") }); }
+            edges_vec.push(VisEdge { from: n_name, to: m_name, label: r_type, attributes: Self::row_to_attributes(&row, "r_attributes"), provenance: None });
         }
         Ok(GraphDataResponse { nodes: nodes_vec, edges: edges_vec })
     }
 
-    async fn find_hybrid_context(&self, embedding: Vec<f32>, limit: usize) -> Result<Vec<HybridContext>, AppError> {
-        let q_str = format!("CALL db.index.vector.queryNodes('chunk_embeddings', {}, $embedding) YIELD node as chunk, score MATCH (chunk)-[:MENTIONS]->(e:Entity) RETURN chunk.id as id, chunk.content as content, collect(DISTINCT e.name) as entities", limit);
-        let q = query(&q_str).param("embedding", embedding);
+    async fn find_hybrid_context(&self, embedding: Vec<f32>, limit: usize, filter: &MetadataFilter) -> Result<Vec<HybridContext>, AppError> {
+        filter.validate()?;
+        let (conditions, params) = Self::metadata_conditions(filter);
+        // El índice vectorial devuelve los k más cercanos antes de filtrar: con filtro se piden más candidatos
+        let (candidates, filter_clause) = if conditions.is_empty() {
+            (limit, String::new())
+        } else {
+            ((limit * 20).min(500), format!(" WHERE {} WITH chunk, score ORDER BY score DESC LIMIT {}", conditions.join(" AND "), limit))
+        };
+        let q_str = format!("CALL db.index.vector.queryNodes('chunk_embeddings', {}, $embedding) YIELD node as chunk, score{} MATCH (chunk)-[:MENTIONS]->(e:Entity) WHERE {} OPTIONAL MATCH (chunk)-[:PART_OF]->(d:Document) RETURN chunk.id as id, chunk.content as content, d.filename as document, chunk.char_start as char_start, chunk.char_end as char_end, chunk.page_start as page_start, chunk.page_end as page_end, chunk.time_start as time_start, chunk.time_end as time_end, collect(DISTINCT e.name) as entities", candidates, filter_clause, live("e"));
+        let mut q = query(&q_str).param("embedding", embedding);
+        for (name, value) in params {
+            q = q.param(&name, value);
+        }
         let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
         let mut results = Vec::new();
         while let Ok(Some(row)) = stream.next().await {
@@ -155,13 +1000,16 @@ impl KGRepository for Neo4jRepo {
                 chunk_id: row.get("id").unwrap_or_else(|_| "unk".to_string()),
                 content: row.get("content").unwrap_or_default(),
                 connected_entities: row.get("entities").unwrap_or_default(),
+                document_name: row.get("document").ok(),
+                location: row.get::<i64>("char_end").ok().map(|_| Self::row_to_location(&row, "")),
             });
         }
         Ok(results)
     }
 
     async fn get_concept_neighborhood(&self, concept_name: &str) -> Result<GraphDataResponse, AppError> {
-        let q = query("MATCH (center:Entity {name: $name})-[r]-(neighbor:Entity) RETURN center.name, center.category, type(r) as rel, startNode(r) = center as is_source, neighbor.name, neighbor.category LIMIT 100").param("name", concept_name);
+        let cypher = format!("MATCH (center:Entity {{name: $name}})-[r]-(neighbor:Entity) WHERE {} AND {} AND {} RETURN center.name, center.category, {} as center_attributes, type(r) as rel, {} as r_attributes, startNode(r) = center as is_source, neighbor.name, neighbor.category, {} as neighbor_attributes, {} LIMIT 100", live("center"), live("r"), live("neighbor"), attribute_pairs("center"), attribute_pairs("r"), attribute_pairs("neighbor"), PROVENANCE_COLUMNS);
+        let q = query(&cypher).param("name", concept_name);
         let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
         let mut nodes_vec = Vec::new();
         let mut edges_vec = Vec::new();
@@ -175,18 +1023,19 @@ impl KGRepository for Neo4jRepo {
             let is_source: bool = row.get("is_source").unwrap_or(true);
             let n_name: String = row.get("neighbor.name").unwrap_or_default();
             let n_cat: String = row.get("neighbor.category").unwrap_or_else(|_| "Concept".to_string());
-            if unique_nodes.insert(c_name.clone()) { nodes_vec.push(VisNode { id: c_name.clone(), label: c_name.clone(), group: c_cat }); }
-            if unique_nodes.insert(n_name.clone()) { nodes_vec.push(VisNode { id: n_name.clone(), label: n_name.clone(), group: n_cat }); }
+            if unique_nodes.insert(c_name.clone()) { nodes_vec.push(VisNode { id: c_name.clone(), label: c_name.clone(), group: c_cat, attributes: Self::row_to_attributes(&row, "center_attributes") }); }
+            if unique_nodes.insert(n_name.clone()) { nodes_vec.push(VisNode { id: n_name.clone(), label: n_name.clone(), group: n_cat, attributes: Self::row_to_attributes(&row, "neighbor_attributes") }); }
             let (from, to) = if is_source { (c_name, n_name) } else { (n_name, c_name) };
-            edges_vec.push(VisEdge { from, to, label: rel_type });
+            edges_vec.push(VisEdge { from, to, label: rel_type, attributes: Self::row_to_attributes(&row, "r_attributes"), provenance: Some(Self::row_to_provenance(&row)) });
         }
         if !relations_found {
-             let q_fallback = query("MATCH (center:Entity {name: $name}) RETURN center.name, center.category").param("name", concept_name);
+             let cypher_fallback = format!("MATCH (center:Entity {{name: $name}}) WHERE {} RETURN center.name, center.category, {} as center_attributes", live("center"), attribute_pairs("center"));
+             let q_fallback = query(&cypher_fallback).param("name", concept_name);
              let mut stream_fallback = self.graph.execute(q_fallback).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
              if let Ok(Some(row)) = stream_fallback.next().await {
                 let name: String = row.get("center.name").unwrap_or_default();
                 let cat: String = row.get("center.category").unwrap_or_else(|_| "Concept".to_string());
-                nodes_vec.push(VisNode { id: name.clone(), label: name, group: cat });
+                nodes_vec.push(VisNode { id: name.clone(), label: name, group: cat, attributes: Self::row_to_attributes(&row, "center_attributes") });
              }
         }
         nodes_vec.sort_by(|a, b| a.id.cmp(&b.id));
@@ -196,7 +1045,8 @@ impl KGRepository for Neo4jRepo {
 
     // --- RAZONAMIENTO Y EXPORTACIÓN ---
     async fn get_graph_context_for_reasoning(&self, limit: usize) -> Result<String, AppError> {
-        let q = query("MATCH (n:Entity)-[r]->(m:Entity) WITH n, r, m, count(n) as degree ORDER BY degree DESC LIMIT $limit RETURN n.name, type(r), m.name").param("limit", limit as i64);
+        let cypher = format!("MATCH (n:Entity)-[r]->(m:Entity) WHERE {} AND {} AND {} WITH n, r, m, count(n) as degree ORDER BY degree DESC LIMIT $limit RETURN n.name, type(r), m.name", live("n"), live("r"), live("m"));
+        let q = query(&cypher).param("limit", limit as i64);
         let mut stream = self.graph.execute(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
         let mut context = String::new();
         while let Ok(Some(row)) = stream.next().await {
@@ -209,10 +1059,18 @@ impl KGRepository for Neo4jRepo {
     }
 
     async fn save_inferred_relations(&self, relations: Vec<InferredRelation>) -> Result<(), AppError> {
+        // Las inferencias también son del LLM: pasan por la misma revisión que lo extraído
+        let status = self.initial_review_status();
+        let extracted_at = chrono::Utc::now().to_rfc3339();
         let mut txn = self.graph.start_txn().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
         for rel in relations {
-            let cypher = format!("MATCH (a:Entity {{name: $source}}), (b:Entity {{name: $target}}) MERGE (a)-[r:INFERRED_{}]->(b) ON CREATE SET r.reasoning = $reasoning, r.is_ai_generated = true", rel.relation.replace(" ", "_").to_uppercase());
-            let q = query(&cypher).param("source", rel.source).param("target", rel.target).param("reasoning", rel.reasoning);
+            let cypher = format!("MATCH (a:Entity {{name: $source}}), (b:Entity {{name: $target}}) MERGE (a)-[r:`INFERRED_{}`]->(b) ON CREATE SET r.reasoning = $reasoning, r.is_ai_generated = true, r.review_status = $status, r.curation_id = randomUUID(), r.extracted_at = $extracted_at", rel.relation.replace(" ", "_").to_uppercase());
+            let q = query(&cypher)
+                .param("source", rel.source)
+                .param("target", rel.target)
+                .param("reasoning", rel.reasoning)
+                .param("status", status.clone())
+                .param("extracted_at", extracted_at.as_str());
             txn.run(q).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
         }
         txn.commit().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
@@ -220,17 +1078,23 @@ impl KGRepository for Neo4jRepo {
     }
 
     async fn export_full_knowledge_graph(&self) -> Result<ExportedGraph, AppError> {
-        let q_nodes = query("MATCH (n:Entity) RETURN n.name, n.category");
+        let q_nodes = query(&format!("MATCH (n:Entity) WHERE {} RETURN n.name, n.category, {} as attributes", live("n"), attribute_pairs("n")));
         let mut stream_nodes = self.graph.execute(q_nodes).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
         let mut nodes = Vec::new();
         while let Ok(Some(row)) = stream_nodes.next().await {
-            nodes.push(GraphEntity { name: row.get("n.name").unwrap_or_default(), category: row.get("n.category").unwrap_or("Unknown".to_string()) });
+            nodes.push(GraphEntity { name: row.get("n.name").unwrap_or_default(), category: row.get("n.category").unwrap_or("Unknown".to_string()), attributes: Self::row_to_attributes(&row, "attributes") });
         }
-        let q_edges = query("MATCH (n:Entity)-[r]->(m:Entity) RETURN n.name, type(r), m.name");
+        let q_edges = query(&format!("MATCH (n:Entity)-[r]->(m:Entity) WHERE {} AND {} AND {} RETURN n.name, type(r), m.name, {} as attributes, {}", live("n"), live("r"), live("m"), attribute_pairs("r"), PROVENANCE_COLUMNS));
         let mut stream_edges = self.graph.execute(q_edges).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
         let mut edges = Vec::new();
         while let Ok(Some(row)) = stream_edges.next().await {
-            edges.push(GraphRelation { source: row.get("n.name").unwrap_or_default(), target: row.get("m.name").unwrap_or_default(), relation_type: row.get("type(r)").unwrap_or_default() });
+            edges.push(ExportedRelation {
+                source: row.get("n.name").unwrap_or_default(),
+                target: row.get("m.name").unwrap_or_default(),
+                relation_type: row.get("type(r)").unwrap_or_default(),
+                attributes: Self::row_to_attributes(&row, "attributes"),
+                provenance: Self::row_to_provenance(&row),
+            });
         }
         Ok(ExportedGraph { generated_at: chrono::Utc::now().to_rfc3339(), domain: "Mental Health".to_string(), nodes, edges })
     }
@@ -297,26 +1161,51 @@ impl KGRepository for Neo4jRepo {
     // FIX: Usamos to::<HashMap> para leer filas genéricas sin conocer las claves de antemano.
     
     async fn get_graph_schema(&self) -> Result<String, AppError> {
-        // Labels
-        let q_labels = query("CALL db.labels()");
-        let mut stream_labels = self.graph.execute(q_labels).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
-        let mut labels = Vec::new();
-        while let Ok(Some(row)) = stream_labels.next().await {
-            if let Ok(label) = row.get::<String>("label") { labels.push(label); }
-        }
-
-        // Relationships
-        let q_rels = query("CALL db.relationshipTypes()");
-        let mut stream_rels = self.graph.execute(q_rels).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
-        let mut rels = Vec::new();
-        while let Ok(Some(row)) = stream_rels.next().await {
-            if let Ok(rel) = row.get::<String>("relationshipType") { rels.push(rel); }
-        }
-
-        let schema = format!(
-            "Graph Schema:\n- Node Labels: [{}]\n- Relationship Types: [{}]\n\nSample Structure: (Person)-[PARTICIPATES_IN]->(Intervention), (Person)-[HAS_CONDITION]->(Condition)",
-            labels.join(", "), rels.join(", ")
-        );
+        // Propiedades por etiqueta y por tipo de relación (muestreo del propio Neo4j)
+        let mut label_properties: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let q_nodes = query("CALL db.schema.nodeTypeProperties() YIELD nodeLabels, propertyName RETURN nodeLabels, propertyName");
+        let mut stream = self.graph.execute(q_nodes).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        while let Ok(Some(row)) = stream.next().await {
+            let labels: Vec<String> = row.get("nodeLabels").unwrap_or_default();
+            let property: Option<String> = row.get("propertyName").ok();
+            for label in labels {
+                let properties = label_properties.entry(label).or_default();
+                properties.extend(property.clone());
+            }
+        }
+        let mut type_properties: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let q_rels = query("CALL db.schema.relTypeProperties() YIELD relType, propertyName RETURN relType, propertyName");
+        let mut stream = self.graph.execute(q_rels).await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        while let Ok(Some(row)) = stream.next().await {
+            // relType llega como ":`TIPO`"
+            let relation_type: String = row.get::<String>("relType").unwrap_or_default().trim_start_matches(':').replace('`', "");
+            let properties = type_properties.entry(relation_type).or_default();
+            properties.extend(row.get::<String>("propertyName").ok());
+        }
+
+        // Recuentos: `count` sobre una etiqueta o tipo sale del almacén de estadísticas
+        let mut lines = vec!["Graph Schema:".to_string(), "- Node Labels (count): properties".to_string()];
+        for (label, properties) in &label_properties {
+            let cypher = format!("MATCH (n:`{}`) RETURN count(n) AS total", label.replace('`', ""));
+            let total = self.count(&cypher).await?;
+            let properties: Vec<&str> = properties.iter().map(String::as_str).filter(|p| *p != "embedding").collect();
+            lines.push(format!("  - {} ({}): {}", label, total, properties.join(", ")));
+        }
+        lines.push("- Relationship Types (count): properties".to_string());
+        for (relation_type, properties) in &type_properties {
+            let cypher = format!("MATCH ()-[r:`{}`]->() RETURN count(r) AS total", relation_type);
+            let total = self.count(&cypher).await?;
+            let properties: Vec<&str> = properties.iter().map(String::as_str).collect();
+            lines.push(format!("  - {} ({}): {}", relation_type, total, properties.join(", ")));
+        }
+        lines.push(String::new());
+        lines.push("Extracted entities carry :Entity plus their category label, e.g. (:Entity:Person {name}). Prefer the category label: MATCH (p:Person)-[:HAS_CONDITION]->(c:Condition). Entity attributes are attr_<key> properties (e.g. attr_dosis).".to_string());
+        lines.push("Sample Structure: (Person)-[PARTICIPATES_IN]->(Intervention), (Person)-[HAS_CONDITION]->(Condition)".to_string());
+
+        let schema = lines.join("\n");
+        if self.review_required {
+            return Ok(format!("{}\n\nReview: entities and relationships whose review_status is 'PENDING' or 'REJECTED' are not validated. Always filter with coalesce(x.review_status, 'APPROVED') = 'APPROVED'.", schema));
+        }
         Ok(schema)
     }
 
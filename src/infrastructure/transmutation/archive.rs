@@ -0,0 +1,48 @@
+use std::io::{Cursor, Read};
+use anyhow::{anyhow, Context, Result};
+
+/// Límites contra archivos ZIP maliciosos (bombas de descompresión)
+const MAX_ENTRIES: usize = 1000;
+const MAX_TOTAL_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Archivo contenido en un ZIP, con su ruta dentro del archivo
+pub struct ArchiveEntry {
+    pub path: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Expande un ZIP en sus archivos (en el orden del archivo), sin directorios,
+/// metadatos de macOS (`__MACOSX/`) ni archivos ocultos.
+pub fn expand_zip(data: &[u8]) -> Result<Vec<ArchiveEntry>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .context("No es un archivo ZIP válido")?;
+
+    let mut entries = Vec::new();
+    let mut total: u64 = 0;
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if file.is_dir() {
+            continue;
+        }
+        let Some(path) = file.enclosed_name().and_then(|p| p.to_str().map(str::to_string)) else { continue };
+        let hidden = path.starts_with("__MACOSX/")
+            || path.rsplit('/').next().is_some_and(|name| name.starts_with('.'));
+        if hidden {
+            continue;
+        }
+
+        if entries.len() >= MAX_ENTRIES {
+            return Err(anyhow!("El ZIP supera el máximo de {} archivos", MAX_ENTRIES));
+        }
+        // El tamaño declarado puede mentir: se limita también la lectura real
+        let remaining = MAX_TOTAL_BYTES - total;
+        let mut bytes = Vec::new();
+        (&mut file).take(remaining + 1).read_to_end(&mut bytes)?;
+        total += bytes.len() as u64;
+        if total > MAX_TOTAL_BYTES {
+            return Err(anyhow!("El ZIP descomprimido supera {} MB", MAX_TOTAL_BYTES / (1024 * 1024)));
+        }
+        entries.push(ArchiveEntry { path, bytes });
+    }
+    Ok(entries)
+}
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use anyhow::{Context, Result};
+use xml::attribute::OwnedAttribute;
+use xml::reader::{EventReader, XmlEvent};
+use super::markdown::MarkdownWriter;
+use super::{read_zip_entry, xml_attr as attr};
+
+/// Convierte un DOCX en texto estructurado tipo markdown: títulos con `#`, listas, tablas
+/// como filas `| a | b |`, y al final notas al pie, comentarios de revisión y encabezados/pies.
+pub fn parse_docx(data: &[u8]) -> Result<String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .context("No es un archivo ZIP/DOCX válido")?;
+
+    let document = read_zip_entry(&mut archive, "word/document.xml")?
+        .context("DOCX corrupto: falta document.xml")?;
+    let ctx = DocxContext {
+        headings: read_zip_entry(&mut archive, "word/styles.xml")?.map(|x| heading_styles(&x)).unwrap_or_default(),
+        ordered: read_zip_entry(&mut archive, "word/numbering.xml")?.map(|x| ordered_lists(&x)).unwrap_or_default(),
+    };
+
+    let mut text = Renderer::render(&ctx, &document).writer.out.trim_end().to_string();
+
+    // Notas al pie y notas finales, referenciadas en el cuerpo como [^N] y [^nN]
+    let mut notes = Vec::new();
+    for (part, prefix) in [("word/footnotes.xml", ""), ("word/endnotes.xml", "n")] {
+        if let Some(xml) = read_zip_entry(&mut archive, part)? {
+            notes.extend(Renderer::render(&ctx, &xml).notes.into_iter()
+                .map(|n| format!("[^{}{}]: {}", prefix, n.id, n.text)));
+        }
+    }
+    push_section(&mut text, "Notas al pie", &notes);
+
+    // Comentarios de revisión, referenciados en el cuerpo como [comentario N]
+    let comments: Vec<String> = match read_zip_entry(&mut archive, "word/comments.xml")? {
+        Some(xml) => Renderer::render(&ctx, &xml).notes.into_iter()
+            .map(|n| match n.author {
+                Some(author) => format!("- [comentario {}] ({}): {}", n.id, author, n.text),
+                None => format!("- [comentario {}]: {}", n.id, n.text),
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    push_section(&mut text, "Comentarios", &comments);
+
+    // Encabezados y pies se repiten en cada página: se incluyen una sola vez
+    let mut parts: Vec<String> = archive.file_names()
+        .filter(|n| (n.starts_with("word/header") || n.starts_with("word/footer")) && n.ends_with(".xml"))
+        .map(String::from)
+        .collect();
+    parts.sort();
+    let mut margins: Vec<String> = Vec::new();
+    for part in parts {
+        if let Some(xml) = read_zip_entry(&mut archive, &part)? {
+            let rendered = Renderer::render(&ctx, &xml).writer.out.trim().to_string();
+            if !rendered.is_empty() && !margins.contains(&rendered) {
+                margins.push(rendered);
+            }
+        }
+    }
+    push_section(&mut text, "Encabezados y pies de página", &margins);
+
+    Ok(text)
+}
+
+fn push_section(text: &mut String, title: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    text.push_str(&format!("\n\n## {}\n\n", title));
+    text.push_str(&items.join("\n"));
+}
+
+/// Estilos y numeraciones del documento necesarios para reconocer títulos y listas
+#[derive(Default)]
+struct DocxContext {
+    /// styleId -> nivel de título
+    headings: HashMap<String, usize>,
+    /// (numId, ilvl) -> lista numerada (true) o de viñetas (false)
+    ordered: HashMap<(String, u32), bool>,
+}
+
+impl DocxContext {
+    fn heading_level(&self, style_id: &str) -> Option<usize> {
+        self.headings.get(style_id).copied().or_else(|| {
+            // Sin styles.xml: los estilos integrados se llaman "Heading1", "Heading2"...
+            style_id.to_lowercase().strip_prefix("heading").and_then(|n| n.parse().ok())
+        })
+    }
+}
+
+/// Lee `styles.xml` y asocia a cada estilo de título ("heading N", "Title" o con outlineLvl) su nivel
+fn heading_styles(xml: &str) -> HashMap<String, usize> {
+    let mut headings = HashMap::new();
+    let mut current: Option<(String, Option<usize>)> = None;
+
+    for event in EventReader::from_str(xml).into_iter().flatten() {
+        match event {
+            XmlEvent::StartElement { name, attributes, .. } => match name.local_name.as_str() {
+                "style" => current = attr(&attributes, "styleId").map(|id| (id, None)),
+                "name" => if let Some((_, level)) = current.as_mut() {
+                    let style_name = attr(&attributes, "val").unwrap_or_default().to_lowercase();
+                    if style_name == "title" {
+                        *level = Some(1);
+                    } else if let Some(n) = style_name.strip_prefix("heading ").and_then(|n| n.parse().ok()) {
+                        *level = Some(n);
+                    }
+                },
+                "outlineLvl" => if let Some((_, level)) = current.as_mut() {
+                    if let Some(n) = attr(&attributes, "val").and_then(|v| v.parse::<usize>().ok()).filter(|n| *n < 9) {
+                        level.get_or_insert(n + 1);
+                    }
+                },
+                _ => {}
+            },
+            XmlEvent::EndElement { name } if name.local_name == "style" => {
+                if let Some((id, Some(level))) = current.take() {
+                    headings.insert(id, level);
+                }
+            },
+            _ => {}
+        }
+    }
+    headings
+}
+
+/// Lee `numbering.xml` y determina qué niveles de cada lista son numerados
+fn ordered_lists(xml: &str) -> HashMap<(String, u32), bool> {
+    let mut abstract_formats: HashMap<String, HashMap<u32, bool>> = HashMap::new();
+    let mut num_to_abstract: Vec<(String, String)> = Vec::new();
+    let mut abstract_id: Option<String> = None;
+    let mut level: u32 = 0;
+    let mut num_id: Option<String> = None;
+
+    for event in EventReader::from_str(xml).into_iter().flatten() {
+        if let XmlEvent::StartElement { name, attributes, .. } = event {
+            match name.local_name.as_str() {
+                "abstractNum" => abstract_id = attr(&attributes, "abstractNumId"),
+                "lvl" => level = attr(&attributes, "ilvl").and_then(|v| v.parse().ok()).unwrap_or(0),
+                "numFmt" => if let Some(id) = &abstract_id {
+                    let format = attr(&attributes, "val").unwrap_or_default();
+                    abstract_formats.entry(id.clone()).or_default()
+                        .insert(level, format != "bullet" && format != "none");
+                },
+                "num" => {
+                    abstract_id = None;
+                    num_id = attr(&attributes, "numId");
+                },
+                "abstractNumId" => if let (Some(num), Some(target)) = (num_id.take(), attr(&attributes, "val")) {
+                    num_to_abstract.push((num, target));
+                },
+                _ => {}
+            }
+        }
+    }
+
+    let mut ordered = HashMap::new();
+    for (num, target) in num_to_abstract {
+        for (level, is_ordered) in abstract_formats.get(&target).into_iter().flatten() {
+            ordered.insert((num.clone(), *level), *is_ordered);
+        }
+    }
+    ordered
+}
+
+/// Nota al pie, nota final o comentario (cada uno es un bloque con `w:id`)
+struct Note {
+    id: String,
+    author: Option<String>,
+    text: String,
+}
+
+/// Recorre un part de WordprocessingML (cuerpo, encabezado, notas o comentarios) y lo escribe como markdown
+struct Renderer<'a> {
+    ctx: &'a DocxContext,
+    writer: MarkdownWriter,
+    notes: Vec<Note>,
+    // Párrafo en curso
+    para: String,
+    heading: Option<usize>,
+    list_id: Option<String>,
+    list_level: u32,
+    in_num_pr: bool,
+    in_text: bool,
+    // Contenido alternativo duplicado (mc:Fallback) que no debe repetirse
+    fallback_depth: usize,
+    counters: HashMap<String, Vec<usize>>,
+    note: Option<(Note, usize, bool)>,
+}
+
+impl<'a> Renderer<'a> {
+    fn render(ctx: &'a DocxContext, xml: &str) -> Self {
+        let mut renderer = Renderer {
+            ctx,
+            writer: MarkdownWriter::default(),
+            notes: Vec::new(),
+            para: String::new(),
+            heading: None,
+            list_id: None,
+            list_level: 0,
+            in_num_pr: false,
+            in_text: false,
+            fallback_depth: 0,
+            counters: HashMap::new(),
+            note: None,
+        };
+        for event in EventReader::from_str(xml).into_iter().flatten() {
+            match event {
+                XmlEvent::StartElement { name, attributes, .. } => renderer.start(&name.local_name, &attributes),
+                XmlEvent::EndElement { name } => renderer.end(&name.local_name),
+                XmlEvent::Characters(s) | XmlEvent::Whitespace(s) if renderer.in_text && renderer.fallback_depth == 0 => {
+                    renderer.para.push_str(&s);
+                },
+                _ => {}
+            }
+        }
+        renderer
+    }
+
+    fn start(&mut self, name: &str, attributes: &[OwnedAttribute]) {
+        match name {
+            "p" => {
+                self.para.clear();
+                self.heading = None;
+                self.list_id = None;
+                self.list_level = 0;
+            },
+            "pStyle" => self.heading = attr(attributes, "val").and_then(|v| self.ctx.heading_level(&v)),
+            "outlineLvl" if self.heading.is_none() => {
+                self.heading = attr(attributes, "val").and_then(|v| v.parse::<usize>().ok()).filter(|n| *n < 9).map(|n| n + 1);
+            },
+            "numPr" => self.in_num_pr = true,
+            "ilvl" if self.in_num_pr => self.list_level = attr(attributes, "val").and_then(|v| v.parse().ok()).unwrap_or(0),
+            "numId" if self.in_num_pr => self.list_id = attr(attributes, "val").filter(|v| v != "0"),
+            "t" => self.in_text = true,
+            "tab" if self.fallback_depth == 0 => self.para.push('\t'),
+            "br" | "cr" if self.fallback_depth == 0 => self.para.push('\n'),
+            "commentReference" => if let Some(id) = attr(attributes, "id") {
+                self.para.push_str(&format!(" [comentario {}]", id));
+            },
+            "footnoteReference" => if let Some(id) = attr(attributes, "id") {
+                self.para.push_str(&format!("[^{}]", id));
+            },
+            "endnoteReference" => if let Some(id) = attr(attributes, "id") {
+                self.para.push_str(&format!("[^n{}]", id));
+            },
+            "tbl" => self.writer.start_table(),
+            "tr" => self.writer.start_row(),
+            "tc" => self.writer.start_cell(),
+            "Fallback" => self.fallback_depth += 1,
+            "footnote" | "endnote" | "comment" => {
+                // Los separadores de notas no tienen contenido útil
+                let separator = attr(attributes, "type").is_some_and(|t| t != "normal");
+                let note = Note {
+                    id: attr(attributes, "id").unwrap_or_default(),
+                    author: attr(attributes, "author"),
+                    text: String::new(),
+                };
+                self.note = Some((note, self.writer.out.len(), separator));
+            },
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, name: &str) {
+        match name {
+            "t" => self.in_text = false,
+            "numPr" => self.in_num_pr = false,
+            "p" => self.finish_paragraph(),
+            "tbl" => self.writer.end_table(),
+            "Fallback" => self.fallback_depth = self.fallback_depth.saturating_sub(1),
+            "footnote" | "endnote" | "comment" => if let Some((mut note, start, separator)) = self.note.take() {
+                note.text = self.writer.take_from(start);
+                if !separator && !note.text.is_empty() {
+                    self.notes.push(note);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn finish_paragraph(&mut self) {
+        let text = std::mem::take(&mut self.para);
+        let heading = self.heading.take();
+        let list_id = self.list_id.take();
+        if text.trim().is_empty() {
+            return;
+        }
+
+        match (list_id, heading) {
+            (Some(list_id), _) if !self.writer.in_table() => {
+                let level = self.list_level as usize;
+                let ordered = self.ctx.ordered.get(&(list_id.clone(), self.list_level)).copied().unwrap_or(false);
+                let counters = self.counters.entry(list_id).or_default();
+                counters.resize(level + 1, 0);
+                counters[level] += 1;
+                let marker = if ordered { format!("{}.", counters[level]) } else { "-".to_string() };
+                self.writer.list_item(level, &marker, &text);
+            },
+            (_, Some(level)) => self.writer.heading(level, &text),
+            _ => self.writer.paragraph(&text),
+        }
+    }
+}
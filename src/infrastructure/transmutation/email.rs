@@ -0,0 +1,141 @@
+use std::io::Cursor;
+use anyhow::{anyhow, Result};
+use mail_parser::{Address, Message, MessageParser, MimeHeaders};
+use mail_parser::mailbox::mbox::MessageIterator;
+use super::{DocumentTransmuter, SupportedFormat};
+
+/// Profundidad máxima de mensajes adjuntos dentro de mensajes
+const MAX_NESTING: usize = 3;
+
+/// Correo electrónico convertido a texto: cabeceras como metadatos, cuerpo y adjuntos transmutados
+pub struct EmailMessage {
+    pub subject: Option<String>,
+    pub from: Vec<String>,
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    /// Fecha en RFC 3339
+    pub date: Option<String>,
+    pub message_id: Option<String>,
+    /// Mensaje original, base de su huella de contenido
+    pub raw: Vec<u8>,
+    /// Cabecera + cuerpo + texto de los adjuntos
+    pub text: String,
+}
+
+impl EmailMessage {
+    /// Asunto, participantes y fecha en texto: se antepone a cada fragmento para la extracción de entidades
+    pub fn header_block(&self) -> String {
+        let mut lines = vec![format!("Asunto: {}", self.subject.as_deref().unwrap_or("(sin asunto)"))];
+        if !self.from.is_empty() {
+            lines.push(format!("De: {}", self.from.join(", ")));
+        }
+        if !self.to.is_empty() {
+            lines.push(format!("Para: {}", self.to.join(", ")));
+        }
+        if !self.cc.is_empty() {
+            lines.push(format!("CC: {}", self.cc.join(", ")));
+        }
+        if let Some(date) = &self.date {
+            lines.push(format!("Fecha: {}", date));
+        }
+        lines.join("\n")
+    }
+
+    /// Nombre único del documento de este mensaje dentro del archivo de origen
+    pub fn document_name(&self, source: &str, index: usize) -> String {
+        let subject = self.subject.as_deref().unwrap_or("(sin asunto)");
+        match &self.message_id {
+            Some(id) => format!("{} — {} <{}>", source, subject, id),
+            None => format!("{} — {} #{}", source, subject, index + 1),
+        }
+    }
+}
+
+/// Un archivo `.eml` (un mensaje) o `.mbox` (varios) según su extensión
+pub fn parse_mail_file(filename: &str, data: &[u8]) -> Result<Vec<EmailMessage>> {
+    if filename.to_lowercase().ends_with(".mbox") {
+        parse_mbox(data)
+    } else {
+        parse_eml(data).map(|message| vec![message])
+    }
+}
+
+pub fn parse_eml(data: &[u8]) -> Result<EmailMessage> {
+    let message = MessageParser::default().parse(data)
+        .ok_or_else(|| anyhow!("Mensaje de correo ilegible"))?;
+    Ok(convert(&message, data, 0))
+}
+
+pub fn parse_mbox(data: &[u8]) -> Result<Vec<EmailMessage>> {
+    let messages: Vec<EmailMessage> = MessageIterator::new(Cursor::new(data))
+        .flatten()
+        .filter_map(|entry| parse_eml(entry.contents()).ok())
+        .collect();
+    if messages.is_empty() {
+        return Err(anyhow!("MBOX sin mensajes legibles"));
+    }
+    Ok(messages)
+}
+
+fn convert(message: &Message, raw: &[u8], depth: usize) -> EmailMessage {
+    let mut email = EmailMessage {
+        subject: message.subject().map(str::to_string),
+        from: addresses(message.from()),
+        to: addresses(message.to()),
+        cc: addresses(message.cc()),
+        date: message.date().map(|d| d.to_rfc3339()),
+        message_id: message.message_id().map(str::to_string),
+        raw: raw.to_vec(),
+        text: String::new(),
+    };
+
+    let mut text = email.header_block();
+    let body: Vec<String> = (0..message.text_body_count())
+        .filter_map(|i| message.body_text(i))
+        .map(|b| b.trim().to_string())
+        .filter(|b| !b.is_empty())
+        .collect();
+    if !body.is_empty() {
+        text.push_str("\n\n");
+        text.push_str(&body.join("\n\n"));
+    }
+
+    for part in message.attachments() {
+        if let Some(nested) = part.message() {
+            if depth < MAX_NESTING {
+                let nested = convert(nested, part.contents(), depth + 1);
+                text.push_str(&format!("\n\n## Mensaje adjunto\n\n{}", nested.text));
+            }
+            continue;
+        }
+
+        let name = part.attachment_name().unwrap_or("adjunto");
+        let rendered = match SupportedFormat::from_filename(name) {
+            Some(SupportedFormat::EML | SupportedFormat::MBOX) if depth >= MAX_NESTING => continue,
+            Some(_) => match DocumentTransmuter::transmute(name, part.contents()) {
+                Ok(extracted) if !extracted.text.trim().is_empty() => extracted.text.trim().to_string(),
+                Ok(_) => "(sin texto extraíble)".to_string(),
+                Err(e) => format!("(no se pudo procesar: {})", e),
+            },
+            None => "(formato no soportado)".to_string(),
+        };
+        text.push_str(&format!("\n\n## Adjunto: {}\n\n{}", name, rendered));
+    }
+
+    email.text = text;
+    email
+}
+
+/// "Nombre <correo>" por cada dirección (incluidas las de grupos)
+fn addresses(address: Option<&Address>) -> Vec<String> {
+    address.map(|a| {
+        a.iter()
+            .filter_map(|addr| match (addr.name.as_deref(), addr.address.as_deref()) {
+                (Some(name), Some(email)) => Some(format!("{} <{}>", name, email)),
+                (None, Some(email)) => Some(email.to_string()),
+                (Some(name), None) => Some(name.to_string()),
+                (None, None) => None,
+            })
+            .collect()
+    }).unwrap_or_default()
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use anyhow::{Context, Result};
+use xml::reader::{EventReader, XmlEvent};
+use super::{read_zip_entry, resolve_zip_path, xml_attr as attr};
+
+/// Convierte un libro EPUB en texto: título del libro y, en el orden de lectura (spine),
+/// el texto de cada capítulo XHTML.
+pub fn parse_epub(data: &[u8]) -> Result<String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .context("No es un archivo ZIP/EPUB válido")?;
+
+    let container = read_zip_entry(&mut archive, "META-INF/container.xml")?
+        .context("EPUB corrupto: falta META-INF/container.xml")?;
+    let opf_path = EventReader::from_str(&container).into_iter().flatten()
+        .find_map(|event| match event {
+            XmlEvent::StartElement { name, attributes, .. } if name.local_name == "rootfile" => attr(&attributes, "full-path"),
+            _ => None,
+        })
+        .context("EPUB corrupto: container.xml sin rootfile")?;
+    let opf = read_zip_entry(&mut archive, &opf_path)?
+        .with_context(|| format!("EPUB corrupto: falta {}", opf_path))?;
+    let package = Package::parse(&opf);
+    let base_dir = opf_path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+
+    let mut text = String::new();
+    if let Some(title) = &package.title {
+        text.push_str(&format!("# {}\n\n", title.trim()));
+    }
+    for idref in &package.spine {
+        let Some((href, media_type)) = package.manifest.get(idref) else { continue };
+        if !media_type.contains("html") {
+            continue;
+        }
+        let path = resolve_zip_path(base_dir, href.split('#').next().unwrap_or(href));
+        let Ok(mut file) = archive.by_name(&path) else { continue };
+        let mut xhtml = Vec::new();
+        file.read_to_end(&mut xhtml)?;
+
+        let chapter = html2text::from_read(xhtml.as_slice(), 80);
+        if !chapter.trim().is_empty() {
+            text.push_str(chapter.trim());
+            text.push_str("\n\n");
+        }
+    }
+
+    if text.trim().is_empty() {
+        anyhow::bail!("EPUB sin contenido legible");
+    }
+    Ok(text.trim_end().to_string())
+}
+
+/// Lo necesario del paquete OPF: título, manifiesto (id -> href, media-type) y orden de lectura
+#[derive(Default)]
+struct Package {
+    title: Option<String>,
+    manifest: HashMap<String, (String, String)>,
+    spine: Vec<String>,
+}
+
+impl Package {
+    fn parse(opf: &str) -> Self {
+        let mut package = Package::default();
+        let mut in_title = false;
+        for event in EventReader::from_str(opf).into_iter().flatten() {
+            match event {
+                XmlEvent::StartElement { name, attributes, .. } => match name.local_name.as_str() {
+                    "title" if package.title.is_none() => in_title = true,
+                    "item" => if let (Some(id), Some(href)) = (attr(&attributes, "id"), attr(&attributes, "href")) {
+                        package.manifest.insert(id, (href, attr(&attributes, "media-type").unwrap_or_default()));
+                    },
+                    "itemref" => package.spine.extend(attr(&attributes, "idref")),
+                    _ => {}
+                },
+                XmlEvent::Characters(s) if in_title => package.title = Some(s),
+                XmlEvent::EndElement { name } if name.local_name == "title" => in_title = false,
+                _ => {}
+            }
+        }
+        package
+    }
+}
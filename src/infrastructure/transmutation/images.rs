@@ -0,0 +1,84 @@
+use std::io::Cursor;
+use anyhow::{Result, anyhow};
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, imageops::FilterType};
+use image::codecs::jpeg::JpegEncoder;
+
+/// Calidad JPEG al re-codificar fotos (sin canal alfa)
+const JPEG_QUALITY: u8 = 85;
+
+/// Normalización de imágenes antes del análisis por visión
+#[derive(Debug, Clone, Copy)]
+pub struct ImagePreparation {
+    /// Lado mayor máximo en píxeles; las imágenes más grandes se reducen
+    pub max_dimension: u32,
+}
+
+impl Default for ImagePreparation {
+    fn default() -> Self {
+        Self { max_dimension: 2048 }
+    }
+}
+
+impl ImagePreparation {
+    /// Lee IMAGE_MAX_DIMENSION
+    pub fn from_env() -> Self {
+        let max_dimension = std::env::var("IMAGE_MAX_DIMENSION").ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(Self::default().max_dimension);
+        Self { max_dimension }
+    }
+
+    /// Decodifica y valida la imagen, la endereza según su orientación EXIF, la reduce si
+    /// excede `max_dimension` y la re-codifica. El resultado no conserva metadatos (EXIF,
+    /// GPS, XMP): los codificadores solo escriben los píxeles.
+    pub fn prepare(&self, data: &[u8]) -> Result<PreparedImage> {
+        let reader = ImageReader::new(Cursor::new(data)).with_guessed_format()?;
+        match reader.format() {
+            Some(ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP | ImageFormat::Gif) => {},
+            Some(other) => return Err(anyhow!(
+                "Formato de imagen no admitido por el modelo de visión: {}. Usa JPEG, PNG, WEBP o GIF.",
+                other.extensions_str().first().copied().unwrap_or("desconocido").to_uppercase()
+            )),
+            None => return Err(anyhow!("El archivo no es una imagen reconocible (se admiten JPEG, PNG, WEBP y GIF).")),
+        }
+
+        let mut decoder = reader.into_decoder()
+            .map_err(|e| anyhow!("Imagen dañada o ilegible: {}", e))?;
+        let orientation = decoder.orientation()
+            .map_err(|e| anyhow!("Orientación de la imagen ilegible: {}", e))?;
+        let mut image = DynamicImage::from_decoder(decoder)
+            .map_err(|e| anyhow!("Imagen dañada o ilegible: {}", e))?;
+        let original = (image.width(), image.height());
+        if original.0 == 0 || original.1 == 0 {
+            return Err(anyhow!("La imagen está vacía (0 píxeles)."));
+        }
+        image.apply_orientation(orientation);
+
+        if image.width().max(image.height()) > self.max_dimension {
+            // `resize` conserva la proporción dentro del cuadrado máximo
+            image = image.resize(self.max_dimension, self.max_dimension, FilterType::Lanczos3);
+        }
+
+        let mut bytes = Vec::new();
+        let mime_type = if image.color().has_alpha() {
+            image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+            "image/png"
+        } else {
+            JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY).encode_image(&image.to_rgb8())?;
+            "image/jpeg"
+        };
+
+        Ok(PreparedImage { bytes, mime_type, width: image.width(), height: image.height(), original })
+    }
+}
+
+/// Imagen lista para enviar al modelo de visión
+pub struct PreparedImage {
+    pub bytes: Vec<u8>,
+    pub mime_type: &'static str,
+    pub width: u32,
+    pub height: u32,
+    /// Dimensiones del archivo subido (antes de enderezar y reducir)
+    pub original: (u32, u32),
+}
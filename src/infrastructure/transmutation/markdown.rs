@@ -0,0 +1,134 @@
+/// Acumula bloques markdown (párrafos, títulos, listas y tablas) para los formatos con estructura.
+/// Mientras hay una tabla abierta, todo el texto va a su celda actual.
+#[derive(Default)]
+pub struct MarkdownWriter {
+    pub out: String,
+    tables: Vec<Table>,
+    in_list_block: bool,
+}
+
+impl MarkdownWriter {
+    pub fn in_table(&self) -> bool {
+        !self.tables.is_empty()
+    }
+
+    pub fn start_table(&mut self) {
+        self.tables.push(Table::default());
+    }
+
+    pub fn start_row(&mut self) {
+        if let Some(table) = self.tables.last_mut() {
+            table.rows.push(Vec::new());
+        }
+    }
+
+    pub fn start_cell(&mut self) {
+        if let Some(row) = self.tables.last_mut().and_then(|t| t.rows.last_mut()) {
+            row.push(String::new());
+        }
+    }
+
+    /// Cierra la tabla actual; una tabla anidada se aplana dentro de la celda que la contiene
+    pub fn end_table(&mut self) {
+        let Some(table) = self.tables.pop() else { return };
+        if self.in_table() {
+            self.append_to_cell(&table.to_inline());
+        } else {
+            self.start_block(false);
+            self.out.push_str(&table.to_markdown());
+            self.out.push('\n');
+        }
+    }
+
+    pub fn paragraph(&mut self, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        if self.in_table() {
+            self.append_to_cell(text);
+            return;
+        }
+        self.start_block(false);
+        self.out.push_str(text);
+        self.out.push_str("\n\n");
+    }
+
+    pub fn heading(&mut self, level: usize, text: &str) {
+        let text = text.trim();
+        if text.is_empty() || self.in_table() {
+            return self.paragraph(text);
+        }
+        self.start_block(false);
+        self.out.push_str(&format!("{} {}\n\n", "#".repeat(level.clamp(1, 6)), text.replace('\n', " ")));
+    }
+
+    /// Elemento de lista con sangría por nivel (0 = primer nivel) y marcador ("-" o "1.")
+    pub fn list_item(&mut self, level: usize, marker: &str, text: &str) {
+        let text = text.trim();
+        if text.is_empty() || self.in_table() {
+            return self.paragraph(text);
+        }
+        self.start_block(true);
+        self.out.push_str(&format!("{}{} {}\n", "  ".repeat(level), marker, text.replace('\n', " ")));
+    }
+
+    /// Descarta lo escrito desde `len` y lo devuelve en una sola línea (notas, comentarios...)
+    pub fn take_from(&mut self, len: usize) -> String {
+        let taken = self.out[len..].split_whitespace().collect::<Vec<_>>().join(" ");
+        self.out.truncate(len);
+        self.in_list_block = false;
+        taken
+    }
+
+    /// Separa con una línea en blanco el final de una lista del bloque siguiente
+    fn start_block(&mut self, is_list: bool) {
+        if self.in_list_block && !is_list {
+            self.out.push('\n');
+        }
+        self.in_list_block = is_list;
+    }
+
+    fn append_to_cell(&mut self, text: &str) {
+        let Some(cell) = self.tables.last_mut().and_then(|t| t.rows.last_mut()).and_then(|r| r.last_mut()) else { return };
+        if text.is_empty() {
+            return;
+        }
+        if !cell.is_empty() {
+            cell.push(' ');
+        }
+        cell.push_str(&text.split_whitespace().collect::<Vec<_>>().join(" "));
+    }
+}
+
+#[derive(Default)]
+struct Table {
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn to_markdown(&self) -> String {
+        let rows: Vec<&Vec<String>> = self.rows.iter().filter(|r| r.iter().any(|c| !c.is_empty())).collect();
+        let columns = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        let mut out = String::new();
+        for (i, row) in rows.iter().enumerate() {
+            let cells: Vec<String> = (0..columns)
+                .map(|c| row.get(c).map(|s| s.replace('|', "\\|")).unwrap_or_default())
+                .collect();
+            out.push_str(&format!("| {} |\n", cells.join(" | ")));
+            if i == 0 {
+                out.push_str(&format!("|{}\n", " --- |".repeat(columns)));
+            }
+        }
+        out
+    }
+
+    /// Tabla anidada dentro de otra celda: se aplana en una línea
+    fn to_inline(&self) -> String {
+        self.rows.iter()
+            .map(|r| r.iter().filter(|c| !c.is_empty()).cloned().collect::<Vec<_>>().join(" / "))
+            .filter(|r| !r.is_empty())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
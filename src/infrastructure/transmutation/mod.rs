@@ -0,0 +1,442 @@
+use std::io::{Cursor, Read};
+use image::ImageEncoder;
+use std::path::Path;
+use anyhow::{Context, Result, anyhow};
+// CORRECCIÓN AQUÍ: Usamos 'Data' en lugar de 'DataType'
+use calamine::{Reader, open_workbook_auto_from_rs, Data};
+use lopdf::{Document, Object, ObjectId, Stream};
+use flate2::read::ZlibDecoder;
+use tokio::sync::mpsc;
+use crate::domain::models::{PageSpan, TimeSpan, Transcription};
+use crate::domain::ports::OcrEngine;
+
+pub mod archive;
+mod docx;
+pub mod email;
+mod epub;
+pub mod images;
+mod markdown;
+mod odt;
+mod pptx;
+mod rtf;
+
+#[cfg(test)]
+mod tests;
+
+/// Enumeración de tipos de documentos soportados
+#[allow(clippy::upper_case_acronyms)]
+pub enum SupportedFormat {
+    PDF,
+    DOCX,
+    XLSX,
+    XLS,
+    ODT,
+    ODS,
+    PPTX,
+    RTF,
+    EPUB,
+    EML,
+    MBOX,
+    CSV,
+    HTML,
+    PlainText,
+}
+
+impl SupportedFormat {
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let ext = Path::new(filename)
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_lowercase())?;
+
+        match ext.as_str() {
+            "pdf" => Some(Self::PDF),
+            "docx" => Some(Self::DOCX),
+            "xlsx" => Some(Self::XLSX),
+            "xls" => Some(Self::XLS),
+            "odt" => Some(Self::ODT),
+            "ods" => Some(Self::ODS),
+            "pptx" => Some(Self::PPTX),
+            "rtf" => Some(Self::RTF),
+            "epub" => Some(Self::EPUB),
+            "eml" => Some(Self::EML),
+            "mbox" => Some(Self::MBOX),
+            "csv" => Some(Self::CSV),
+            "html" | "htm" => Some(Self::HTML),
+            "txt" | "md" | "json" | "xml" | "log" => Some(Self::PlainText),
+            _ => None,
+        }
+    }
+}
+
+/// Imagen de una página escaneada (sin capa de texto), lista para OCR
+pub struct PageImage {
+    pub page: u32,
+    pub bytes: Vec<u8>,
+    pub mime_type: &'static str,
+}
+
+/// Texto extraído de un archivo junto con los rangos (en caracteres) de cada página, si el formato los tiene
+pub struct ExtractedDocument {
+    pub text: String,
+    pub pages: Vec<PageSpan>,
+    /// Tiempos de cada segmento, si el texto es la transcripción de una grabación
+    pub segments: Vec<TimeSpan>,
+    /// Páginas sin texto extraíble cuya imagen debe pasar por OCR
+    pub scanned_pages: Vec<PageImage>,
+}
+
+impl ExtractedDocument {
+    /// Documento sin paginación (DOCX, hojas de cálculo, texto plano...)
+    pub fn plain(text: String) -> Self {
+        Self { text, pages: Vec::new(), segments: Vec::new(), scanned_pages: Vec::new() }
+    }
+
+    /// Transcripción con un segmento por párrafo tras la cabecera: el chunking por párrafos
+    /// corta así en los límites de los segmentos y cada chunk sabe qué tiempos abarca
+    pub fn from_transcript(header: &str, transcription: Transcription) -> Self {
+        if transcription.segments.is_empty() {
+            return Self::plain(format!("{}\n\n{}", header, transcription.text));
+        }
+        let mut text = format!("{}\n\n", header);
+        let mut offset = text.chars().count();
+        let mut segments = Vec::new();
+        for segment in transcription.segments {
+            let len = segment.text.chars().count();
+            segments.push(TimeSpan { start_secs: segment.start_secs, end_secs: segment.end_secs, char_start: offset, char_end: offset + len });
+            text.push_str(&segment.text);
+            text.push_str("\n\n");
+            offset += len + 2;
+        }
+        Self { text, pages: Vec::new(), segments, scanned_pages: Vec::new() }
+    }
+
+    /// Une el texto de cada página (en orden) calculando sus rangos de caracteres
+    fn from_pages(page_texts: Vec<(u32, String)>, scanned_pages: Vec<PageImage>) -> Self {
+        let mut text = String::new();
+        let mut pages = Vec::new();
+        let mut offset = 0;
+        for (page, content) in page_texts {
+            let len = content.chars().count();
+            pages.push(PageSpan { page, char_start: offset, char_end: offset + len });
+            text.push_str(&content);
+            text.push_str("\n\n");
+            offset += len + 2;
+        }
+        Self { text, pages, segments: Vec::new(), scanned_pages }
+    }
+
+    /// Sustituye las páginas escaneadas por el texto reconocido, página a página.
+    /// Informa en `progress_tx` de qué páginas se han pasado por OCR.
+    pub async fn complete_with_ocr(
+        self,
+        ocr: &dyn OcrEngine,
+        progress_tx: &mpsc::Sender<String>,
+    ) -> Result<ExtractedDocument> {
+        if self.scanned_pages.is_empty() {
+            return Ok(self);
+        }
+
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut recognized = std::collections::HashMap::new();
+        for scan in &self.scanned_pages {
+            let _ = progress_tx.send(format!("🔎 Página {} sin capa de texto. Aplicando OCR...", scan.page)).await;
+            match ocr.recognize_page(&scan.bytes, scan.mime_type).await {
+                Ok(text) => { recognized.insert(scan.page, text); },
+                Err(e) => { let _ = progress_tx.send(format!("⚠️ OCR falló en la página {}: {}", scan.page, e)).await; }
+            }
+        }
+
+        let mut ocr_pages: Vec<u32> = recognized.keys().copied().collect();
+        ocr_pages.sort_unstable();
+        if !ocr_pages.is_empty() {
+            let list: Vec<String> = ocr_pages.iter().map(|p| p.to_string()).collect();
+            let _ = progress_tx.send(format!("🔎 OCR aplicado a {} página(s): {}", ocr_pages.len(), list.join(", "))).await;
+        }
+
+        let page_texts = self.pages.iter()
+            .map(|span| {
+                let text = recognized.remove(&span.page)
+                    .unwrap_or_else(|| chars[span.char_start..span.char_end].iter().collect());
+                (span.page, text)
+            })
+            .collect();
+        let document = Self::from_pages(page_texts, Vec::new());
+
+        if document.text.trim().is_empty() {
+            return Err(anyhow!("PDF escaneado sin texto reconocible tras el OCR."));
+        }
+        Ok(document)
+    }
+}
+
+pub struct DocumentTransmuter;
+
+impl DocumentTransmuter {
+    pub fn transmute(filename: &str, data: &[u8]) -> Result<ExtractedDocument> {
+        let format = SupportedFormat::from_filename(filename)
+            .ok_or_else(|| anyhow!("Formato no soportado por Transmuter: {}", filename))?;
+
+        match format {
+            SupportedFormat::PDF => Self::parse_pdf(data),
+            SupportedFormat::DOCX => docx::parse_docx(data).map(ExtractedDocument::plain),
+            SupportedFormat::XLSX | SupportedFormat::XLS | SupportedFormat::ODS => {
+                Self::parse_spreadsheet(data).map(ExtractedDocument::plain)
+            },
+            SupportedFormat::ODT => odt::parse_odt(data).map(ExtractedDocument::plain),
+            SupportedFormat::PPTX => pptx::parse_pptx(data).map(ExtractedDocument::plain),
+            SupportedFormat::RTF => rtf::parse_rtf(data).map(ExtractedDocument::plain),
+            SupportedFormat::EPUB => epub::parse_epub(data).map(ExtractedDocument::plain),
+            // Un correo dentro de otro (adjunto): todo su texto en un documento.
+            // Subidos directamente, el handler de ingesta crea un documento por mensaje.
+            SupportedFormat::EML | SupportedFormat::MBOX => email::parse_mail_file(filename, data)
+                .map(|messages| messages.into_iter().map(|m| m.text).collect::<Vec<_>>().join("\n\n---\n\n"))
+                .map(ExtractedDocument::plain),
+            SupportedFormat::CSV => Self::parse_csv(data).map(ExtractedDocument::plain),
+            SupportedFormat::HTML => Self::parse_html(data).map(ExtractedDocument::plain),
+            SupportedFormat::PlainText => {
+                String::from_utf8(data.to_vec())
+                    .map(ExtractedDocument::plain)
+                    .map_err(|e| anyhow!("Error UTF-8: {}", e))
+            },
+        }
+    }
+
+    /// Extrae el texto página a página, registrando el rango de caracteres de cada una.
+    /// Las páginas sin capa de texto se rasterizan para el OCR posterior (`complete_with_ocr`).
+    fn parse_pdf(data: &[u8]) -> Result<ExtractedDocument> {
+        let doc = Document::load_mem(data)
+            .map_err(|e| anyhow!("Fallo al cargar PDF: {}", e))?;
+        
+        let mut page_texts = Vec::new();
+        let mut scanned_pages = Vec::new();
+        for (page_num, page_id) in doc.get_pages() {
+            let content = doc.extract_text(&[page_num]).unwrap_or_default();
+            if content.trim().is_empty() {
+                if let Some((bytes, mime_type)) = Self::rasterize_page(&doc, page_id) {
+                    scanned_pages.push(PageImage { page: page_num, bytes, mime_type });
+                }
+            }
+            page_texts.push((page_num, content));
+        }
+        
+        let extracted = ExtractedDocument::from_pages(page_texts, scanned_pages);
+        if extracted.text.trim().is_empty() && extracted.scanned_pages.is_empty() {
+             return Err(anyhow!("PDF vacío o escaneado sin imágenes legibles para OCR."));
+        }
+        Ok(extracted)
+    }
+
+    /// Imagen de una página escaneada: la mayor imagen referenciada en sus recursos,
+    /// en JPEG tal cual o reconstruida como PNG a partir de los píxeles (FlateDecode / sin filtro).
+    /// No se renderiza la página: solo sirve para escaneos guardados como una imagen por página.
+    /// Limitaciones: las páginas que mezclan texto e imágenes conservan solo su capa de texto
+    /// (el texto de sus imágenes se pierde), y los escaneos vectoriales o con imágenes
+    /// CCITT/JBIG2/JPX se quedan sin OCR.
+    fn rasterize_page(doc: &Document, page_id: ObjectId) -> Option<(Vec<u8>, &'static str)> {
+        let (own_resources, inherited) = doc.get_page_resources(page_id);
+        let resources = own_resources.into_iter()
+            .chain(inherited.into_iter().filter_map(|id| doc.get_dictionary(id).ok()));
+
+        let mut largest: Option<(i64, &Stream)> = None;
+        for dict in resources {
+            let Ok(xobjects) = dict.get_deref(b"XObject", doc).and_then(Object::as_dict) else { continue };
+            for (_, obj) in xobjects.iter() {
+                let Ok((_, Object::Stream(stream))) = doc.dereference(obj) else { continue };
+                if stream.dict.get(b"Subtype").and_then(Object::as_name_str).ok() != Some("Image") {
+                    continue;
+                }
+                let area = Self::image_dimension(stream, b"Width") * Self::image_dimension(stream, b"Height");
+                if largest.is_none_or(|(best, _)| area > best) {
+                    largest = Some((area, stream));
+                }
+            }
+        }
+
+        let (_, stream) = largest?;
+        let filters = stream.filters().unwrap_or_default();
+        match filters.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+            ["DCTDecode"] => Some((stream.content.clone(), "image/jpeg")),
+            ["FlateDecode"] => {
+                let mut pixels = Vec::new();
+                ZlibDecoder::new(stream.content.as_slice()).read_to_end(&mut pixels).ok()?;
+                Self::encode_png(doc, stream, pixels)
+            },
+            [] => Self::encode_png(doc, stream, stream.content.clone()),
+            // CCITT, JBIG2, JPX...: sin decodificador disponible
+            _ => None,
+        }
+    }
+
+    fn image_dimension(stream: &Stream, key: &[u8]) -> i64 {
+        stream.dict.get(key).and_then(Object::as_i64).unwrap_or(0)
+    }
+
+    /// Codifica como PNG píxeles de 8 bits en escala de grises o RGB (sin predictores)
+    fn encode_png(doc: &Document, stream: &Stream, pixels: Vec<u8>) -> Option<(Vec<u8>, &'static str)> {
+        let width = Self::image_dimension(stream, b"Width") as u32;
+        let height = Self::image_dimension(stream, b"Height") as u32;
+        let bits = stream.dict.get(b"BitsPerComponent").and_then(Object::as_i64).unwrap_or(8);
+        let has_predictor = stream.dict.get_deref(b"DecodeParms", doc)
+            .and_then(Object::as_dict)
+            .and_then(|p| p.get(b"Predictor"))
+            .and_then(Object::as_i64)
+            .is_ok_and(|p| p > 1);
+        if bits != 8 || has_predictor {
+            return None;
+        }
+
+        let color_type = match Self::color_components(doc, stream)? {
+            1 => image::ExtendedColorType::L8,
+            3 => image::ExtendedColorType::Rgb8,
+            _ => return None,
+        };
+        let mut png = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut png)
+            .write_image(&pixels, width, height, color_type)
+            .ok()?;
+        Some((png, "image/png"))
+    }
+
+    fn color_components(doc: &Document, stream: &Stream) -> Option<i64> {
+        let space = stream.dict.get_deref(b"ColorSpace", doc).ok()?;
+        match space {
+            Object::Name(name) => match name.as_slice() {
+                b"DeviceGray" | b"CalGray" => Some(1),
+                b"DeviceRGB" | b"CalRGB" => Some(3),
+                _ => None,
+            },
+            // [/ICCBased <stream>]: el número de componentes está en /N
+            Object::Array(items) if items.len() == 2 => {
+                let (_, profile) = doc.dereference(&items[1]).ok()?;
+                profile.as_stream().ok()?.dict.get(b"N").and_then(Object::as_i64).ok()
+            },
+            _ => None,
+        }
+    }
+
+    /// Hojas de cálculo XLSX, XLS (BIFF) y ODS: el lector automático de calamine detecta el formato
+    fn parse_spreadsheet(data: &[u8]) -> Result<String> {
+        let cursor = Cursor::new(data);
+        let mut workbook = open_workbook_auto_from_rs(cursor)
+            .map_err(|e| anyhow!("Error abriendo hoja de cálculo: {}", e))?;
+
+        let mut text = String::new();
+        
+        for sheet_name in workbook.sheet_names().to_vec() {
+            if let Ok(range) = workbook.worksheet_range(&sheet_name) {
+                text.push_str(&format!("--- HOJA: {} ---\n", sheet_name));
+                for row in range.rows() {
+                    let row_str: Vec<String> = row.iter().map(Self::cell_text).collect();
+                    text.push_str(&row_str.join(" | ")); 
+                    text.push('\n');
+                }
+                text.push('\n');
+            }
+        }
+        Ok(text)
+    }
+
+    fn cell_text(cell: &Data) -> String {
+        match cell {
+            // CORRECCIÓN AQUÍ: Usamos Data::Enum
+            Data::String(s) => s.to_string(),
+            Data::Float(f) => f.to_string(),
+            Data::Int(i) => i.to_string(),
+            Data::Bool(b) => b.to_string(),
+            Data::DateTimeIso(s) | Data::DurationIso(s) => s.to_string(),
+            // Manejo de celdas vacías, errores o DateTime
+            _ => "".to_string() 
+        }
+    }
+
+    /// Filas de una tabla (CSV o una hoja de XLSX/XLS/ODS) como celdas de texto; la primera es la cabecera.
+    /// Sin `sheet` se usa la primera hoja.
+    pub fn read_table(filename: &str, data: &[u8], sheet: Option<&str>) -> Result<Vec<Vec<String>>> {
+        match SupportedFormat::from_filename(filename) {
+            Some(SupportedFormat::CSV) => {
+                let mut rdr = csv::ReaderBuilder::new()
+                    .has_headers(false)
+                    .flexible(true)
+                    .from_reader(Cursor::new(data));
+                rdr.records()
+                    .map(|record| Ok(record?.iter().map(|s| s.trim().to_string()).collect()))
+                    .collect()
+            },
+            Some(SupportedFormat::XLSX | SupportedFormat::XLS | SupportedFormat::ODS) => {
+                let mut workbook = open_workbook_auto_from_rs(Cursor::new(data))
+                    .map_err(|e| anyhow!("Error abriendo hoja de cálculo: {}", e))?;
+                let sheet_name = match sheet {
+                    Some(name) => name.to_string(),
+                    None => workbook.sheet_names().first().cloned()
+                        .ok_or_else(|| anyhow!("Hoja de cálculo sin hojas"))?,
+                };
+                let range = workbook.worksheet_range(&sheet_name)
+                    .map_err(|e| anyhow!("Hoja '{}' no disponible: {}", sheet_name, e))?;
+                Ok(range.rows()
+                    .map(|row| row.iter().map(|c| Self::cell_text(c).trim().to_string()).collect())
+                    .collect())
+            },
+            _ => Err(anyhow!("Formato no tabular: {}", filename)),
+        }
+    }
+
+    fn parse_csv(data: &[u8]) -> Result<String> {
+        let mut rdr = csv::Reader::from_reader(Cursor::new(data));
+        let mut text = String::new();
+        
+        if let Ok(headers) = rdr.headers() {
+            let header_line: Vec<String> = headers.iter().map(|s| s.to_string()).collect();
+            text.push_str(&header_line.join(" | "));
+            text.push_str("\n---\n");
+        }
+
+        for record in rdr.records().flatten() {
+            let row: Vec<String> = record.iter().map(|s| s.to_string()).collect();
+            text.push_str(&row.join(" | "));
+            text.push('\n');
+        }
+        Ok(text)
+    }
+
+    pub fn parse_html(data: &[u8]) -> Result<String> {
+        let html_string = String::from_utf8(data.to_vec())
+            .context("El HTML no es UTF-8 válido")?;
+        let text = html2text::from_read(html_string.as_bytes(), 80); 
+        Ok(text)
+    }
+}
+/// Lee una entrada de texto de un contenedor ZIP (OOXML, OpenDocument, EPUB); `None` si no existe
+fn read_zip_entry<R: Read + std::io::Seek>(archive: &mut zip::ZipArchive<R>, name: &str) -> Result<Option<String>> {
+    match archive.by_name(name) {
+        Ok(mut file) => {
+            let mut content = String::new();
+            file.read_to_string(&mut content)?;
+            Ok(Some(content))
+        },
+        Err(zip::result::ZipError::FileNotFound) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Resuelve un destino relativo (`../notesSlides/n1.xml`) respecto al directorio de la entrada que lo referencia
+fn resolve_zip_path(base_dir: &str, target: &str) -> String {
+    let mut segments: Vec<&str> = if target.starts_with('/') {
+        Vec::new()
+    } else {
+        base_dir.split('/').filter(|s| !s.is_empty()).collect()
+    };
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {},
+            ".." => { segments.pop(); },
+            s => segments.push(s),
+        }
+    }
+    segments.join("/")
+}
+
+/// Valor de un atributo XML por nombre local (sin prefijo de espacio de nombres)
+fn xml_attr(attributes: &[xml::attribute::OwnedAttribute], name: &str) -> Option<String> {
+    attributes.iter().find(|a| a.name.local_name == name).map(|a| a.value.clone())
+}
\ No newline at end of file
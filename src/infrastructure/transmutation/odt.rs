@@ -0,0 +1,126 @@
+use std::io::Cursor;
+use anyhow::{Context, Result};
+use xml::attribute::OwnedAttribute;
+use xml::reader::{EventReader, XmlEvent};
+use super::markdown::MarkdownWriter;
+use super::{read_zip_entry, xml_attr as attr};
+
+/// Convierte un documento OpenDocument (ODT) en texto estructurado tipo markdown:
+/// títulos por `outline-level`, listas anidadas, tablas, y notas/comentarios en línea.
+pub fn parse_odt(data: &[u8]) -> Result<String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .context("No es un archivo ZIP/ODT válido")?;
+    let content = read_zip_entry(&mut archive, "content.xml")?
+        .context("ODT corrupto: falta content.xml")?;
+
+    let mut renderer = Renderer::default();
+    for event in EventReader::from_str(&content).into_iter().flatten() {
+        match event {
+            XmlEvent::StartElement { name, attributes, .. } => renderer.start(&name.local_name, &attributes),
+            XmlEvent::EndElement { name } => renderer.end(&name.local_name),
+            XmlEvent::Characters(s) | XmlEvent::Whitespace(s) => renderer.text(&s),
+            _ => {}
+        }
+    }
+    Ok(renderer.writer.out.trim_end().to_string())
+}
+
+/// Nota al pie o comentario (office:annotation) que se está leyendo; su texto se inserta en el párrafo
+struct Inline {
+    kind: &'static str,
+    author: Option<String>,
+    text: String,
+}
+
+#[derive(Default)]
+struct Renderer {
+    writer: MarkdownWriter,
+    para: String,
+    heading: Option<usize>,
+    list_depth: usize,
+    inlines: Vec<Inline>,
+    // Metadatos que no forman parte del texto (número de cita, fecha del comentario...)
+    skip_depth: usize,
+    in_author: bool,
+}
+
+impl Renderer {
+    fn buffer(&mut self) -> &mut String {
+        match self.inlines.last_mut() {
+            Some(inline) => &mut inline.text,
+            None => &mut self.para,
+        }
+    }
+
+    fn text(&mut self, s: &str) {
+        if self.skip_depth > 0 {
+            return;
+        }
+        if self.in_author {
+            if let Some(inline) = self.inlines.last_mut() {
+                inline.author.get_or_insert_with(String::new).push_str(s);
+            }
+            return;
+        }
+        self.buffer().push_str(s);
+    }
+
+    fn start(&mut self, name: &str, attributes: &[OwnedAttribute]) {
+        match name {
+            "h" if self.inlines.is_empty() => {
+                self.para.clear();
+                self.heading = Some(attr(attributes, "outline-level").and_then(|v| v.parse().ok()).unwrap_or(1));
+            },
+            "p" if self.inlines.is_empty() => {
+                self.para.clear();
+                self.heading = None;
+            },
+            // Espacios repetidos comprimidos: <text:s text:c="3"/>
+            "s" => {
+                let count = attr(attributes, "c").and_then(|v| v.parse().ok()).unwrap_or(1);
+                self.buffer().push_str(&" ".repeat(count));
+            },
+            "tab" => self.buffer().push('\t'),
+            "line-break" => self.buffer().push('\n'),
+            "list" => self.list_depth += 1,
+            "table" => self.writer.start_table(),
+            "table-row" => self.writer.start_row(),
+            "table-cell" | "covered-table-cell" => self.writer.start_cell(),
+            "annotation" => self.inlines.push(Inline { kind: "comentario", author: None, text: String::new() }),
+            "note" => self.inlines.push(Inline { kind: "nota", author: None, text: String::new() }),
+            "creator" if !self.inlines.is_empty() => self.in_author = true,
+            "date" if !self.inlines.is_empty() => self.skip_depth += 1,
+            "note-citation" => self.skip_depth += 1,
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, name: &str) {
+        match name {
+            "p" | "h" if !self.inlines.is_empty() => self.buffer().push(' '),
+            "p" | "h" => {
+                let text = std::mem::take(&mut self.para);
+                match self.heading.take() {
+                    Some(level) => self.writer.heading(level, &text),
+                    None if self.list_depth > 0 => self.writer.list_item(self.list_depth - 1, "-", &text),
+                    None => self.writer.paragraph(&text),
+                }
+            },
+            "list" => self.list_depth = self.list_depth.saturating_sub(1),
+            "table" => self.writer.end_table(),
+            "annotation" | "note" => if let Some(inline) = self.inlines.pop() {
+                let text = inline.text.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !text.is_empty() {
+                    let rendered = match inline.author {
+                        Some(author) => format!(" [{} ({}): {}]", inline.kind, author.trim(), text),
+                        None => format!(" [{}: {}]", inline.kind, text),
+                    };
+                    self.buffer().push_str(&rendered);
+                }
+            },
+            "creator" => self.in_author = false,
+            "note-citation" | "date" => self.skip_depth = self.skip_depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+}
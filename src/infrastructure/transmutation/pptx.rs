@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek};
+use anyhow::{Context, Result};
+use xml::reader::{EventReader, XmlEvent};
+use super::markdown::MarkdownWriter;
+use super::{read_zip_entry, resolve_zip_path, xml_attr as attr};
+
+/// Convierte una presentación PPTX en una sección por diapositiva (en el orden de la presentación),
+/// con su título, el texto y las tablas de sus formas, y las notas del orador.
+pub fn parse_pptx(data: &[u8]) -> Result<String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .context("No es un archivo ZIP/PPTX válido")?;
+
+    let slides = slide_order(&mut archive)?;
+    if slides.is_empty() {
+        anyhow::bail!("PPTX sin diapositivas");
+    }
+
+    let mut text = String::new();
+    for (index, slide_path) in slides.iter().enumerate() {
+        let Some(xml) = read_zip_entry(&mut archive, slide_path)? else { continue };
+        let slide = render_shapes(&xml, false);
+
+        match slide.title {
+            Some(title) => text.push_str(&format!("## Diapositiva {}: {}\n\n", index + 1, title)),
+            None => text.push_str(&format!("## Diapositiva {}\n\n", index + 1)),
+        }
+        text.push_str(slide.body.trim_end());
+        text.push_str("\n\n");
+
+        if let Some(notes_path) = notes_for_slide(&mut archive, slide_path)? {
+            if let Some(notes_xml) = read_zip_entry(&mut archive, &notes_path)? {
+                let notes = render_shapes(&notes_xml, true).body.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !notes.is_empty() {
+                    text.push_str(&format!("Notas del orador: {}\n\n", notes));
+                }
+            }
+        }
+    }
+    Ok(text.trim_end().to_string())
+}
+
+/// Rutas de las diapositivas según `p:sldIdLst` de presentation.xml; si falta, por número de archivo
+fn slide_order<R: Read + Seek>(archive: &mut zip::ZipArchive<R>) -> Result<Vec<String>> {
+    let rels = read_zip_entry(archive, "ppt/_rels/presentation.xml.rels")?
+        .map(|xml| relationships(&xml, "ppt"))
+        .unwrap_or_default();
+
+    if let Some(presentation) = read_zip_entry(archive, "ppt/presentation.xml")? {
+        let ordered: Vec<String> = EventReader::from_str(&presentation).into_iter().flatten()
+            .filter_map(|event| match event {
+                // `id` numérico y `r:id` comparten nombre local: se busca el de relaciones
+                XmlEvent::StartElement { name, attributes, .. } if name.local_name == "sldId" => attributes.iter()
+                    .find(|a| a.name.local_name == "id" && a.name.prefix.as_deref() == Some("r"))
+                    .and_then(|a| rels.get(&a.value).map(|(_, target)| target.clone())),
+                _ => None,
+            })
+            .collect();
+        if !ordered.is_empty() {
+            return Ok(ordered);
+        }
+    }
+
+    let mut slides: Vec<(u32, String)> = archive.file_names()
+        .filter_map(|name| {
+            let number = name.strip_prefix("ppt/slides/slide")?.strip_suffix(".xml")?.parse().ok()?;
+            Some((number, name.to_string()))
+        })
+        .collect();
+    slides.sort();
+    Ok(slides.into_iter().map(|(_, name)| name).collect())
+}
+
+/// Notas del orador enlazadas desde `ppt/slides/_rels/slideN.xml.rels`
+fn notes_for_slide<R: Read + Seek>(archive: &mut zip::ZipArchive<R>, slide_path: &str) -> Result<Option<String>> {
+    let (dir, file) = slide_path.rsplit_once('/').unwrap_or(("", slide_path));
+    let rels_path = format!("{}/_rels/{}.rels", dir, file);
+    Ok(read_zip_entry(archive, &rels_path)?.and_then(|xml| {
+        relationships(&xml, dir).into_values()
+            .find(|(kind, _)| kind.ends_with("/notesSlide"))
+            .map(|(_, target)| target)
+    }))
+}
+
+/// Id -> (tipo, ruta resuelta) de un archivo .rels
+fn relationships(xml: &str, base_dir: &str) -> HashMap<String, (String, String)> {
+    EventReader::from_str(xml).into_iter().flatten()
+        .filter_map(|event| match event {
+            XmlEvent::StartElement { name, attributes, .. } if name.local_name == "Relationship" => {
+                let id = attr(&attributes, "Id")?;
+                let kind = attr(&attributes, "Type").unwrap_or_default();
+                let target = resolve_zip_path(base_dir, &attr(&attributes, "Target")?);
+                Some((id, (kind, target)))
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+struct RenderedSlide {
+    title: Option<String>,
+    body: String,
+}
+
+/// Texto de las formas de una diapositiva. Los marcadores de título se separan como título;
+/// en las notas (`notes_only`) solo cuenta el cuerpo, no la miniatura ni el número de página.
+fn render_shapes(xml: &str, notes_only: bool) -> RenderedSlide {
+    let mut writer = MarkdownWriter::default();
+    let mut titles: Vec<String> = Vec::new();
+    let mut placeholder: Option<String> = None;
+    let mut para = String::new();
+    let mut in_text = false;
+
+    for event in EventReader::from_str(xml).into_iter().flatten() {
+        match event {
+            XmlEvent::StartElement { name, attributes, .. } => match name.local_name.as_str() {
+                "sp" | "graphicFrame" => placeholder = None,
+                "ph" => placeholder = Some(attr(&attributes, "type").unwrap_or_else(|| "body".to_string())),
+                "p" => para.clear(),
+                "t" => in_text = true,
+                "br" => para.push('\n'),
+                "tbl" => writer.start_table(),
+                "tr" => writer.start_row(),
+                "tc" => writer.start_cell(),
+                _ => {}
+            },
+            XmlEvent::EndElement { name } => match name.local_name.as_str() {
+                "t" => in_text = false,
+                "p" => {
+                    let text = std::mem::take(&mut para);
+                    match placeholder.as_deref() {
+                        Some("title" | "ctrTitle") if !writer.in_table() => {
+                            if !text.trim().is_empty() {
+                                titles.push(text.trim().to_string());
+                            }
+                        },
+                        Some("body") => writer.paragraph(&text),
+                        _ if notes_only => {},
+                        _ => writer.paragraph(&text),
+                    }
+                },
+                "tbl" => writer.end_table(),
+                _ => {}
+            },
+            XmlEvent::Characters(s) | XmlEvent::Whitespace(s) if in_text => para.push_str(&s),
+            _ => {}
+        }
+    }
+
+    RenderedSlide {
+        title: (!titles.is_empty()).then(|| titles.join(" ")),
+        body: writer.out,
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::{anyhow, Result};
+
+/// Destinos RTF cuyo contenido no es texto del documento (tablas de fuentes, metadatos, imágenes...)
+const SKIPPED_DESTINATIONS: &[&str] = &[
+    "fonttbl", "colortbl", "stylesheet", "info", "pict", "object", "themedata", "colorschememapping",
+    "datastore", "latentstyles", "listtable", "listoverridetable", "rsidtbl", "generator", "xmlnstbl",
+    "mmathPr", "fldinst", "filetbl", "revtbl", "pgdsctbl", "header", "footer", "headerl", "headerr",
+    "headerf", "footerl", "footerr", "footerf", "nonshppict", "shppict",
+];
+
+/// Caracteres 0x80-0x9F de Windows-1252 (el resto coincide con Latin-1)
+const CP1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+#[derive(Clone, Copy)]
+struct GroupState {
+    skip: bool,
+    /// Caracteres alternativos que siguen a cada `\uN` (`\ucN`)
+    unicode_skip: usize,
+}
+
+/// Extrae el texto de un documento RTF: párrafos y saltos de línea, filas de tabla separadas por `|`,
+/// caracteres `\'hh` (Windows-1252) y `\uN`. Ignora fuentes, estilos, metadatos e imágenes.
+pub fn parse_rtf(data: &[u8]) -> Result<String> {
+    if !data.starts_with(b"{\\rtf") {
+        return Err(anyhow!("No es un documento RTF válido"));
+    }
+
+    let mut text = String::new();
+    let mut state = GroupState { skip: false, unicode_skip: 1 };
+    let mut stack: Vec<GroupState> = Vec::new();
+    // Caracteres alternativos pendientes de descartar tras un `\uN`
+    let mut pending_skip = 0usize;
+    let mut i = 0;
+
+    while i < data.len() {
+        match data[i] {
+            b'{' => {
+                stack.push(state);
+                i += 1;
+            },
+            b'}' => {
+                state = stack.pop().unwrap_or(state);
+                pending_skip = 0;
+                i += 1;
+            },
+            b'\\' => {
+                i += 1;
+                let Some(&next) = data.get(i) else { break };
+                match next {
+                    b'\'' => {
+                        let hex = data.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+                        i += 3;
+                        if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                            if pending_skip > 0 {
+                                pending_skip -= 1;
+                            } else if !state.skip {
+                                text.push(cp1252(byte));
+                            }
+                        }
+                    },
+                    b'*' => {
+                        state.skip = true;
+                        i += 1;
+                    },
+                    b'\\' | b'{' | b'}' => {
+                        if !state.skip {
+                            text.push(next as char);
+                        }
+                        i += 1;
+                    },
+                    b'~' => {
+                        if !state.skip {
+                            text.push(' ');
+                        }
+                        i += 1;
+                    },
+                    b'_' => {
+                        if !state.skip {
+                            text.push('-');
+                        }
+                        i += 1;
+                    },
+                    b'\n' | b'\r' => {
+                        if !state.skip {
+                            text.push('\n');
+                        }
+                        i += 1;
+                    },
+                    c if c.is_ascii_alphabetic() => {
+                        let start = i;
+                        while data.get(i).is_some_and(u8::is_ascii_alphabetic) {
+                            i += 1;
+                        }
+                        let word = std::str::from_utf8(&data[start..i]).unwrap_or_default();
+                        let param_start = i;
+                        if data.get(i) == Some(&b'-') {
+                            i += 1;
+                        }
+                        while data.get(i).is_some_and(u8::is_ascii_digit) {
+                            i += 1;
+                        }
+                        let param: Option<i32> = std::str::from_utf8(&data[param_start..i]).ok().and_then(|p| p.parse().ok());
+                        // Un espacio tras la palabra de control es su delimitador
+                        if data.get(i) == Some(&b' ') {
+                            i += 1;
+                        }
+
+                        if SKIPPED_DESTINATIONS.contains(&word) {
+                            state.skip = true;
+                            continue;
+                        }
+                        if word == "uc" {
+                            state.unicode_skip = param.unwrap_or(1).max(0) as usize;
+                            continue;
+                        }
+                        if state.skip {
+                            continue;
+                        }
+                        match word {
+                            "par" | "line" | "sect" | "page" => text.push('\n'),
+                            "tab" => text.push('\t'),
+                            "cell" => text.push_str(" | "),
+                            "row" => {
+                                let trimmed = text.trim_end_matches([' ', '|']).len();
+                                text.truncate(trimmed);
+                                text.push('\n');
+                            },
+                            "u" => {
+                                if let Some(code) = param {
+                                    let code = if code < 0 { code + 65536 } else { code } as u32;
+                                    text.extend(char::from_u32(code));
+                                    pending_skip = state.unicode_skip;
+                                }
+                            },
+                            "emdash" => text.push('—'),
+                            "endash" => text.push('–'),
+                            "bullet" => text.push('•'),
+                            "lquote" => text.push('‘'),
+                            "rquote" => text.push('’'),
+                            "ldblquote" => text.push('“'),
+                            "rdblquote" => text.push('”'),
+                            _ => {}
+                        }
+                    },
+                    _ => i += 1,
+                }
+            },
+            b'\r' | b'\n' => i += 1,
+            byte => {
+                i += 1;
+                if pending_skip > 0 {
+                    pending_skip -= 1;
+                } else if !state.skip {
+                    text.push(cp1252(byte));
+                }
+            },
+        }
+    }
+
+    Ok(normalize_blank_lines(&text))
+}
+
+fn cp1252(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => CP1252_HIGH[(byte - 0x80) as usize],
+        b => b as char,
+    }
+}
+
+/// Recorta espacios finales y colapsa más de una línea en blanco seguida
+fn normalize_blank_lines(text: &str) -> String {
+    let mut out = String::new();
+    let mut blank = 0;
+    for line in text.lines().map(str::trim_end) {
+        if line.trim().is_empty() {
+            blank += 1;
+            if blank > 1 {
+                continue;
+            }
+        } else {
+            blank = 0;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.trim().to_string()
+}
@@ -0,0 +1,229 @@
+use super::*;
+
+fn transmute_fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+    let data = std::fs::read(&path).unwrap_or_else(|e| panic!("No se pudo leer {}: {}", path.display(), e));
+    DocumentTransmuter::transmute(name, &data)
+        .unwrap_or_else(|e| panic!("Fallo al transmutar {}: {}", name, e))
+        .text
+}
+
+#[test]
+fn detects_new_formats_by_extension() {
+    assert!(matches!(SupportedFormat::from_filename("a.ODT"), Some(SupportedFormat::ODT)));
+    assert!(matches!(SupportedFormat::from_filename("a.ods"), Some(SupportedFormat::ODS)));
+    assert!(matches!(SupportedFormat::from_filename("a.pptx"), Some(SupportedFormat::PPTX)));
+    assert!(matches!(SupportedFormat::from_filename("a.rtf"), Some(SupportedFormat::RTF)));
+    assert!(matches!(SupportedFormat::from_filename("a.epub"), Some(SupportedFormat::EPUB)));
+    assert!(matches!(SupportedFormat::from_filename("a.xls"), Some(SupportedFormat::XLS)));
+    assert!(matches!(SupportedFormat::from_filename("a.eml"), Some(SupportedFormat::EML)));
+    assert!(matches!(SupportedFormat::from_filename("a.mbox"), Some(SupportedFormat::MBOX)));
+    assert!(SupportedFormat::from_filename("a.odp").is_none());
+}
+
+#[test]
+fn parses_odt_structure() {
+    let text = transmute_fixture("sample.odt");
+    assert!(text.starts_with("# Informe de seguimiento"));
+    assert!(text.contains("## Objetivos"));
+    assert!(text.contains("- Buscar vivienda\n  - Contactar con el ayuntamiento"));
+    assert!(text.contains("| Fecha | Actividad |\n| --- | --- |\n| 12/01 | Entrevista |"));
+    assert!(text.contains("[comentario (Marta): Confirmar asistencia]"));
+    assert!(text.contains("Derivada por servicios sociales [nota: Expediente 2023/118]."));
+}
+
+#[test]
+fn parses_ods_sheets() {
+    let text = transmute_fixture("sample.ods");
+    assert!(text.contains("--- HOJA: Pacientes ---"));
+    assert!(text.contains("Nombre | Edad\nAna | 34"));
+}
+
+#[test]
+fn parses_legacy_xls_with_auto_reader() {
+    let text = transmute_fixture("sample.xls");
+    assert!(text.contains("--- HOJA: Usuarios ---"));
+    assert!(text.contains("Nombre | Edad\nLuis | 58"));
+}
+
+#[test]
+fn parses_pptx_in_presentation_order_with_notes() {
+    let text = transmute_fixture("sample.pptx");
+    let first = text.find("## Diapositiva 1: Programa de inclusión").expect("primera diapositiva");
+    let second = text.find("## Diapositiva 2: Resultados").expect("segunda diapositiva");
+    assert!(first < second);
+    assert!(text.contains("Nuevo convenio con el SEPE"));
+    assert!(text.contains("| Inserción laboral | 42% |"));
+    assert!(text.contains("Notas del orador: Destacar el convenio"));
+    // El número de diapositiva de las notas no es contenido
+    assert!(!text.contains("Notas del orador: Destacar el convenio 2"));
+}
+
+#[test]
+fn parses_rtf_text_and_escapes() {
+    let text = transmute_fixture("sample.rtf");
+    assert!(text.starts_with("Valoración inicial"));
+    assert!(text.contains("Próxima cita:\tmartes\npor la mañana"));
+    assert!(text.contains("15/03 | Educadora social"));
+    assert!(!text.contains("Arial"));
+    assert!(!text.contains("Msftedit"));
+    assert!(!text.contains("Plantilla"));
+}
+
+#[test]
+fn parses_epub_in_spine_order() {
+    let text = transmute_fixture("sample.epub");
+    assert!(text.starts_with("# Guía de recursos sociales"));
+    let employment = text.find("Itinerarios de inserción laboral").expect("capítulo de empleo");
+    let housing = text.find("Ayudas al alquiler").expect("capítulo de vivienda");
+    assert!(employment < housing);
+    assert!(!text.contains("margin"));
+}
+
+#[test]
+fn parses_docx_structure() {
+    let text = transmute_fixture("sample.docx");
+    assert!(text.starts_with("# Plan individual"));
+    assert!(text.contains("1. Acudir a la revisión médica\n  - Pedir cita\n2. Renovar la tarjeta sanitaria"));
+    assert!(text.contains("| Salud | Enfermería |"));
+    assert!(text.contains("[^1]: Según la última valoración"));
+    assert!(text.contains("- [comentario 0] (Coordinación): Revisar con la familia"));
+    assert!(text.contains("Centro de día Norte"));
+}
+
+fn read_fixture(name: &str) -> Vec<u8> {
+    std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)).expect("fixture")
+}
+
+#[test]
+fn parses_eml_headers_body_and_attachments() {
+    let message = email::parse_eml(&read_fixture("sample.eml")).expect("eml");
+    assert_eq!(message.subject.as_deref(), Some("Derivación de Ana"));
+    assert_eq!(message.from, vec!["Marta Gil <marta.gil@servicios.example>"]);
+    assert_eq!(message.to, vec!["Luis Pérez <luis@servicios.example>", "equipo@servicios.example"]);
+    assert_eq!(message.message_id.as_deref(), Some("derivacion-001@servicios.example"));
+
+    let header = message.header_block();
+    assert!(header.starts_with("Asunto: Derivación de Ana\nDe: Marta Gil"));
+    assert!(header.contains("CC: Coordinación <coordinacion@servicios.example>"));
+    assert!(header.contains("Fecha: 2023-03-14T10:30:00+01:00"));
+
+    assert!(message.text.contains("Te derivo el caso de Ana"));
+    assert!(message.text.contains("## Adjunto: usuarios.csv\n\nNombre | Centro\n---\nAna | Centro de día Norte"));
+    assert!(message.text.contains("## Adjunto: firma.p7s\n\n(formato no soportado)"));
+}
+
+#[test]
+fn splits_mbox_into_messages() {
+    let messages = email::parse_mbox(&read_fixture("sample.mbox")).expect("mbox");
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[1].subject.as_deref(), Some("Re: Primera cita"));
+    assert!(messages[1].text.contains("Confirmado, nos vemos el martes."));
+    assert!(!messages[0].text.contains("Confirmado"));
+    // Sin Message-ID el nombre del documento usa la posición en el buzón
+    assert_ne!(messages[0].document_name("buzon.mbox", 0), messages[1].document_name("buzon.mbox", 1));
+    assert_eq!(messages[1].document_name("buzon.mbox", 1), "buzon.mbox — Re: Primera cita #2");
+}
+
+#[test]
+fn expands_zip_skipping_directories_and_hidden_files() {
+    use std::io::Write;
+    let mut buffer = Cursor::new(Vec::new());
+    {
+        let mut writer = zip::ZipWriter::new(&mut buffer);
+        let options = zip::write::FileOptions::default();
+        writer.add_directory("informes/", options).unwrap();
+        writer.start_file("informes/enero.txt", options).unwrap();
+        writer.write_all(b"Informe de enero").unwrap();
+        writer.start_file("__MACOSX/informes/._enero.txt", options).unwrap();
+        writer.write_all(b"metadatos").unwrap();
+        writer.start_file("informes/.DS_Store", options).unwrap();
+        writer.write_all(b"metadatos").unwrap();
+        writer.start_file("febrero.csv", options).unwrap();
+        writer.write_all(b"a,b\n1,2\n").unwrap();
+        writer.finish().unwrap();
+    }
+
+    let entries = archive::expand_zip(buffer.get_ref()).expect("zip");
+    let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(paths, vec!["informes/enero.txt", "febrero.csv"]);
+    assert_eq!(entries[0].bytes, b"Informe de enero");
+}
+
+#[test]
+fn transcript_segments_map_to_paragraphs_with_times() {
+    use crate::application::chunking::strategy_for;
+    use crate::domain::models::{ChunkingConfig, ChunkLocation, TranscriptSegment};
+
+    let segment = |start: f64, end: f64, text: &str| TranscriptSegment { start_secs: start, end_secs: end, text: text.to_string() };
+    let transcription = Transcription {
+        text: String::new(),
+        language: Some("spanish".to_string()),
+        segments: vec![
+            segment(0.0, 4.5, "Buenos días, empezamos la sesión."),
+            segment(4.5, 9.0, "¿Cómo ha ido la semana?"),
+            segment(9.0, 75.2, "Mejor, he vuelto a dormir bien."),
+        ],
+    };
+    let doc = ExtractedDocument::from_transcript("--- [AUDIO: sesion.mp3] ---", transcription);
+    assert_eq!(doc.segments.len(), 3);
+
+    let chars: Vec<char> = doc.text.chars().collect();
+    let third = &doc.segments[2];
+    let slice: String = chars[third.char_start..third.char_end].iter().collect();
+    assert_eq!(slice, "Mejor, he vuelto a dormir bien.");
+
+    // El chunking por párrafos nunca corta un segmento por la mitad
+    let chunks = strategy_for(&ChunkingConfig::Paragraph { max_chars: 60 }).split(&doc.text);
+    for chunk in &chunks {
+        for span in &doc.segments {
+            let (start, end) = (doc.text.char_indices().nth(span.char_start).unwrap().0, doc.text.char_indices().nth(span.char_end - 1).unwrap().0);
+            assert!(end < chunk.start || start >= chunk.end || (start >= chunk.start && end < chunk.end));
+        }
+    }
+
+    let location = ChunkLocation { time_start: Some(4.5), time_end: Some(75.2), ..Default::default() };
+    assert_eq!(location.time_label().as_deref(), Some("00:00:04-00:01:15"));
+}
+
+/// JPEG de `width`x`height` con un segmento EXIF (orientación 6: girar 90° a la derecha)
+/// y una etiqueta de GPS ficticia
+fn jpeg_with_exif(width: u32, height: u32) -> Vec<u8> {
+    let mut jpeg = Vec::new();
+    let image = image::RgbImage::from_pixel(width, height, image::Rgb([200, 30, 30]));
+    image::codecs::jpeg::JpegEncoder::new(&mut jpeg).encode_image(&image).unwrap();
+
+    let mut tiff = b"MM\0\x2a\0\0\0\x08".to_vec();
+    tiff.extend_from_slice(&[0, 2]);
+    tiff.extend_from_slice(&[0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0]); // Orientation = 6
+    tiff.extend_from_slice(&[0x88, 0x25, 0, 4, 0, 0, 0, 1, 0, 0, 0, 0]); // GPSInfo
+    tiff.extend_from_slice(&[0, 0, 0, 0]);
+    let mut app1 = b"Exif\0\0".to_vec();
+    app1.extend_from_slice(&tiff);
+    let len = (app1.len() + 2) as u16;
+
+    let mut out = jpeg[..2].to_vec();
+    out.extend_from_slice(&[0xff, 0xe1]);
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(&app1);
+    out.extend_from_slice(&jpeg[2..]);
+    out
+}
+
+#[test]
+fn prepares_images_orienting_downscaling_and_stripping_exif() {
+    let original = jpeg_with_exif(40, 20);
+    assert!(original.windows(4).any(|w| w == b"Exif"));
+
+    let prepared = images::ImagePreparation { max_dimension: 10 }.prepare(&original).expect("imagen");
+    assert_eq!(prepared.original, (40, 20));
+    assert_eq!((prepared.width, prepared.height), (5, 10));
+    assert_eq!(prepared.mime_type, "image/jpeg");
+    assert!(!prepared.bytes.windows(4).any(|w| w == b"Exif"));
+
+    let mut bmp = Vec::new();
+    image::RgbImage::new(4, 4).write_to(&mut Cursor::new(&mut bmp), image::ImageFormat::Bmp).unwrap();
+    let rejected = images::ImagePreparation::default().prepare(&bmp).err().expect("BMP rechazado");
+    assert!(rejected.to_string().contains("BMP"));
+    assert!(images::ImagePreparation::default().prepare(b"no soy una imagen").is_err());
+}
@@ -0,0 +1,273 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use reqwest::{header, redirect};
+use tokio::sync::Mutex;
+use url::Url;
+use crate::domain::errors::AppError;
+
+mod robots;
+mod sitemap;
+
+pub use robots::RobotsRules;
+pub use sitemap::{parse_sitemap, Sitemap};
+
+#[cfg(test)]
+mod tests;
+
+/// Redirecciones seguidas por petición (cada salto pasa de nuevo por robots.txt)
+const MAX_REDIRECTS: usize = 5;
+/// Profundidad máxima de índices de sitemaps anidados
+const MAX_SITEMAP_DEPTH: usize = 3;
+/// Tamaño máximo de un robots.txt (RFC 9309 exige procesar al menos 500 KiB)
+const MAX_ROBOTS_BYTES: usize = 512 * 1024;
+
+/// Límites del rastreo web (/api/ingest/url)
+#[derive(Debug, Clone)]
+pub struct WebFetchLimits {
+    pub user_agent: String,
+    /// Tamaño máximo de cada respuesta (página o sitemap)
+    pub max_page_bytes: usize,
+    /// Páginas como máximo por sitemap
+    pub max_pages: usize,
+    pub timeout: Duration,
+    /// Vigencia de cada robots.txt cacheado (RFC 9309: no más de 24 horas)
+    pub robots_ttl: Duration,
+}
+
+impl Default for WebFetchLimits {
+    fn default() -> Self {
+        Self {
+            user_agent: "LaMurallaBot/1.0".to_string(),
+            max_page_bytes: 5 * 1024 * 1024,
+            max_pages: 200,
+            timeout: Duration::from_secs(30),
+            robots_ttl: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+impl WebFetchLimits {
+    /// Lee WEB_USER_AGENT, WEB_MAX_PAGE_BYTES, WEB_MAX_PAGES, WEB_TIMEOUT_SECS y WEB_ROBOTS_TTL_SECS
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let number = |name: &str, default: usize| std::env::var(name).ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(default);
+        Self {
+            user_agent: std::env::var("WEB_USER_AGENT").ok().filter(|v| !v.trim().is_empty()).unwrap_or(defaults.user_agent),
+            max_page_bytes: number("WEB_MAX_PAGE_BYTES", defaults.max_page_bytes),
+            max_pages: number("WEB_MAX_PAGES", defaults.max_pages),
+            timeout: Duration::from_secs(number("WEB_TIMEOUT_SECS", defaults.timeout.as_secs() as usize) as u64),
+            robots_ttl: Duration::from_secs(number("WEB_ROBOTS_TTL_SECS", defaults.robots_ttl.as_secs() as usize) as u64),
+        }
+    }
+}
+
+/// Respuesta descargada; `url` es la dirección final tras las redirecciones
+#[derive(Debug)]
+pub struct FetchedPage {
+    pub url: String,
+    /// Tipo MIME sin parámetros ("text/html")
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Cliente HTTP del rastreo: respeta robots.txt (cacheado por origen) y los límites de tamaño
+pub struct WebFetcher {
+    client: reqwest::Client,
+    limits: WebFetchLimits,
+    /// robots.txt por origen junto al instante en que se descargó
+    robots: Mutex<HashMap<String, (Instant, RobotsRules)>>,
+}
+
+impl WebFetcher {
+    pub fn new(limits: WebFetchLimits) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(limits.user_agent.clone())
+            .timeout(limits.timeout)
+            // Las redirecciones se siguen a mano para comprobar robots.txt en cada salto
+            .redirect(redirect::Policy::none())
+            .build()
+            .unwrap_or_default();
+        Self { client, limits, robots: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn limits(&self) -> &WebFetchLimits {
+        &self.limits
+    }
+
+    /// Descarga una URL http(s) permitida por robots.txt y dentro del tamaño máximo
+    pub async fn fetch(&self, url: &str) -> Result<FetchedPage, AppError> {
+        let mut current = parse_http_url(url)?;
+        for _ in 0..=MAX_REDIRECTS {
+            if !self.is_allowed(&current).await {
+                return Err(AppError::ValidationError(format!("{} está bloqueada por robots.txt", current)));
+            }
+            let response = self.client.get(current.clone()).send().await
+                .map_err(|e| AppError::ValidationError(format!("No se pudo descargar {}: {}", current, e)))?;
+
+            let status = response.status();
+            if status.is_redirection() {
+                let location = response.headers().get(header::LOCATION)
+                    .and_then(|v| v.to_str().ok())
+                    .ok_or_else(|| AppError::ValidationError(format!("Redirección sin destino en {}", current)))?;
+                let next = current.join(location)
+                    .map_err(|e| AppError::ValidationError(format!("Redirección inválida en {}: {}", current, e)))?;
+                current = parse_http_url(next.as_str())?;
+                continue;
+            }
+            if !status.is_success() {
+                return Err(AppError::ValidationError(format!("{} respondió {}", current, status)));
+            }
+
+            let content_type = response.headers().get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.split(';').next())
+                .map(|v| v.trim().to_lowercase())
+                .unwrap_or_default();
+            let bytes = self.read_limited(response, self.limits.max_page_bytes).await
+                .map_err(|e| match e {
+                    AppError::ValidationError(msg) => AppError::ValidationError(format!("{}: {}", current, msg)),
+                    other => other,
+                })?;
+            return Ok(FetchedPage { url: current.to_string(), content_type, bytes });
+        }
+        Err(AppError::ValidationError(format!("Demasiadas redirecciones desde {}", url)))
+    }
+
+    /// Páginas listadas por un sitemap, siguiendo índices anidados; sin duplicados y
+    /// recortadas a `max_pages`
+    pub async fn sitemap_urls(&self, url: &str) -> Result<Vec<String>, AppError> {
+        let mut pages = Vec::new();
+        let mut seen_pages = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![(url.to_string(), 0)];
+
+        while let Some((sitemap_url, depth)) = pending.pop() {
+            if !visited.insert(sitemap_url.clone()) {
+                continue;
+            }
+            let fetched = match self.fetch(&sitemap_url).await {
+                Ok(fetched) => fetched,
+                // El sitemap raíz es obligatorio; los anidados que fallen se omiten
+                Err(e) if depth == 0 => return Err(e),
+                Err(e) => {
+                    tracing::warn!("⚠️ Sitemap omitido {}: {}", sitemap_url, e);
+                    continue;
+                },
+            };
+            match parse_sitemap(&fetched.bytes)? {
+                Sitemap::Pages(urls) => {
+                    for page in urls {
+                        if pages.len() >= self.limits.max_pages {
+                            return Ok(pages);
+                        }
+                        if seen_pages.insert(page.clone()) {
+                            pages.push(page);
+                        }
+                    }
+                },
+                Sitemap::Index(children) if depth < MAX_SITEMAP_DEPTH => {
+                    // Se apilan al revés para recorrerlos en el orden del índice
+                    pending.extend(children.into_iter().rev().map(|child| (child, depth + 1)));
+                },
+                Sitemap::Index(_) => tracing::warn!("⚠️ Índice de sitemaps demasiado anidado: {}", sitemap_url),
+            }
+        }
+        Ok(pages)
+    }
+
+    /// Consulta robots.txt del origen de la URL (se vuelve a descargar al caducar `robots_ttl`)
+    pub async fn is_allowed(&self, url: &Url) -> bool {
+        let origin = url.origin().ascii_serialization();
+        let mut path = url.path().to_string();
+        if let Some(query) = url.query() {
+            path.push('?');
+            path.push_str(query);
+        }
+
+        if let Some((fetched_at, rules)) = self.robots.lock().await.get(&origin) {
+            if fetched_at.elapsed() < self.limits.robots_ttl {
+                return rules.allows(&path);
+            }
+        }
+        // Se descarga sin retener el cerrojo para que un origen lento no bloquee a los demás;
+        // dos peticiones simultáneas al mismo origen pueden descargarlo dos veces
+        let rules = self.fetch_robots(&origin).await;
+        let allowed = rules.allows(&path);
+        self.robots.lock().await.insert(origin, (Instant::now(), rules));
+        allowed
+    }
+
+    /// RFC 9309: 4xx (sin robots.txt) permite todo; 5xx o fallo de red lo prohíbe todo
+    async fn fetch_robots(&self, origin: &str) -> RobotsRules {
+        let robots_url = format!("{}/robots.txt", origin);
+        // Las redirecciones de robots.txt se siguen (hasta cinco, como pide la RFC)
+        let mut target = robots_url.clone();
+        for _ in 0..=MAX_REDIRECTS {
+            let response = match self.client.get(&target).send().await {
+                Ok(response) => response,
+                Err(e) => {
+                    tracing::warn!("⚠️ robots.txt inaccesible en {}: {}", origin, e);
+                    return RobotsRules::disallow_all();
+                },
+            };
+            let status = response.status();
+            if status.is_redirection() {
+                let next = response.headers().get(header::LOCATION)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|location| Url::parse(&target).ok()?.join(location).ok());
+                match next {
+                    Some(next) => {
+                        target = next.to_string();
+                        continue;
+                    },
+                    None => return RobotsRules::allow_all(),
+                }
+            }
+            if status.is_client_error() {
+                return RobotsRules::allow_all();
+            }
+            if !status.is_success() {
+                tracing::warn!("⚠️ robots.txt de {} respondió {}", origin, status);
+                return RobotsRules::disallow_all();
+            }
+            return match self.read_limited(response, MAX_ROBOTS_BYTES).await {
+                Ok(bytes) => RobotsRules::parse(&String::from_utf8_lossy(&bytes), &self.limits.user_agent),
+                // Un robots.txt desmesurado se trata como inaccesible
+                Err(_) => RobotsRules::disallow_all(),
+            };
+        }
+        RobotsRules::allow_all()
+    }
+
+    /// Lee el cuerpo cortando en cuanto supera `max_bytes` (aunque no declare Content-Length)
+    async fn read_limited(&self, mut response: reqwest::Response, max_bytes: usize) -> Result<Vec<u8>, AppError> {
+        let too_large = || AppError::ValidationError(format!("la respuesta supera el límite de {} bytes", max_bytes));
+        if response.content_length().is_some_and(|len| len as usize > max_bytes) {
+            return Err(too_large());
+        }
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await
+            .map_err(|e| AppError::ValidationError(format!("descarga interrumpida: {}", e)))?
+        {
+            if bytes.len() + chunk.len() > max_bytes {
+                return Err(too_large());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Valida que sea una URL absoluta http(s) con host
+pub fn parse_http_url(url: &str) -> Result<Url, AppError> {
+    let parsed = Url::parse(url.trim())
+        .map_err(|e| AppError::ValidationError(format!("URL inválida '{}': {}", url, e)))?;
+    match parsed.scheme() {
+        "http" | "https" if parsed.host().is_some() => Ok(parsed),
+        _ => Err(AppError::ValidationError(format!("Solo se admiten URLs http(s): {}", url))),
+    }
+}
+
@@ -0,0 +1,100 @@
+/// (permitir, patrón) de una línea `Allow` / `Disallow`
+type Rule = (bool, String);
+
+/// Reglas de robots.txt que aplican a nuestro agente (RFC 9309): se usan los grupos cuyo
+/// `User-agent` coincide con nuestro token de producto o, si no hay ninguno, los de `*`.
+#[derive(Debug, Clone, Default)]
+pub struct RobotsRules {
+    rules: Vec<Rule>,
+}
+
+impl RobotsRules {
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    pub fn disallow_all() -> Self {
+        Self { rules: vec![(false, "/".to_string())] }
+    }
+
+    pub fn parse(text: &str, user_agent: &str) -> Self {
+        // Token de producto: "LaMurallaBot/1.0 (+url)" -> "lamurallabot"
+        let token = user_agent.split(['/', ' ']).next().unwrap_or_default().to_lowercase();
+
+        let mut groups: Vec<(Vec<String>, Vec<Rule>)> = Vec::new();
+        let mut last_was_agent = false;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else { continue };
+            let (key, value) = (key.trim().to_lowercase(), value.trim());
+            match key.as_str() {
+                "user-agent" => {
+                    // Varias líneas User-agent seguidas comparten el mismo grupo
+                    if !last_was_agent || groups.is_empty() {
+                        groups.push((Vec::new(), Vec::new()));
+                    }
+                    if let Some((agents, _)) = groups.last_mut() {
+                        agents.push(value.to_lowercase());
+                    }
+                    last_was_agent = true;
+                },
+                "allow" | "disallow" => {
+                    last_was_agent = false;
+                    // `Disallow:` vacío no restringe nada
+                    if value.is_empty() {
+                        continue;
+                    }
+                    if let Some((_, rules)) = groups.last_mut() {
+                        rules.push((key == "allow", value.to_string()));
+                    }
+                },
+                _ => last_was_agent = false,
+            }
+        }
+
+        let matches_us = |agent: &String| agent != "*" && !token.is_empty() && token.starts_with(agent.as_str());
+        let ours = groups.iter().any(|(agents, _)| agents.iter().any(matches_us));
+        let rules = groups.into_iter()
+            .filter(|(agents, _)| if ours { agents.iter().any(matches_us) } else { agents.iter().any(|a| a == "*") })
+            .flat_map(|(_, rules)| rules)
+            .collect();
+        Self { rules }
+    }
+
+    /// Decide con la regla más específica (patrón más largo); en empate gana `Allow`.
+    /// `path` incluye la query (`/buscar?q=1`).
+    pub fn allows(&self, path: &str) -> bool {
+        let mut best: Option<(usize, bool)> = None;
+        for (allow, pattern) in &self.rules {
+            if !pattern_matches(pattern, path) {
+                continue;
+            }
+            let candidate = (pattern.len(), *allow);
+            if best.is_none_or(|(len, allowed)| candidate.0 > len || (candidate.0 == len && candidate.1 && !allowed)) {
+                best = Some(candidate);
+            }
+        }
+        best.is_none_or(|(_, allow)| allow)
+    }
+}
+
+/// Patrón de robots.txt: prefijo con comodines `*` y `$` como ancla de final
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let Some(rest) = path.strip_prefix(parts[0]) else { return false };
+    let mut rest = rest;
+    for (i, part) in parts.iter().enumerate().skip(1) {
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
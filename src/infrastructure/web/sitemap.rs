@@ -0,0 +1,51 @@
+use std::io::Read;
+use flate2::read::GzDecoder;
+use xml::reader::{EventReader, XmlEvent};
+use crate::domain::errors::AppError;
+
+/// Contenido de un sitemap (protocolo sitemaps.org): páginas o, en un índice, otros sitemaps
+#[derive(Debug, PartialEq)]
+pub enum Sitemap {
+    Pages(Vec<String>),
+    Index(Vec<String>),
+}
+
+/// Interpreta un `<urlset>` o `<sitemapindex>`, comprimido con gzip o no
+pub fn parse_sitemap(data: &[u8]) -> Result<Sitemap, AppError> {
+    let mut decoded = Vec::new();
+    let data = if data.starts_with(&[0x1f, 0x8b]) {
+        GzDecoder::new(data).read_to_end(&mut decoded)
+            .map_err(|e| AppError::ParseError(format!("Sitemap gzip inválido: {}", e)))?;
+        decoded.as_slice()
+    } else {
+        data
+    };
+
+    let mut root: Option<String> = None;
+    let mut in_loc = false;
+    let mut locations = Vec::new();
+    for event in EventReader::new(data) {
+        match event.map_err(|e| AppError::ParseError(format!("Sitemap XML inválido: {}", e)))? {
+            XmlEvent::StartElement { name, .. } => {
+                if root.is_none() {
+                    root = Some(name.local_name.clone());
+                }
+                in_loc = name.local_name == "loc";
+            },
+            XmlEvent::Characters(text) | XmlEvent::CData(text) if in_loc => {
+                let text = text.trim();
+                if !text.is_empty() {
+                    locations.push(text.to_string());
+                }
+            },
+            XmlEvent::EndElement { .. } => in_loc = false,
+            _ => {},
+        }
+    }
+
+    match root.as_deref() {
+        Some("urlset") => Ok(Sitemap::Pages(locations)),
+        Some("sitemapindex") => Ok(Sitemap::Index(locations)),
+        other => Err(AppError::ParseError(format!("No es un sitemap (raíz <{}>)", other.unwrap_or_default()))),
+    }
+}
@@ -0,0 +1,138 @@
+use super::*;
+use axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
+
+const ROBOTS: &str = "\
+# Reglas de ejemplo
+User-agent: OtroBot
+Disallow: /
+
+User-agent: *
+Disallow: /privado/
+Allow: /privado/publico.html
+Disallow: /*.pdf$
+";
+
+/// Servidor HTTP local que hace de sitio web rastreado
+async fn stand_in() -> String {
+    let app = Router::new()
+        .route("/robots.txt", get(|| async { ROBOTS }))
+        .route("/pagina.html", get(|| async {
+            ([("content-type", "text/html; charset=utf-8")], "<html><body><h1>Horarios</h1><p>Abrimos a las 9.</p></body></html>")
+        }))
+        .route("/antigua.html", get(|| async {
+            (StatusCode::MOVED_PERMANENTLY, [("location", "/pagina.html")]).into_response()
+        }))
+        .route("/privado/ficha.html", get(|| async { "secreto" }))
+        .route("/privado/publico.html", get(|| async { "visible" }))
+        .route("/grande.html", get(|| async { "x".repeat(4096) }))
+        .route("/falta.html", get(|| async { StatusCode::NOT_FOUND }));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("puerto local");
+    let base = format!("http://{}", listener.local_addr().expect("dirección local"));
+    let index = format!(
+        "<?xml version=\"1.0\"?><sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\
+         <sitemap><loc>{}/sitemap-paginas.xml</loc></sitemap></sitemapindex>", base);
+    let pages = format!(
+        "<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\
+         <url><loc>{b}/pagina.html</loc></url><url><loc>{b}/privado/ficha.html</loc></url>\
+         <url><loc>{b}/pagina.html</loc></url><url><loc>{b}/grande.html</loc></url></urlset>", b = base);
+    let app = app
+        .route("/sitemap.xml", get(move || async move { index }))
+        .route("/sitemap-paginas.xml", get(move || async move { pages }));
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    base
+}
+
+fn fetcher() -> WebFetcher {
+    WebFetcher::new(WebFetchLimits { max_page_bytes: 1024, max_pages: 10, ..Default::default() })
+}
+
+#[test]
+fn robots_uses_longest_match_and_agent_groups() {
+    let rules = RobotsRules::parse(ROBOTS, "LaMurallaBot/1.0");
+    assert!(rules.allows("/pagina.html"));
+    assert!(!rules.allows("/privado/ficha.html"));
+    assert!(rules.allows("/privado/publico.html"));
+    assert!(!rules.allows("/docs/informe.pdf"));
+    assert!(rules.allows("/docs/informe.pdf?v=2"));
+
+    let other = RobotsRules::parse(ROBOTS, "OtroBot/2.0");
+    assert!(!other.allows("/pagina.html"));
+    assert!(RobotsRules::parse("", "LaMurallaBot").allows("/cualquier"));
+}
+
+#[test]
+fn parses_urlsets_and_indexes() {
+    let urlset = br#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"><url><loc> https://a.org/1 </loc><lastmod>2024-01-01</lastmod></url></urlset>"#;
+    assert_eq!(parse_sitemap(urlset).unwrap(), Sitemap::Pages(vec!["https://a.org/1".to_string()]));
+    let index = br#"<sitemapindex><sitemap><loc>https://a.org/s1.xml</loc></sitemap></sitemapindex>"#;
+    assert_eq!(parse_sitemap(index).unwrap(), Sitemap::Index(vec!["https://a.org/s1.xml".to_string()]));
+    assert!(parse_sitemap(b"<html></html>").is_err());
+}
+
+#[tokio::test]
+async fn fetches_pages_respecting_robots_redirects_and_size() {
+    let base = stand_in().await;
+    let fetcher = fetcher();
+
+    let page = fetcher.fetch(&format!("{}/antigua.html", base)).await.expect("página");
+    assert_eq!(page.url, format!("{}/pagina.html", base));
+    assert_eq!(page.content_type, "text/html");
+    assert!(String::from_utf8_lossy(&page.bytes).contains("Horarios"));
+
+    let blocked = fetcher.fetch(&format!("{}/privado/ficha.html", base)).await.unwrap_err();
+    assert!(blocked.to_string().contains("robots.txt"));
+    assert!(fetcher.fetch(&format!("{}/privado/publico.html", base)).await.is_ok());
+    assert!(fetcher.fetch(&format!("{}/grande.html", base)).await.unwrap_err().to_string().contains("límite"));
+    assert!(fetcher.fetch(&format!("{}/falta.html", base)).await.is_err());
+    assert!(fetcher.fetch("file:///etc/passwd").await.is_err());
+}
+
+#[tokio::test]
+async fn lists_sitemap_pages_through_indexes() {
+    let base = stand_in().await;
+    let pages = fetcher().sitemap_urls(&format!("{}/sitemap.xml", base)).await.expect("sitemap");
+    assert_eq!(pages, vec![
+        format!("{}/pagina.html", base),
+        format!("{}/privado/ficha.html", base),
+        format!("{}/grande.html", base),
+    ]);
+
+    let limited = WebFetcher::new(WebFetchLimits { max_pages: 1, ..Default::default() });
+    assert_eq!(limited.sitemap_urls(&format!("{}/sitemap.xml", base)).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn unreachable_robots_disallows_everything() {
+    // Puerto cerrado: robots.txt inaccesible equivale a "Disallow: /"
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    let url = Url::parse(&format!("http://{}/pagina.html", addr)).unwrap();
+    assert!(!fetcher().is_allowed(&url).await);
+}
+
+#[tokio::test]
+async fn robots_cache_expires_after_ttl() {
+    // Sitio que bloquea todo a partir de la segunda descarga de robots.txt
+    let served = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = served.clone();
+    let app = Router::new().route("/robots.txt", get(move || async move {
+        match counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+            0 => "User-agent: *\nAllow: /\n",
+            _ => "User-agent: *\nDisallow: /\n",
+        }
+    }));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = Url::parse(&format!("http://{}/pagina.html", listener.local_addr().unwrap())).unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let cached = fetcher();
+    assert!(cached.is_allowed(&url).await);
+    assert!(cached.is_allowed(&url).await);
+    assert_eq!(served.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+    let expiring = WebFetcher::new(WebFetchLimits { robots_ttl: Duration::ZERO, ..Default::default() });
+    assert!(!expiring.is_allowed(&url).await);
+    assert_eq!(served.load(std::sync::atomic::Ordering::SeqCst), 2);
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::domain::models::DocumentMetadata;
+use crate::interface::handlers::admin::AppState;
+use crate::interface::handlers::ingest::{self, UploadOptions};
+
+/// Subcarpetas de destino dentro de la carpeta vigilada y registro de resultados
+const PROCESSED_DIR: &str = "procesados";
+const FAILED_DIR: &str = "fallidos";
+const RESULTS_LOG: &str = "ingesta.log";
+
+/// Carpeta vigilada (WATCH_DIR): los archivos que se dejan en ella se ingieren igual que
+/// una subida desde el panel y después se mueven a `procesados/` o `fallidos/`. Un archivo
+/// que vuelve con el mismo nombre sustituye a su versión anterior (nombre y WATCH_UPLOADED_BY).
+#[derive(Debug, Clone)]
+pub struct FolderWatchConfig {
+    pub dir: PathBuf,
+    pub interval: Duration,
+    pub uploaded_by: String,
+}
+
+impl FolderWatchConfig {
+    /// Lee WATCH_DIR, WATCH_INTERVAL_SECS y WATCH_UPLOADED_BY; `None` si no hay carpeta configurada
+    pub fn from_env() -> Option<Self> {
+        let dir = std::env::var("WATCH_DIR").ok().filter(|d| !d.trim().is_empty())?;
+        let interval = std::env::var("WATCH_INTERVAL_SECS").ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(30);
+        Some(Self {
+            dir: PathBuf::from(dir),
+            interval: Duration::from_secs(interval),
+            uploaded_by: std::env::var("WATCH_UPLOADED_BY").unwrap_or_else(|_| "carpeta_vigilada".to_string()),
+        })
+    }
+}
+
+/// Arranca la vigilancia en segundo plano. Se sondea la carpeta en lugar de usar eventos del
+/// sistema de archivos porque las carpetas compartidas en red no siempre los emiten.
+pub fn spawn(state: AppState, config: FolderWatchConfig) {
+    tokio::spawn(async move {
+        for sub in [PROCESSED_DIR, FAILED_DIR] {
+            if let Err(e) = tokio::fs::create_dir_all(config.dir.join(sub)).await {
+                warn!("⚠️ No se puede preparar la carpeta vigilada {}: {}", config.dir.display(), e);
+                return;
+            }
+        }
+        info!("👀 Vigilando {} cada {} s.", config.dir.display(), config.interval.as_secs());
+
+        let mut watcher = FolderWatcher { state, config, pending: HashMap::new() };
+        let mut interval = tokio::time::interval(watcher.config.interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = watcher.scan().await {
+                warn!("⚠️ Error leyendo la carpeta vigilada: {}", e);
+            }
+        }
+    });
+}
+
+struct FolderWatcher {
+    state: AppState,
+    config: FolderWatchConfig,
+    /// Tamaño y fecha de modificación vistos en la pasada anterior: un archivo solo se
+    /// procesa cuando no cambia entre dos pasadas (la copia ya terminó)
+    pending: HashMap<PathBuf, (u64, SystemTime)>,
+}
+
+impl FolderWatcher {
+    async fn scan(&mut self) -> std::io::Result<()> {
+        let mut ready = Vec::new();
+        let mut present = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.config.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !metadata.is_file() || is_ignored(&name) {
+                continue;
+            }
+            let path = entry.path();
+            let snapshot = (metadata.len(), metadata.modified()?);
+            present.push(path.clone());
+            if self.pending.get(&path) == Some(&snapshot) {
+                ready.push(path);
+            } else {
+                self.pending.insert(path, snapshot);
+            }
+        }
+        self.pending.retain(|path, _| present.contains(path));
+
+        for path in ready {
+            self.pending.remove(&path);
+            self.process(&path).await;
+        }
+        Ok(())
+    }
+
+    /// Ingiere el archivo esperando a que terminen sus trabajos, lo mueve a su carpeta de
+    /// resultado junto con el registro de progreso y anota el resultado en `ingesta.log`
+    async fn process(&self, path: &Path) {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        info!("📥 Carpeta vigilada: {}", name);
+
+        let (lines, queued) = match tokio::fs::read(path).await {
+            Ok(bytes) => self.ingest(&name, bytes).await,
+            Err(e) => (vec![format!("❌ No se pudo leer: {}", e)], 0),
+        };
+        let failed = queued == 0 || lines.iter().any(|line| line.contains("❌"));
+        let first_error = lines.iter().find(|line| line.contains("❌")).cloned();
+
+        let target_dir = self.config.dir.join(if failed { FAILED_DIR } else { PROCESSED_DIR });
+        let target = unique_destination(&target_dir, &name).await;
+        if let Err(e) = tokio::fs::rename(path, &target).await {
+            // Si no se puede mover, se volvería a ingerir en cada pasada
+            warn!("⚠️ No se pudo mover {} a {}: {}", path.display(), target_dir.display(), e);
+        }
+        let mut transcript = lines.join("\n");
+        transcript.push('\n');
+        let log_path = target.with_file_name(format!("{}.log", target.file_name().unwrap_or_default().to_string_lossy()));
+        if let Err(e) = tokio::fs::write(&log_path, transcript).await {
+            warn!("⚠️ No se pudo escribir {}: {}", log_path.display(), e);
+        }
+
+        let summary = format!(
+            "{}\t{}\t{}\t{} documento(s){}\n",
+            chrono::Utc::now().to_rfc3339(),
+            if failed { "ERROR" } else { "OK" },
+            name,
+            queued,
+            first_error.map(|e| format!("\t{}", e)).unwrap_or_default(),
+        );
+        if let Err(e) = self.append_result(&summary).await {
+            warn!("⚠️ No se pudo escribir {}: {}", RESULTS_LOG, e);
+        }
+        if failed {
+            warn!("❌ {} movido a {}/.", name, FAILED_DIR);
+        } else {
+            info!("✅ {} ingerido ({} documento(s)).", name, queued);
+        }
+    }
+
+    /// Pasa el archivo por la misma ruta que una subida y recoge todo el progreso; el canal se
+    /// cierra cuando terminan todos los trabajos encolados para él
+    async fn ingest(&self, name: &str, bytes: Vec<u8>) -> (Vec<String>, usize) {
+        let (tx, mut rx) = mpsc::channel::<String>(20);
+        let state = self.state.clone();
+        let options = UploadOptions {
+            uploaded_by: self.config.uploaded_by.clone(),
+            chunking: None,
+            mapping: None,
+            metadata: DocumentMetadata::new(),
+            language: None,
+            preview: false,
+            // Los procesados salen de la carpeta: volver a dejar un archivo con el mismo nombre
+            // es su nueva versión y sustituye a la anterior, igual que al refrescar una URL
+            replace: true,
+        };
+        let filename = name.to_string();
+        let upload = tokio::spawn(async move {
+            if !ingest::is_supported(&filename) && !filename.to_lowercase().ends_with(".zip") {
+                let _ = tx.send("❌ Formato no soportado.".to_string()).await;
+                return 0;
+            }
+            ingest::ingest_upload(&state, &options, &filename, bytes, &tx).await
+        });
+
+        let mut lines = Vec::new();
+        while let Some(line) = rx.recv().await {
+            lines.push(line);
+        }
+        (lines, upload.await.unwrap_or(0))
+    }
+
+    async fn append_result(&self, line: &str) -> std::io::Result<()> {
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.config.dir.join(RESULTS_LOG))
+            .await?;
+        file.write_all(line.as_bytes()).await
+    }
+}
+
+/// Ocultos, temporales de copia o del propio registro de resultados
+fn is_ignored(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    name.starts_with('.')
+        || name.starts_with("~$")
+        || name == RESULTS_LOG
+        || [".tmp", ".part", ".crdownload", ".partial"].iter().any(|ext| lower.ends_with(ext))
+}
+
+/// Destino libre: si ya existe un archivo con ese nombre se antepone la fecha
+async fn unique_destination(dir: &Path, name: &str) -> PathBuf {
+    let candidate = dir.join(name);
+    if tokio::fs::try_exists(&candidate).await.unwrap_or(false) {
+        dir.join(format!("{}_{}", chrono::Utc::now().format("%Y%m%d_%H%M%S"), name))
+    } else {
+        candidate
+    }
+}
@@ -6,8 +6,11 @@ use axum::{
 };
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use crate::domain::{ports::{KGRepository, AIService}, errors::AppError};
+use crate::domain::{ports::{KGRepository, AIService, OcrEngine}, errors::AppError};
 use crate::application::dtos::AdminConfigPayload;
+use crate::application::ingestion_jobs::IngestionJobQueue;
+use crate::application::web_ingestion::WebIngestionService;
+use crate::infrastructure::transmutation::images::ImagePreparation;
 use tera::Tera;
 
 /// Estado global de la aplicación
@@ -16,6 +19,10 @@ pub struct AppState {
     pub repo: Arc<dyn KGRepository>,
     pub ai_service: Arc<RwLock<dyn AIService>>,
     pub tera: Arc<Tera>,
+    pub ingestion_jobs: Arc<IngestionJobQueue>,
+    pub ocr: Arc<dyn OcrEngine>,
+    pub web_ingestion: Arc<WebIngestionService>,
+    pub image_preparation: ImagePreparation,
 }
 
 #[utoipa::path(
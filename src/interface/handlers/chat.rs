@@ -31,7 +31,7 @@ pub async fn chat_handler(
     
     // 2. Buscar contexto híbrido (Texto + Entidades del Grafo)
     // Pedimos los 5 fragmentos más relevantes
-    let hybrid_contexts = state.repo.find_hybrid_context(embedding, 5).await?;
+    let hybrid_contexts = state.repo.find_hybrid_context(embedding, 5, &payload.filter).await?;
     
     let mut context_text = String::new();
     let mut sources_output = Vec::new();
@@ -44,9 +44,10 @@ pub async fn chat_handler(
         let entities_str = ctx.connected_entities.join(", ");
         
         // Inyectamos al Prompt tanto el texto como las entidades que el grafo conoce sobre este texto
+        let origin_str = ctx.citation().map(|c| format!("- Origen: {}\n", c)).unwrap_or_default();
         context_text.push_str(&format!(
-            "FUENTE [{}]:\n- Texto: \"{}\"\n- Entidades Clave en Grafo: [{}]\n\n", 
-            idx, clean_content, entities_str
+            "FUENTE [{}]:\n{}- Texto: \"{}\"\n- Entidades Clave en Grafo: [{}]\n\n", 
+            idx, origin_str, clean_content, entities_str
         ));
 
         sources_output.push(SourceReference {
@@ -55,6 +56,8 @@ pub async fn chat_handler(
             short_content: clean_content.chars().take(200).collect(),
             relevance: 1.0, // Podríamos usar score si Neo4j lo devuelve
             concepts: ctx.connected_entities.clone(),
+            document_name: ctx.document_name.clone(),
+            location: ctx.location.clone(),
         });
     }
 
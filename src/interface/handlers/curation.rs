@@ -0,0 +1,111 @@
+use axum::{
+    Json,
+    extract::{State, Path, Extension},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use crate::domain::{
+    models::{CurationQueue, CurationDecision, Claims, EntityAlias, EntityMergeRequest},
+    errors::AppError,
+};
+use super::admin::AppState;
+use tracing::info;
+
+/// Elementos pendientes que se muestran por tipo en cada consulta
+const QUEUE_LIMIT: usize = 200;
+
+#[utoipa::path(
+    get,
+    path = "/api/curation",
+    responses(
+        (status = 200, description = "Entidades y relaciones extraídas pendientes de revisión", body = CurationQueue)
+    ),
+    tag = "curation"
+)]
+pub async fn list_pending(
+    State(state): State<AppState>,
+) -> Result<Json<CurationQueue>, AppError> {
+    let queue = state.repo.list_pending_curation(QUEUE_LIMIT).await?;
+    Ok(Json(queue))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/curation/entities/{id}",
+    params(
+        ("id" = String, Path, description = "Curation ID de la entidad")
+    ),
+    request_body = CurationDecision,
+    responses(
+        (status = 200, description = "Decisión registrada con revisor y fecha"),
+        (status = 400, description = "Edición inválida"),
+        (status = 404, description = "Entidad no encontrada")
+    ),
+    tag = "curation"
+)]
+pub async fn review_entity(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<String>,
+    Json(decision): Json<CurationDecision>,
+) -> Result<impl IntoResponse, AppError> {
+    decision.validate()?;
+    if !state.repo.review_entity(&id, &decision, &claims.sub).await? {
+        return Err(AppError::NotFound(format!("Entidad no encontrada: {}", id)));
+    }
+    info!("🧐 Entidad {} revisada por {}: {:?}", id, claims.sub, decision.action);
+    Ok((StatusCode::OK, Json("Entity reviewed")))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/curation/relations/{id}",
+    params(
+        ("id" = String, Path, description = "Curation ID de la relación")
+    ),
+    request_body = CurationDecision,
+    responses(
+        (status = 200, description = "Decisión registrada con revisor y fecha"),
+        (status = 400, description = "Edición inválida"),
+        (status = 404, description = "Relación no encontrada")
+    ),
+    tag = "curation"
+)]
+pub async fn review_relation(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<String>,
+    Json(decision): Json<CurationDecision>,
+) -> Result<impl IntoResponse, AppError> {
+    decision.validate()?;
+    if !state.repo.review_relation(&id, &decision, &claims.sub).await? {
+        return Err(AppError::NotFound(format!("Relación no encontrada: {}", id)));
+    }
+    info!("🧐 Relación {} revisada por {}: {:?}", id, claims.sub, decision.action);
+    Ok((StatusCode::OK, Json("Relation reviewed")))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/curation/merge",
+    request_body = EntityMergeRequest,
+    responses(
+        (status = 200, description = "Entidades fusionadas; el nombre de origen queda como alias", body = EntityAlias),
+        (status = 400, description = "Fusión inválida"),
+        (status = 404, description = "Entidad no encontrada")
+    ),
+    tag = "curation"
+)]
+pub async fn merge_entities(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<EntityMergeRequest>,
+) -> Result<Json<EntityAlias>, AppError> {
+    let source = request.source.trim();
+    let target = request.target.trim();
+    if !state.repo.merge_entities(source, target, &claims.sub).await? {
+        return Err(AppError::NotFound(format!("Entidad no encontrada: '{}' o '{}'", source, target)));
+    }
+    info!("🔗 Entidad '{}' fusionada en '{}' por {}", source, target, claims.sub);
+    Ok(Json(EntityAlias { alias: source.to_string(), canonical: target.to_string(), method: "fusión".to_string() }))
+}
@@ -0,0 +1,74 @@
+use axum::{
+    Json,
+    extract::{State, Path, Extension},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use crate::domain::{
+    models::{SourceDocument, DocumentDetail, Claims},
+    errors::AppError,
+};
+use super::admin::AppState;
+use tracing::info;
+
+#[utoipa::path(
+    get,
+    path = "/api/documents",
+    responses(
+        (status = 200, description = "Documentos ingeridos", body = Vec<SourceDocument>),
+        (status = 500, description = "Database error")
+    ),
+    tag = "documents"
+)]
+pub async fn list_documents(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<SourceDocument>>, AppError> {
+    let documents = state.repo.list_documents().await?;
+    Ok(Json(documents))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}",
+    params(
+        ("id" = String, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Documento con sus fragmentos", body = DocumentDetail),
+        (status = 404, description = "Documento no encontrado")
+    ),
+    tag = "documents"
+)]
+pub async fn get_document(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<DocumentDetail>, AppError> {
+    state.repo.get_document(&id).await?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Documento no encontrado: {}", id)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/documents/{id}",
+    params(
+        ("id" = String, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Documento, fragmentos y entidades huérfanas eliminados"),
+        (status = 404, description = "Documento no encontrado")
+    ),
+    tag = "documents"
+)]
+pub async fn delete_document(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    if !state.repo.delete_document(&id).await? {
+        return Err(AppError::NotFound(format!("Documento no encontrado: {}", id)));
+    }
+
+    info!("🗑️ Documento '{}' eliminado por '{}'.", id, claims.sub);
+    Ok((StatusCode::OK, Json("Document deleted")))
+}
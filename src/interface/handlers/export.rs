@@ -67,7 +67,8 @@ fn export_jsonld(graph: ExportedGraph) -> Result<Response, AppError> {
             "@id": id,
             "@type": type_uri,
             "name": node.name,
-            "category": node.category 
+            "category": node.category,
+            "attributes": node.attributes
         });
         
         nodes_map.insert(id, node_json);
@@ -78,7 +79,9 @@ fn export_jsonld(graph: ExportedGraph) -> Result<Response, AppError> {
         edges_json.push(json!({
             "source": format!("mhealth:{}", sanitize_uri(&edge.source)),
             "target": format!("mhealth:{}", sanitize_uri(&edge.target)),
-            "relation": edge.relation_type
+            "relation": edge.relation_type,
+            "attributes": edge.attributes,
+            "provenance": edge.provenance
         }));
     }
 
@@ -103,7 +106,9 @@ fn export_turtle(graph: ExportedGraph) -> Result<Response, AppError> {
 
     ttl.push_str("@prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .\n");
     ttl.push_str("@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .\n");
-    ttl.push_str("@prefix mhealth: <http://ontologies.lamuralla.org/mental-health#> .\n\n");
+    ttl.push_str("@prefix mhealth: <http://ontologies.lamuralla.org/mental-health#> .\n");
+    ttl.push_str("@prefix prov: <http://www.w3.org/ns/prov#> .\n");
+    ttl.push_str("@prefix xsd: <http://www.w3.org/2001/XMLSchema#> .\n\n");
 
     ttl.push_str(&format!("# Generated by LaMuralla Engine at {}\n\n", graph.generated_at));
 
@@ -129,6 +134,27 @@ fn export_turtle(graph: ExportedGraph) -> Result<Response, AppError> {
         let predicate = format!("mhealth:{}", to_camel_case(&edge.relation_type));
 
         ttl.push_str(&format!("{} {} {} .\n", source, predicate, target));
+
+        // Procedencia como declaración reificada (rdf:Statement)
+        let provenance = &edge.provenance;
+        ttl.push_str(&format!("[] a rdf:Statement ;\n    rdf:subject {} ;\n    rdf:predicate {} ;\n    rdf:object {} ;\n", source, predicate, target));
+        ttl.push_str(&format!("    mhealth:occurrences {} ;\n", provenance.occurrences));
+        if let Some(confidence) = provenance.confidence {
+            ttl.push_str(&format!("    mhealth:confidence \"{}\"^^xsd:decimal ;\n", confidence));
+        }
+        if let Some(model) = &provenance.model {
+            ttl.push_str(&format!("    prov:wasAttributedTo {} ;\n", turtle_literal(model)));
+        }
+        if let Some(extracted_at) = &provenance.extracted_at {
+            ttl.push_str(&format!("    prov:generatedAtTime \"{}\"^^xsd:dateTime ;\n", extracted_at));
+        }
+        for chunk in &provenance.source_chunks {
+            ttl.push_str(&format!("    prov:wasDerivedFrom mhealth:chunk_{} ;\n", sanitize_uri(chunk)));
+        }
+        for quote in &provenance.evidence {
+            ttl.push_str(&format!("    prov:value {} ;\n", turtle_literal(quote)));
+        }
+        ttl.push_str("    .\n");
     }
 
     Ok((
@@ -149,7 +175,13 @@ fn export_graphml(graph: ExportedGraph) -> Result<Response, AppError> {
     ).map_err(|e| AppError::ParseError(e.to_string()))?;
 
     xml.write(XmlEvent::start_element("key").attr("id", "d0").attr("for", "node").attr("attr.name", "category").attr("attr.type", "string")).ok();
+    xml.write(XmlEvent::end_element()).ok();
     xml.write(XmlEvent::start_element("key").attr("id", "d1").attr("for", "edge").attr("attr.name", "label").attr("attr.type", "string")).ok();
+    xml.write(XmlEvent::end_element()).ok();
+    for (id, name, kind) in PROVENANCE_KEYS {
+        xml.write(XmlEvent::start_element("key").attr("id", id).attr("for", "edge").attr("attr.name", name).attr("attr.type", kind)).ok();
+        xml.write(XmlEvent::end_element()).ok();
+    }
 
     xml.write(XmlEvent::start_element("graph").attr("id", "G").attr("edgedefault", "directed")).ok();
 
@@ -176,6 +208,23 @@ fn export_graphml(graph: ExportedGraph) -> Result<Response, AppError> {
         xml.write(XmlEvent::start_element("data").attr("key", "d1")).ok();
         xml.write(XmlEvent::characters(&edge.relation_type)).ok();
         xml.write(XmlEvent::end_element()).ok();
+
+        let provenance = &edge.provenance;
+        let values = [
+            Some(provenance.occurrences.to_string()),
+            provenance.confidence.map(|c| c.to_string()),
+            provenance.model.clone(),
+            provenance.extracted_at.clone(),
+            Some(provenance.source_chunks.join(";")).filter(|v| !v.is_empty()),
+            Some(provenance.evidence.join(" | ")).filter(|v| !v.is_empty()),
+        ];
+        for ((id, _, _), value) in PROVENANCE_KEYS.iter().zip(values) {
+            if let Some(value) = value {
+                xml.write(XmlEvent::start_element("data").attr("key", id)).ok();
+                xml.write(XmlEvent::characters(&value)).ok();
+                xml.write(XmlEvent::end_element()).ok();
+            }
+        }
         xml.write(XmlEvent::end_element()).ok(); 
     }
 
@@ -192,6 +241,22 @@ fn export_graphml(graph: ExportedGraph) -> Result<Response, AppError> {
     ).into_response())
 }
 
+/// Atributos GraphML de procedencia de las aristas (id, nombre, tipo)
+const PROVENANCE_KEYS: [(&str, &str, &str); 6] = [
+    ("d2", "occurrences", "int"),
+    ("d3", "confidence", "double"),
+    ("d4", "model", "string"),
+    ("d5", "extracted_at", "string"),
+    ("d6", "source_chunks", "string"),
+    ("d7", "evidence", "string"),
+];
+
+/// Literal Turtle entre comillas con los caracteres especiales escapados
+fn turtle_literal(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\r', "\\r");
+    format!("\"{}\"", escaped)
+}
+
 fn sanitize_uri(input: &str) -> String {
     input.trim()
         .replace(" ", "_")
@@ -202,7 +267,7 @@ fn sanitize_uri(input: &str) -> String {
 }
 
 fn to_camel_case(s: &str) -> String {
-    let parts: Vec<&str> = s.split(|c| c == '_' || c == ' ').collect();
+    let parts: Vec<&str> = s.split(['_', ' ']).collect();
     if parts.is_empty() { return String::new(); }
 
     let mut result = parts[0].to_lowercase();
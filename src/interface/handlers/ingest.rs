@@ -1,5 +1,7 @@
 use axum::{
-    extract::{State, Multipart},
+    Json,
+    extract::{State, Multipart, Extension, Path},
+    http::StatusCode,
     response::IntoResponse,
     body::{Body, Bytes},
 };
@@ -8,16 +10,19 @@ use tokio::task;
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::StreamExt;
 use mime_guess::from_path;
+use sha2::{Digest, Sha256};
 
-use crate::application::ingestion::IngestionService;
+use crate::domain::models::{Claims, DocumentOrigin, IngestionJob, ChunkingConfig, IngestionPayload, IngestionPreview, PreviewCommitRequest, DocumentMetadata, UrlIngestionRequest, WebSource, parse_metadata};
+use crate::domain::errors::AppError;
 // IMPORTANTE: Apuntamos al único transmutador válido en Infrastructure
-use crate::infrastructure::transmutation::DocumentTransmuter;
+use crate::infrastructure::transmutation::{archive, email, DocumentTransmuter, ExtractedDocument, SupportedFormat};
+use crate::application::tabular::TabularMapping;
 use super::admin::AppState;
 
 #[utoipa::path(
     post,
     path = "/api/ingest",
-    request_body(content_type = "multipart/form-data", description = "File upload (Multimodal)", content = String), 
+    request_body(content_type = "multipart/form-data", description = "Uno o varios archivos (campo `file` repetido, ZIP incluido); cada uno se ingesta como documento propio. Opcionales: `content`, `chunking`, `mapping`/`mapping_name`, `metadata` (objeto JSON plano), `language` (idioma del audio), `preview` (\"true\": solo propone la extracción, ver /api/ingest/preview/{id}) y `replace` (\"true\": nueva versión del documento del mismo nombre subido por el mismo usuario)", content = String), 
     responses(
         (status = 200, description = "Stream de progreso"),
         (status = 500, description = "Error interno")
@@ -25,6 +30,7 @@ use super::admin::AppState;
 )]
 pub async fn ingest_document(
     State(state): State<AppState>, 
+    Extension(claims): Extension<Claims>,
     mut multipart: Multipart,
 ) -> impl IntoResponse {
 
@@ -32,82 +38,606 @@ pub async fn ingest_document(
     let tx_inner = tx.clone();
 
     tokio::spawn(async move {
-        let mut final_content = String::new();
-        let mut content_found = false;
+        let mut uploads: Vec<(String, Bytes)> = Vec::new();
+        let mut pasted_text = String::new();
+        let mut options = UploadOptions {
+            uploaded_by: claims.sub.clone(),
+            chunking: None,
+            mapping: None,
+            metadata: DocumentMetadata::new(),
+            language: None,
+            preview: false,
+            replace: false,
+        };
 
+        // Primero se recoge todo el formulario: el override de chunking aplica a todos los archivos
         while let Ok(Some(field)) = multipart.next_field().await {
             let name = field.name().unwrap_or("").to_string();
 
             if name == "file" {
                 let filename = field.file_name().unwrap_or("archivo.bin").to_string();
-                let _ = tx_inner.send(format!("📂 Archivo: {}", filename)).await;
-                
                 match field.bytes().await {
-                    Ok(bytes) => {
-                        let bytes_vec = bytes.to_vec();
-                        let mime_type = from_path(&filename).first_or_octet_stream().to_string();
-                        
-                        let processing_result: Result<String, String> = if mime_type.starts_with("image/") {
-                            // 1. VISIÓN
-                            let _ = tx_inner.send("👁️ Imagen detectada. Analizando...".to_string()).await;
-                            state.ai_service.read().await
-                                .describe_image(&bytes_vec, &mime_type).await
-                                .map(|d| format!("--- [IMG: {}] ---\n{}\n---", filename, d))
-                                .map_err(|e| format!("Error Visión: {}", e))
-                        } else if mime_type.starts_with("audio/") {
-                            // 2. AUDIO
-                            let _ = tx_inner.send("👂 Audio detectado. Transcribiendo...".to_string()).await;
-                            state.ai_service.read().await
-                                .transcribe_audio(&bytes_vec, &filename).await
-                                .map(|t| format!("--- [AUDIO: {}] ---\n{}\n---", filename, t))
-                                .map_err(|e| format!("Error Audio: {}", e))
-                        } else {
-                            // 3. DOCUMENTOS (Usando el transmutador unificado)
-                            let _ = tx_inner.send("📄 Extrayendo texto...".to_string()).await;
-                            let fname = filename.clone();
-                            
-                            task::spawn_blocking(move || {
-                                DocumentTransmuter::transmute(&fname, &bytes_vec)
-                            }).await
-                            .map_err(|e| format!("Error Thread: {}", e))
-                            .and_then(|res| res.map_err(|e| format!("Error Formato: {}", e)))
-                        };
-
-                        match processing_result {
-                            Ok(text) => {
-                                final_content.push_str(&text);
-                                final_content.push_str("\n\n");
-                                content_found = true;
-                                let _ = tx_inner.send("✅ Contenido extraído.".to_string()).await;
-                            },
-                            Err(e) => { let _ = tx_inner.send(format!("❌ {}", e)).await; }
+                    Ok(bytes) => uploads.push((filename, bytes)),
+                    Err(e) => { let _ = tx_inner.send(format!("❌ Error Subida ({}): {}", filename, e)).await; }
+                }
+            } else if name == "chunking" {
+                // Override opcional, p.ej. {"strategy": "markdown", "max_chars": 2000}
+                if let Ok(raw) = field.text().await {
+                    match serde_json::from_str::<ChunkingConfig>(&raw) {
+                        Ok(config) => options.chunking = Some(config),
+                        Err(e) => { let _ = tx_inner.send(format!("⚠️ Chunking inválido ({}). Se usa el del formato.", e)).await; }
+                    }
+                }
+            } else if name == "metadata" {
+                // Objeto JSON plano, p.ej. {"program": "Inclusión", "author": "Marta", "date": "2025-03-14"}
+                if let Ok(raw) = field.text().await {
+                    let parsed = serde_json::from_str::<serde_json::Value>(&raw)
+                        .map_err(|e| AppError::ParseError(e.to_string()))
+                        .and_then(|value| parse_metadata(&value));
+                    match parsed {
+                        Ok(metadata) => options.metadata = metadata,
+                        Err(e) => {
+                            // Sin sus metadatos los documentos no aparecerían en las búsquedas filtradas
+                            let _ = tx_inner.send(format!("❌ Metadatos inválidos: {}", e)).await;
+                            return;
                         }
-                    },
-                    Err(e) => { let _ = tx_inner.send(format!("❌ Error Subida: {}", e)).await; }
+                    }
+                }
+            } else if name == "mapping" || name == "mapping_name" {
+                // Mapeo de columnas para tablas: YAML en línea o nombre de config/tabular/{nombre}.yaml
+                if let Ok(raw) = field.text().await {
+                    let parsed = if name == "mapping" {
+                        TabularMapping::from_yaml_str(&raw)
+                    } else {
+                        TabularMapping::from_named(raw.trim())
+                    };
+                    match parsed {
+                        Ok(mapping) => options.mapping = Some(mapping),
+                        Err(e) => { let _ = tx_inner.send(format!("⚠️ Mapeo de columnas inválido ({}). Las tablas pasarán por el LLM.", e)).await; }
+                    }
+                }
+            } else if name == "language" {
+                // Idioma de las grabaciones de audio (ISO-639-1); vacío o "auto" para detectarlo
+                if let Ok(raw) = field.text().await {
+                    options.language = Some(raw.trim().to_string()).filter(|l| !l.is_empty());
+                }
+            } else if name == "preview" {
+                // Vista previa: se extrae sin escribir en el grafo hasta que se confirme
+                if let Ok(raw) = field.text().await {
+                    options.preview = matches!(raw.trim().to_lowercase().as_str(), "true" | "1" | "on");
+                }
+            } else if name == "replace" {
+                // Nueva versión del documento con el mismo nombre subido por el mismo usuario
+                if let Ok(raw) = field.text().await {
+                    options.replace = matches!(raw.trim().to_lowercase().as_str(), "true" | "1" | "on");
                 }
-
             } else if name == "content" {
                  if let Ok(text) = field.text().await {
-                    if !text.trim().is_empty() {
-                        final_content.push_str(&text);
-                        content_found = true;
-                    }
+                    pasted_text.push_str(&text);
                  }
             }
         }
 
-        if content_found && final_content.len() > 5 {
-            let _ = tx_inner.send("🧠 Ingestando en Grafo...".to_string()).await;
-            let service = IngestionService::new(state.repo.clone(), state.ai_service.clone());
-            if let Err(e) = service.ingest_with_progress(final_content, tx_inner.clone()).await {
-                 let _ = tx_inner.send(format!("❌ Error GraphRAG: {}", e)).await;
-            } else {
-                 let _ = tx_inner.send("DONE".to_string()).await;
+        // Cada archivo (o entrada de un ZIP) es un documento propio, con su huella, páginas y chunking
+        let mut queued = 0usize;
+        let total_files = uploads.len();
+        for (index, (filename, bytes)) in uploads.into_iter().enumerate() {
+            let _ = tx_inner.send(format!("📂 [{}/{}] Archivo: {}", index + 1, total_files, filename)).await;
+            queued += ingest_upload(&state, &options, &filename, bytes.to_vec(), &tx_inner).await;
+        }
+
+        if pasted_text.trim().len() > 5 {
+            let origin = DocumentOrigin {
+                // El texto pegado no tiene nombre estable: uno único evita tratarlo como nueva versión de otro
+                filename: format!("texto_directo_{}.txt", chrono::Utc::now().format("%Y%m%d_%H%M%S")),
+                mime_type: "text/plain".to_string(),
+                uploaded_by: claims.sub.clone(),
+                content_hash: format!("{:x}", Sha256::digest(pasted_text.as_bytes())),
+                replace: false,
+            };
+            let payload = IngestionPayload { content: pasted_text, metadata: options.metadata.clone(), ..Default::default() };
+            if enqueue_document(&state, &options, origin, payload, options.chunking.clone(), &tx_inner).await {
+                queued += 1;
             }
-        } else {
+        }
+
+        if queued == 0 {
             let _ = tx_inner.send("❌ Sin contenido válido.".to_string()).await;
+        } else if options.preview {
+            let _ = tx_inner.send(format!("🔍 {} vista(s) previa(s) pendientes de revisión.", queued)).await;
+        } else {
+            let _ = tx_inner.send(format!("🧠 {} documento(s) en cola para el grafo.", queued)).await;
         }
     });
 
     Body::from_stream(ReceiverStream::new(rx).map(|msg| Ok::<_, std::io::Error>(Bytes::from(format!("{}\n", msg)))))
+}
+
+/// Ingiere un archivo subido; un ZIP se expande y cada entrada es un documento propio.
+/// Devuelve cuántos trabajos se han encolado.
+pub(crate) async fn ingest_upload(
+    state: &AppState,
+    options: &UploadOptions,
+    filename: &str,
+    bytes: Vec<u8>,
+    tx: &mpsc::Sender<String>,
+) -> usize {
+    if !is_zip(filename) {
+        return ingest_file(state, options, filename, bytes, tx).await;
+    }
+
+    let _ = tx.send("🗜️ ZIP detectado. Expandiendo...".to_string()).await;
+    let entries = task::spawn_blocking(move || archive::expand_zip(&bytes))
+        .await
+        .map_err(|e| format!("Error Thread: {}", e))
+        .and_then(|res| res.map_err(|e| format!("Error Formato: {}", e)));
+    let entries = match entries {
+        Ok(entries) => entries,
+        Err(e) => {
+            let _ = tx.send(format!("❌ {}", e)).await;
+            return 0;
+        },
+    };
+
+    let total_entries = entries.len();
+    let _ = tx.send(format!("🗜️ {} archivo(s) en {}.", total_entries, filename)).await;
+    let mut queued = 0;
+    for (entry_index, entry) in entries.into_iter().enumerate() {
+        // La ruta dentro del ZIP distingue entradas homónimas de distintas carpetas o archivos
+        let entry_name = format!("{}/{}", filename, entry.path);
+        let _ = tx.send(format!("📂 [{}/{}] {}", entry_index + 1, total_entries, entry_name)).await;
+        if is_zip(&entry.path) {
+            let _ = tx.send("⏭️ ZIP anidado. Se omite.".to_string()).await;
+            continue;
+        }
+        if !is_supported(&entry.path) {
+            let _ = tx.send("⏭️ Formato no soportado. Se omite.".to_string()).await;
+            continue;
+        }
+        queued += ingest_file(state, options, &entry_name, entry.bytes, tx).await;
+    }
+    queued
+}
+
+fn is_zip(filename: &str) -> bool {
+    filename.to_lowercase().ends_with(".zip")
+}
+
+/// Formatos que la ingesta sabe procesar: documentos del transmutador, imágenes y audio
+pub(crate) fn is_supported(filename: &str) -> bool {
+    let mime_type = from_path(filename).first_or_octet_stream();
+    SupportedFormat::from_filename(filename).is_some()
+        || mime_type.type_() == mime_guess::mime::IMAGE
+        || is_transcribable(&mime_type)
+}
+
+/// Audio o vídeo: se transcribe su pista de sonido
+fn is_transcribable(mime_type: &mime_guess::Mime) -> bool {
+    mime_type.type_() == mime_guess::mime::AUDIO || mime_type.type_() == mime_guess::mime::VIDEO
+}
+
+/// Opciones del formulario que se aplican a todos los archivos de la subida
+pub(crate) struct UploadOptions {
+    pub(crate) uploaded_by: String,
+    pub(crate) chunking: Option<ChunkingConfig>,
+    pub(crate) mapping: Option<TabularMapping>,
+    pub(crate) metadata: DocumentMetadata,
+    /// Idioma de las transcripciones; sin valor se usa el de la configuración de IA
+    pub(crate) language: Option<String>,
+    /// Solo proponer la extracción (trabajo `Preview`) en lugar de encolar la ingesta
+    pub(crate) preview: bool,
+    /// Sustituir la versión anterior (mismo nombre y usuario) en lugar de crear otro documento
+    pub(crate) replace: bool,
+}
+
+/// Convierte un archivo en uno o varios documentos (varios si es un buzón de correo) y los encola.
+/// Devuelve cuántos trabajos se han encolado.
+async fn ingest_file(
+    state: &AppState,
+    options: &UploadOptions,
+    filename: &str,
+    bytes: Vec<u8>,
+    tx: &mpsc::Sender<String>,
+) -> usize {
+    match SupportedFormat::from_filename(filename) {
+        Some(SupportedFormat::EML | SupportedFormat::MBOX) => {
+            return ingest_mail(state, options, filename, bytes, tx).await;
+        },
+        Some(SupportedFormat::CSV | SupportedFormat::XLSX | SupportedFormat::XLS | SupportedFormat::ODS) => {
+            if let Some(mapping) = &options.mapping {
+                return ingest_mapped_table(state, options, mapping, filename, bytes, tx).await;
+            }
+        },
+        _ => {},
+    }
+
+    // Huella de los bytes originales: permite saltar archivos idénticos antes de gastar en IA
+    let content_hash = format!("{:x}", Sha256::digest(&bytes));
+    let mime = from_path(filename).first_or_octet_stream();
+    let mime_type = mime.to_string();
+    let transcribed = is_transcribable(&mime);
+
+    let processing_result: Result<ExtractedDocument, String> = if mime_type.starts_with("image/") {
+        // 1. VISIÓN
+        let _ = tx.send("👁️ Imagen detectada. Normalizando...".to_string()).await;
+        // Se decodifica y re-codifica: el modelo nunca recibe el EXIF original (GPS de las fotos del móvil)
+        let preparation = state.image_preparation;
+        let prepared = task::spawn_blocking(move || preparation.prepare(&bytes))
+            .await
+            .map_err(|e| format!("Error Thread: {}", e))
+            .and_then(|res| res.map_err(|e| format!("Error Imagen: {}", e)));
+        match prepared {
+            Ok(image) => {
+                let _ = tx.send(format!(
+                    "🖼️ {}x{} → {}x{}, metadatos eliminados. Analizando...",
+                    image.original.0, image.original.1, image.width, image.height
+                )).await;
+                state.ai_service.read().await
+                    .describe_image(&image.bytes, image.mime_type).await
+                    .map(|d| ExtractedDocument::plain(format!("--- [IMG: {}] ---\n{}\n---", filename, d)))
+                    .map_err(|e| format!("Error Visión: {}", e))
+            },
+            Err(e) => Err(e),
+        }
+    } else if transcribed {
+        // 2. AUDIO (o pista de sonido de un vídeo)
+        let _ = tx.send("👂 Audio detectado. Transcribiendo...".to_string()).await;
+        let transcription = state.ai_service.read().await
+            .transcribe_audio(&bytes, filename, options.language.as_deref()).await
+            .map_err(|e| format!("Error Audio: {}", e));
+        if let Ok(t) = &transcription {
+            let language = t.language.as_deref().unwrap_or("desconocido");
+            let _ = tx.send(format!("👂 {} segmento(s) transcritos (idioma: {}).", t.segments.len(), language)).await;
+        }
+        transcription.map(|t| ExtractedDocument::from_transcript(&format!("--- [AUDIO: {}] ---", filename), t))
+    } else {
+        // 3. DOCUMENTOS (Usando el transmutador unificado)
+        let _ = tx.send("📄 Extrayendo texto...".to_string()).await;
+        let fname = filename.to_string();
+        
+        let extracted = task::spawn_blocking(move || {
+            DocumentTransmuter::transmute(&fname, &bytes)
+        }).await
+        .map_err(|e| format!("Error Thread: {}", e))
+        .and_then(|res| res.map_err(|e| format!("Error Formato: {}", e)));
+
+        // 4. OCR de las páginas escaneadas (PDF sin capa de texto)
+        match extracted {
+            Ok(doc) => doc.complete_with_ocr(state.ocr.as_ref(), tx).await
+                .map_err(|e| format!("Error OCR: {}", e)),
+            Err(e) => Err(e),
+        }
+    };
+
+    let extracted = match processing_result {
+        Ok(extracted) if extracted.text.trim().len() > 5 => extracted,
+        Ok(_) => {
+            let _ = tx.send(format!("❌ Sin contenido válido en {}.", filename)).await;
+            return 0;
+        },
+        Err(e) => {
+            let _ = tx.send(format!("❌ {}", e)).await;
+            return 0;
+        },
+    };
+    let _ = tx.send("✅ Contenido extraído.".to_string()).await;
+
+    let origin = DocumentOrigin {
+        filename: filename.to_string(),
+        mime_type,
+        uploaded_by: options.uploaded_by.clone(),
+        content_hash,
+        replace: options.replace,
+    };
+    let payload = IngestionPayload {
+        content: extracted.text,
+        pages: extracted.pages,
+        segments: extracted.segments,
+        metadata: options.metadata.clone(),
+        ..Default::default()
+    };
+    // Las transcripciones se trocean por segmentos, sea cual sea la extensión o el override
+    let chunking = if transcribed {
+        let chunking = state.ingestion_jobs.chunking_for_transcript(options.chunking.as_ref());
+        if options.chunking.as_ref().is_some_and(|requested| *requested != chunking) {
+            let _ = tx.send("⚠️ Las transcripciones se trocean por segmentos: se ignora el chunking pedido.".to_string()).await;
+        }
+        Some(chunking)
+    } else {
+        options.chunking.clone()
+    };
+    usize::from(enqueue_document(state, options, origin, payload, chunking, tx).await)
+}
+
+/// TABLA CON MAPEO: las filas se convierten en entidades y relaciones exactas, sin LLM
+async fn ingest_mapped_table(
+    state: &AppState,
+    options: &UploadOptions,
+    mapping: &TabularMapping,
+    filename: &str,
+    bytes: Vec<u8>,
+    tx: &mpsc::Sender<String>,
+) -> usize {
+    let _ = tx.send("📊 Tabla con mapeo de columnas. Convirtiendo filas sin LLM...".to_string()).await;
+    // El mismo archivo con otro mapeo produce otro grafo: el mapeo forma parte de la huella
+    let mut hasher = Sha256::new();
+    hasher.update(&bytes);
+    hasher.update(serde_json::to_vec(mapping).unwrap_or_default());
+    let content_hash = format!("{:x}", hasher.finalize());
+
+    let fname = filename.to_string();
+    let sheet = mapping.sheet.clone();
+    let rows = task::spawn_blocking(move || DocumentTransmuter::read_table(&fname, &bytes, sheet.as_deref()))
+        .await
+        .map_err(|e| format!("Error Thread: {}", e))
+        .and_then(|res| res.map_err(|e| format!("Error Formato: {}", e)));
+    let payload = match rows.and_then(|rows| mapping.apply(rows).map_err(|e| format!("Error Mapeo: {}", e))) {
+        Ok(payload) => IngestionPayload { metadata: options.metadata.clone(), ..payload },
+        Err(e) => {
+            let _ = tx.send(format!("❌ {}", e)).await;
+            return 0;
+        },
+    };
+
+    let spans = payload.structured.as_deref().unwrap_or_default();
+    let entities: usize = spans.iter().map(|s| s.extraction.entities.len()).sum();
+    let relations: usize = spans.iter().map(|s| s.extraction.relations.len()).sum();
+    let _ = tx.send(format!("📊 {} filas → {} entidades, {} relaciones.", spans.len(), entities, relations)).await;
+
+    let origin = DocumentOrigin {
+        filename: filename.to_string(),
+        mime_type: from_path(filename).first_or_octet_stream().to_string(),
+        uploaded_by: options.uploaded_by.clone(),
+        content_hash,
+        replace: options.replace,
+    };
+    usize::from(enqueue_document(state, options, origin, payload, options.chunking.clone(), tx).await)
+}
+
+/// CORREO: cada mensaje es un documento con sus participantes como contexto
+async fn ingest_mail(
+    state: &AppState,
+    options: &UploadOptions,
+    filename: &str,
+    bytes: Vec<u8>,
+    tx: &mpsc::Sender<String>,
+) -> usize {
+    let _ = tx.send("✉️ Correo detectado. Separando mensajes...".to_string()).await;
+    let fname = filename.to_string();
+    let parsed = task::spawn_blocking(move || email::parse_mail_file(&fname, &bytes))
+        .await
+        .map_err(|e| format!("Error Thread: {}", e))
+        .and_then(|res| res.map_err(|e| format!("Error Formato: {}", e)));
+
+    let messages = match parsed {
+        Ok(messages) => messages,
+        Err(e) => {
+            let _ = tx.send(format!("❌ {}", e)).await;
+            return 0;
+        },
+    };
+
+    let total = messages.len();
+    // El nombre de cada mensaje no conserva la extensión: se usa la del archivo subido
+    let message_chunking = options.chunking.clone().unwrap_or_else(|| state.ingestion_jobs.chunking_for(filename));
+    let mut queued = 0;
+    for (index, message) in messages.into_iter().enumerate() {
+        let subject = message.subject.clone().unwrap_or_else(|| "(sin asunto)".to_string());
+        let _ = tx.send(format!("✉️ [{}/{}] \"{}\"", index + 1, total, subject)).await;
+        let origin = DocumentOrigin {
+            filename: message.document_name(filename, index),
+            mime_type: "message/rfc822".to_string(),
+            uploaded_by: options.uploaded_by.clone(),
+            content_hash: format!("{:x}", Sha256::digest(&message.raw)),
+            replace: options.replace,
+        };
+        let payload = IngestionPayload {
+            context: Some(message.header_block()),
+            content: message.text,
+            metadata: options.metadata.clone(),
+            ..Default::default()
+        };
+        if enqueue_document(state, options, origin, payload, Some(message_chunking.clone()), tx).await {
+            queued += 1;
+        }
+    }
+    queued
+}
+
+/// Encola un documento informando en el stream. El trabajo sobrevive aunque el navegador
+/// se desconecte; el stream solo refleja su avance. En modo vista previa la extracción se
+/// hace aquí mismo y el trabajo queda a la espera de revisión.
+async fn enqueue_document(
+    state: &AppState,
+    options: &UploadOptions,
+    origin: DocumentOrigin,
+    payload: IngestionPayload,
+    chunking: Option<ChunkingConfig>,
+    tx: &mpsc::Sender<String>,
+) -> bool {
+    if options.preview {
+        return match state.ingestion_jobs.preview(origin, payload, chunking, tx).await {
+            Ok(job) => {
+                let _ = tx.send(format!("🔍 Vista previa {} lista ({}). Revísala en /api/ingest/preview/{}.", job.id, job.filename, job.id)).await;
+                true
+            },
+            Err(e) => {
+                let _ = tx.send(format!("❌ Error GraphRAG: {}", e)).await;
+                false
+            },
+        };
+    }
+    match state.ingestion_jobs.enqueue(origin, payload, chunking, Some(tx.clone())).await {
+        Ok(job) => {
+            let _ = tx.send(format!("🆔 Trabajo {} en cola ({}).", job.id, job.filename)).await;
+            true
+        },
+        Err(e) => {
+            let _ = tx.send(format!("❌ Error GraphRAG: {}", e)).await;
+            false
+        },
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/ingest/url",
+    request_body = UrlIngestionRequest,
+    responses(
+        (status = 200, description = "Stream de progreso; cada página se encola como documento cuyo nombre es su URL"),
+        (status = 400, description = "URL o metadatos inválidos")
+    )
+)]
+pub async fn ingest_url(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<UrlIngestionRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let source = state.web_ingestion.prepare(&request, &claims.sub)?;
+    let (tx, rx) = mpsc::channel::<String>(20);
+
+    tokio::spawn(async move {
+        match state.web_ingestion.ingest(source, &tx).await {
+            Ok(0) => { let _ = tx.send("⏭️ Ninguna página nueva o cambiada.".to_string()).await; },
+            Ok(queued) => { let _ = tx.send(format!("🧠 {} documento(s) en cola para el grafo.", queued)).await; },
+            Err(e) => { let _ = tx.send(format!("❌ {}", e)).await; },
+        }
+    });
+
+    Ok(Body::from_stream(ReceiverStream::new(rx).map(|msg| Ok::<_, std::io::Error>(Bytes::from(format!("{}\n", msg))))))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/ingest/sources",
+    responses(
+        (status = 200, description = "Fuentes web con re-descarga programada", body = Vec<WebSource>)
+    )
+)]
+pub async fn list_web_sources(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<WebSource>>, AppError> {
+    let sources = state.repo.list_web_sources().await?;
+    Ok(Json(sources))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/ingest/sources/{id}",
+    params(
+        ("id" = String, Path, description = "Web source ID")
+    ),
+    responses(
+        (status = 200, description = "Re-descarga cancelada; los documentos ya ingeridos se conservan"),
+        (status = 404, description = "Fuente no encontrada")
+    )
+)]
+pub async fn delete_web_source(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    if !state.repo.delete_web_source(&id).await? {
+        return Err(AppError::NotFound(format!("Fuente no encontrada: {}", id)));
+    }
+    Ok((StatusCode::OK, Json("Source removed")))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/ingest/preview/{id}",
+    params(
+        ("id" = String, Path, description = "Ingestion job ID (estado Preview)")
+    ),
+    responses(
+        (status = 200, description = "Fragmentos con las entidades y relaciones que se escribirían", body = IngestionPreview),
+        (status = 400, description = "Vista previa ya confirmada"),
+        (status = 404, description = "Vista previa no encontrada")
+    )
+)]
+pub async fn get_ingestion_preview(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<IngestionPreview>, AppError> {
+    state.ingestion_jobs.get_preview(&id).await?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Vista previa no encontrada: {}", id)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/ingest/preview/{id}/commit",
+    params(
+        ("id" = String, Path, description = "Ingestion job ID (estado Preview)")
+    ),
+    request_body = PreviewCommitRequest,
+    responses(
+        (status = 200, description = "Trabajo en cola con las extracciones revisadas; el avance se consulta en /api/ingest/jobs/{id}", body = IngestionJob),
+        (status = 400, description = "Vista previa ya confirmada o con extracciones inválidas"),
+        (status = 404, description = "Vista previa no encontrada")
+    )
+)]
+pub async fn commit_ingestion_preview(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<PreviewCommitRequest>,
+) -> Result<Json<IngestionJob>, AppError> {
+    state.ingestion_jobs.commit_preview(&id, request.chunks).await?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Vista previa no encontrada: {}", id)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/ingest/jobs",
+    responses(
+        (status = 200, description = "Trabajos de ingesta recientes", body = Vec<IngestionJob>)
+    )
+)]
+pub async fn list_ingestion_jobs(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<IngestionJob>>, AppError> {
+    let jobs = state.repo.list_ingestion_jobs(50).await?;
+    Ok(Json(jobs))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/ingest/jobs/{id}",
+    params(
+        ("id" = String, Path, description = "Ingestion job ID")
+    ),
+    responses(
+        (status = 200, description = "Estado y progreso del trabajo", body = IngestionJob),
+        (status = 404, description = "Trabajo no encontrado")
+    )
+)]
+pub async fn get_ingestion_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<IngestionJob>, AppError> {
+    state.repo.get_ingestion_job(&id).await?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Trabajo no encontrado: {}", id)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/ingest/jobs/{id}",
+    params(
+        ("id" = String, Path, description = "Ingestion job ID")
+    ),
+    responses(
+        (status = 200, description = "Trabajo cancelado (si estaba activo) o eliminado (si ya había terminado)"),
+        (status = 404, description = "Trabajo no encontrado")
+    )
+)]
+pub async fn cancel_ingestion_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let job = state.ingestion_jobs.cancel_or_remove(&id).await?
+        .ok_or_else(|| AppError::NotFound(format!("Trabajo no encontrado: {}", id)))?;
+
+    let message = if job.status.is_active() { "Job cancelled" } else { "Job removed" };
+    Ok((StatusCode::OK, Json(message)))
 }
\ No newline at end of file
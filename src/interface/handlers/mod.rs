@@ -6,4 +6,6 @@ pub mod chat;
 pub mod reasoning;
 pub mod export;
 pub mod users; // <--- NUEVO
-pub mod agents; // <--- ESTA LÍNEA ES LA QUE FALTA
\ No newline at end of file
+pub mod agents; // <--- ESTA LÍNEA ES LA QUE FALTA
+pub mod documents;
+pub mod curation;
\ No newline at end of file
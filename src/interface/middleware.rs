@@ -24,13 +24,7 @@ pub async fn auth_middleware(
     let token_opt = cookie_header
         .split(';')
         .map(|s| s.trim())
-        .find_map(|part| {
-            if let Some(rest) = part.strip_prefix("lamuralla_jwt=") {
-                Some(rest.to_string())
-            } else {
-                None
-            }
-        });
+        .find_map(|part| part.strip_prefix("lamuralla_jwt=").map(|rest| rest.to_string()));
 
     let token = match token_opt {
         Some(t) => t,
@@ -1,3 +1,4 @@
 pub mod handlers;
+pub mod folder_watch;
 pub mod middleware; // <--- DESCOMENTADO
 // pub mod api;
@@ -26,8 +26,17 @@ use bcrypt::{hash, DEFAULT_COST};
 
 use crate::domain::models::*;
 use crate::domain::ports::KGRepository;
+use crate::application::chunking::ChunkingPolicy;
+use crate::application::ontology::Ontology;
+use crate::application::entity_resolution::ResolutionConfig;
+use crate::application::ingestion::IngestionTuning;
+use crate::application::ingestion_jobs::IngestionJobQueue;
+use crate::application::web_ingestion::WebIngestionService;
 use crate::infrastructure::ai::rig_client::RigAIService;
+use crate::infrastructure::ai::vision_ocr::VisionOcrEngine;
 use crate::infrastructure::persistence::neo4j_repo::Neo4jRepo;
+use crate::infrastructure::web::{WebFetcher, WebFetchLimits};
+use crate::infrastructure::transmutation::images::ImagePreparation;
 use crate::interface::handlers::{
     admin::{self, AppState},
     ingest,
@@ -37,19 +46,37 @@ use crate::interface::handlers::{
     reasoning,
     export,
     users, // <--- IMPORTADO
+    documents,
+    curation,
 };
 use crate::interface::handlers::agents;
+use crate::interface::folder_watch::{self, FolderWatchConfig};
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         interface::handlers::admin::update_config,
         interface::handlers::ingest::ingest_document,
+        interface::handlers::ingest::list_ingestion_jobs,
+        interface::handlers::ingest::get_ingestion_job,
+        interface::handlers::ingest::cancel_ingestion_job,
+        interface::handlers::ingest::get_ingestion_preview,
+        interface::handlers::ingest::commit_ingestion_preview,
+        interface::handlers::ingest::ingest_url,
+        interface::handlers::ingest::list_web_sources,
+        interface::handlers::ingest::delete_web_source,
         interface::handlers::graph::get_graph,
         interface::handlers::graph::get_concept_neighborhood,
         interface::handlers::chat::chat_handler,
         interface::handlers::reasoning::run_reasoning,
-        interface::handlers::export::export_knowledge_graph
+        interface::handlers::export::export_knowledge_graph,
+        interface::handlers::documents::list_documents,
+        interface::handlers::documents::get_document,
+        interface::handlers::documents::delete_document,
+        interface::handlers::curation::list_pending,
+        interface::handlers::curation::review_entity,
+        interface::handlers::curation::review_relation,
+        interface::handlers::curation::merge_entities
     ),
     components(schemas(
         AIConfig,
@@ -63,6 +90,34 @@ use crate::interface::handlers::agents;
         InferredRelation,
         ExportParams,
         ExportFormat,
+        SourceDocument,
+        DocumentChunkSummary,
+        DocumentDetail,
+        IngestionJob,
+        JobStatus,
+        ChunkingConfig,
+        MetadataFilter,
+        MetadataRange,
+        UrlIngestionRequest,
+        WebSource,
+        ChunkLocation,
+        KnowledgeExtraction,
+        GraphEntity,
+        GraphRelation,
+        ChunkPreview,
+        IngestionPreview,
+        PreviewCommitRequest,
+        ChunkExtractionEdit,
+        ReviewStatus,
+        PendingEntity,
+        PendingRelation,
+        CurationQueue,
+        CurationAction,
+        CurationDecision,
+        EntityAlias,
+        EntityMergeRequest,
+        RelationProvenance,
+        AttributeValue,
         // (Opcional) Agrega CreateUserRequest y UserDto aquí si quieres documentarlos
     )),
     tags((name = "lamuralla", description = "Mental Health API"))
@@ -88,6 +143,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .unwrap_or("1536".to_string())
         .parse::<usize>()?;
     let base_url = std::env::var("AI_BASE_URL").ok();
+    // Sin idioma (o "auto"), Whisper detecta el de cada grabación
+    let transcription_language = std::env::var("AI_TRANSCRIPTION_LANGUAGE").ok()
+        .filter(|l| !l.trim().is_empty() && l.trim() != "auto");
 
     let provider = match provider_str.to_lowercase().as_str() {
         "ollama" => AIProvider::Ollama,
@@ -99,9 +157,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         provider,
         model_name,
         embedding_model,
-        api_key: SecretString::new(api_key_str.into()),
+        api_key: SecretString::new(api_key_str),
         embedding_dim,
         base_url,
+        transcription_language,
     };
 
     // 2. Neo4j
@@ -110,9 +169,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let pass = std::env::var("NEO4J_PASS").expect("NEO4J_PASS required");
 
     let graph = Arc::new(Graph::new(&uri, &user, &pass).await?);
-    let repo = Arc::new(Neo4jRepo::new(graph.clone()));
+    // Revisión humana obligatoria de lo que extrae el LLM antes de que sea visible
+    let review_required = std::env::var("CURATION_REQUIRED").map(|v| v == "true").unwrap_or(false);
+    let repo = Arc::new(Neo4jRepo::new(graph.clone()).with_review(review_required));
 
     let _ = repo.create_indexes(embedding_dim).await;
+    // Entidades anteriores a la resolución: sin clave no se encontrarían por nombre
+    match repo.backfill_entity_keys().await {
+        Ok(0) => {},
+        Ok(n) => tracing::info!("🔑 Clave de resolución calculada para {} entidades existentes.", n),
+        Err(e) => tracing::warn!("⚠️ No se pudieron calcular las claves de entidad: {}", e),
+    }
 
     // 3. Usuario admin (Verifica/Crea el admin del .env)
     let admin_user = std::env::var("ADMIN_USER").unwrap_or("admin".to_string());
@@ -124,12 +191,56 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let ai_service = Arc::new(RwLock::new(RigAIService::new(initial_config)));
     let tera = Tera::new("templates/**/*.html")?;
 
+    // Cola de ingesta persistente: retoma los trabajos interrumpidos por un reinicio
+    let chunking_policy = ChunkingPolicy::from_yaml_file("./config/chunking.yaml").unwrap_or_else(|e| {
+        tracing::warn!("⚠️ {}. Usando chunking por defecto.", e);
+        ChunkingPolicy::default()
+    });
+    // Ontología de la extracción: genera el prompt y valida lo que devuelve el LLM
+    let ontology = Arc::new(Ontology::from_yaml_file("./config/ontology.yaml").unwrap_or_else(|e| {
+        tracing::warn!("⚠️ {}. Usando la ontología por defecto.", e);
+        Ontology::default()
+    }));
+    tracing::info!("🧭 Ontología: {} categorías, {} tipos de relación.", ontology.categories.len(), ontology.relations.len());
+    // Etiqueta por categoría (`:Person`...) también en las entidades anteriores a ellas
+    let categories: Vec<String> = ontology.categories.iter().map(|c| c.name.clone()).collect();
+    match repo.migrate_category_labels(&categories).await {
+        Ok(0) => {},
+        Ok(n) => tracing::info!("🏷️ Etiqueta de categoría añadida a {} entidades existentes.", n),
+        Err(e) => tracing::warn!("⚠️ No se pudieron etiquetar las entidades por categoría: {}", e),
+    }
+    let tuning = IngestionTuning::from_env();
+    tracing::info!("⚙️ Ingesta: lotes de {} embeddings, {} extracciones simultáneas.", tuning.embedding_batch_size, tuning.max_concurrent_extractions);
+    let resolution = ResolutionConfig::from_env();
+    tracing::info!("🔗 Resolución de entidades: similitud ≥ {}, escritura ≥ {}.", resolution.similarity_threshold, resolution.fuzzy_threshold);
+    let ingestion_jobs = Arc::new(IngestionJobQueue::start(repo.clone(), ai_service.clone(), chunking_policy, tuning, ontology, resolution));
+    match ingestion_jobs.resume_unfinished().await {
+        Ok(0) => {},
+        Ok(n) => tracing::info!("⏯️ {} trabajos de ingesta reanudados.", n),
+        Err(e) => tracing::warn!("⚠️ No se pudieron reanudar trabajos de ingesta: {}", e),
+    }
+
+    // Ingesta web: las fuentes programadas se re-descargan en segundo plano
+    let web_limits = WebFetchLimits::from_env();
+    tracing::info!("🌐 Ingesta web como '{}': páginas de hasta {} bytes, {} por sitemap.", web_limits.user_agent, web_limits.max_page_bytes, web_limits.max_pages);
+    let web_ingestion = Arc::new(WebIngestionService::new(WebFetcher::new(web_limits), repo.clone(), ingestion_jobs.clone()));
+    web_ingestion.clone().spawn_refresh_scheduler();
+
     let app_state = AppState {
         repo: repo.clone(),
-        ai_service,
+        ai_service: ai_service.clone(),
         tera: Arc::new(tera),
+        ingestion_jobs,
+        ocr: Arc::new(VisionOcrEngine::new(ai_service.clone())),
+        web_ingestion,
+        image_preparation: ImagePreparation::from_env(),
     };
 
+    // Carpeta vigilada (opcional): ingesta de lo que el equipo deja en WATCH_DIR
+    if let Some(watch_config) = FolderWatchConfig::from_env() {
+        folder_watch::spawn(app_state.clone(), watch_config);
+    }
+
     // 5. Rutas públicas
     let public_routes = Router::new()
         .route("/", get(ui::render_login).post(ui::authenticate))
@@ -149,6 +260,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/api/reasoning/run", post(reasoning::run_reasoning))
         .route("/api/agents", get(agents::list_agents))
         .route("/api/agents/chat", post(agents::chat_agent))
+        .route("/api/documents", get(documents::list_documents))
+        .route("/api/documents/:id", get(documents::get_document))
         .route_layer(middleware::from_fn_with_state(
             app_state.clone(),
             crate::interface::middleware::auth_middleware,
@@ -159,10 +272,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let admin_routes = Router::new()
         .route("/api/admin/config", post(admin::update_config))
         .route("/api/ingest", post(ingest::ingest_document))
+        .route("/api/ingest/jobs", get(ingest::list_ingestion_jobs))
+        .route("/api/ingest/jobs/:id", get(ingest::get_ingestion_job).delete(ingest::cancel_ingestion_job))
+        .route("/api/ingest/preview/:id", get(ingest::get_ingestion_preview))
+        .route("/api/ingest/preview/:id/commit", post(ingest::commit_ingestion_preview))
+        .route("/api/ingest/url", post(ingest::ingest_url))
+        .route("/api/ingest/sources", get(ingest::list_web_sources))
+        .route("/api/ingest/sources/:id", axum::routing::delete(ingest::delete_web_source))
         // NUEVAS RUTAS 👇
         .route("/api/admin/users", get(users::list_users).post(users::create_user))
         .route("/api/admin/users/:username", axum::routing::delete(users::delete_user))
         .route("/api/tools", get(agents::list_tools)) // <--- NUEVA RUTA
+        .route("/api/documents/:id", axum::routing::delete(documents::delete_document))
+        .route("/api/curation", get(curation::list_pending))
+        .route("/api/curation/entities/:id", post(curation::review_entity))
+        .route("/api/curation/relations/:id", post(curation::review_relation))
+        .route("/api/curation/merge", post(curation::merge_entities))
         .route_layer(middleware::from_fn_with_state(
             app_state.clone(),
             crate::interface::middleware::require_admin,